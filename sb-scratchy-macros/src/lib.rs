@@ -0,0 +1,270 @@
+//! Procedural macros for sb-scratchy.
+//!
+//! Use them from `sb_scratchy` instead of depending on this crate directly.
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{quote, quote_spanned};
+use syn::{
+    braced,
+    parse::{discouraged::Speculative, Parse, ParseStream},
+    parse_macro_input,
+    spanned::Spanned,
+    token, Expr, Ident, Token,
+};
+
+/// Build a script with Rust-like syntax.
+///
+/// See `sb_scratchy::scratch` for the syntax.
+#[proc_macro]
+pub fn scratch(input: TokenStream) -> TokenStream {
+    let script = parse_macro_input!(input as Script);
+    match expand_stack(&script.stmts, false, Span::call_site()) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+struct Script {
+    stmts: Vec<Stmt>,
+}
+
+impl Parse for Script {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        Ok(Script {
+            stmts: parse_stmts(input)?,
+        })
+    }
+}
+
+/// Statements in a brace, e.g. body of a loop
+struct Body {
+    span: Span,
+    stmts: Vec<Stmt>,
+}
+
+impl Parse for Body {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let content;
+        let brace = braced!(content in input);
+        Ok(Body {
+            span: brace.span.join(),
+            stmts: parse_stmts(&content)?,
+        })
+    }
+}
+
+enum Stmt {
+    /// `loop { .. }`
+    Loop { keyword: Span, body: Body },
+    /// `repeat <times> { .. }`
+    Repeat {
+        keyword: Span,
+        times: Expr,
+        body: Body,
+    },
+    /// `until <cond> { .. }`
+    Until {
+        keyword: Span,
+        cond: Expr,
+        body: Body,
+    },
+    /// `if <cond> { .. } else if <cond> { .. } else { .. }`
+    If {
+        keyword: Span,
+        branches: Vec<(Expr, Body)>,
+        else_: Option<Body>,
+    },
+    /// Any expression that is a block or a stack
+    Block(Expr),
+}
+
+fn parse_stmts(input: ParseStream) -> syn::Result<Vec<Stmt>> {
+    let mut stmts = vec![];
+    while !input.is_empty() {
+        let stmt: Stmt = input.parse()?;
+        let ends_with_brace = !matches!(stmt, Stmt::Block(_));
+        stmts.push(stmt);
+        if input.is_empty() {
+            break;
+        }
+        if ends_with_brace {
+            let _ = input.parse::<Option<Token![;]>>()?;
+        } else {
+            input.parse::<Token![;]>()?;
+        }
+    }
+    Ok(stmts)
+}
+
+impl Parse for Stmt {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if input.peek(Token![loop]) {
+            let keyword = input.parse::<Token![loop]>()?.span;
+            return Ok(Stmt::Loop {
+                keyword,
+                body: input.parse()?,
+            });
+        }
+        if input.peek(Token![if]) {
+            return parse_if(input);
+        }
+        if input.peek(Ident) {
+            // `repeat` and `until` are not keywords, so `repeat(10, None)` is still a function call
+            let fork = input.fork();
+            if let Ok(stmt) = parse_keyword_loop(&fork) {
+                input.advance_to(&fork);
+                return Ok(stmt);
+            }
+        }
+        Ok(Stmt::Block(input.parse()?))
+    }
+}
+
+fn parse_keyword_loop(input: ParseStream) -> syn::Result<Stmt> {
+    let keyword: Ident = input.parse()?;
+    let expr = Expr::parse_without_eager_brace(input)?;
+    if !input.peek(token::Brace) {
+        return Err(input.error("expected `{`"));
+    }
+    let body = input.parse()?;
+    match keyword.to_string().as_str() {
+        "repeat" => Ok(Stmt::Repeat {
+            keyword: keyword.span(),
+            times: expr,
+            body,
+        }),
+        "until" => Ok(Stmt::Until {
+            keyword: keyword.span(),
+            cond: expr,
+            body,
+        }),
+        _ => Err(syn::Error::new(
+            keyword.span(),
+            "expected `repeat` or `until`",
+        )),
+    }
+}
+
+fn parse_if(input: ParseStream) -> syn::Result<Stmt> {
+    let keyword = input.parse::<Token![if]>()?.span;
+    let mut branches = vec![(Expr::parse_without_eager_brace(input)?, input.parse()?)];
+    let mut else_ = None;
+    while input.peek(Token![else]) {
+        input.parse::<Token![else]>()?;
+        if input.peek(Token![if]) {
+            input.parse::<Token![if]>()?;
+            branches.push((Expr::parse_without_eager_brace(input)?, input.parse()?));
+        } else {
+            else_ = Some(input.parse()?);
+            break;
+        }
+    }
+    Ok(Stmt::If {
+        keyword,
+        branches,
+        else_,
+    })
+}
+
+impl Stmt {
+    fn span(&self) -> Span {
+        match self {
+            Stmt::Loop { keyword, .. }
+            | Stmt::Repeat { keyword, .. }
+            | Stmt::Until { keyword, .. }
+            | Stmt::If { keyword, .. } => *keyword,
+            Stmt::Block(expr) => expr.span(),
+        }
+    }
+
+    fn expand(&self) -> syn::Result<TokenStream2> {
+        Ok(match self {
+            Stmt::Loop { keyword, body } => {
+                let body = expand_substack(body)?;
+                quote_spanned!(*keyword=> ::sb_scratchy::blocks::forever(#body))
+            }
+            Stmt::Repeat {
+                keyword,
+                times,
+                body,
+            } => {
+                let body = expand_substack(body)?;
+                quote_spanned!(*keyword=> ::sb_scratchy::blocks::repeat(#times, #body))
+            }
+            Stmt::Until {
+                keyword,
+                cond,
+                body,
+            } => {
+                let body = expand_substack(body)?;
+                quote_spanned!(*keyword=> ::sb_scratchy::blocks::repeat_until(#cond, #body))
+            }
+            Stmt::If {
+                keyword,
+                branches,
+                else_,
+            } => {
+                let mut branches = branches.iter();
+                let (cond, body) = branches.next().expect("if always has a branch");
+                let body = expand_stack(&body.stmts, true, body.span)?;
+                let mut chain = quote_spanned!(*keyword=> ::sb_scratchy::scripting::if_else_chain::if_(#cond, #body));
+                for (cond, body) in branches {
+                    let body = expand_stack(&body.stmts, true, body.span)?;
+                    chain.extend(quote!(.else_if(#cond, #body)));
+                }
+                if let Some(else_) = else_ {
+                    let body = expand_stack(&else_.stmts, true, else_.span)?;
+                    chain.extend(quote!(.else_(#body)));
+                }
+                quote_spanned!(*keyword=> #chain.end())
+            }
+            Stmt::Block(Expr::Path(path)) if path.qself.is_none() => {
+                quote_spanned!(path.span()=> #path())
+            }
+            // parentheses are only there so the path isn't called
+            Stmt::Block(Expr::Paren(paren)) => {
+                let expr = &paren.expr;
+                quote!(#expr)
+            }
+            Stmt::Block(expr) => quote!(#expr),
+        })
+    }
+}
+
+/// Body of loops can be empty
+fn expand_substack(body: &Body) -> syn::Result<TokenStream2> {
+    if body.stmts.is_empty() {
+        Ok(quote_spanned!(body.span=>
+            ::core::option::Option::None::<::sb_scratchy::scripting::stack::StackBlock>
+        ))
+    } else {
+        let stack = expand_stack(&body.stmts, true, body.span)?;
+        Ok(quote_spanned!(body.span=> ::core::option::Option::Some(#stack)))
+    }
+}
+
+/// Blocks stacked in order. Whether they can be stacked is checked by their types,
+/// so a hat block below another block or a block below a cap block is an error at that block.
+fn expand_stack(stmts: &[Stmt], in_substack: bool, span: Span) -> syn::Result<TokenStream2> {
+    if stmts.is_empty() {
+        return Err(syn::Error::new(span, "expected at least one block"));
+    }
+    let mut stack: Option<TokenStream2> = None;
+    for (idx, stmt) in stmts.iter().enumerate() {
+        let mut block = stmt.expand()?;
+        if idx + 1 < stmts.len() {
+            block = quote_spanned!(stmt.span()=> ::sb_scratchy::scripting::stack::checks::above(#block));
+        }
+        stack = Some(match stack {
+            Some(stack) => quote_spanned!(stmt.span()=>
+                #stack.next(::sb_scratchy::scripting::stack::checks::below(#block))
+            ),
+            None if in_substack => {
+                quote_spanned!(stmt.span()=> ::sb_scratchy::scripting::stack::checks::below(#block))
+            }
+            None => block,
+        });
+    }
+    Ok(stack.expect("checked non-empty above"))
+}
@@ -1,5 +1,5 @@
 //! Block definitions for official Scratch blocks.
-//! Does not included extension blocks.
+//! Extension blocks are in their own module, e.g. [`pen`].
 //!
 //! Block names that suffix with `menu` is a visual selection menu in Scratch.
 //!
@@ -8,10 +8,14 @@
 //!  - `"_mouse_"`
 //!  - `"_edge_"`
 //!  - `"_stage_"`
+//!  - `"_myself_"`
 //!
 
-use crate::scripting::{arg::*, stack::*};
-use sb_itchy::blocks;
+use crate::scripting::{arg::*, extension::menu_input, menu::*, stack::*};
+use sb_itchy::{
+    block::{BlockFieldBuilder, BlockNormalBuilder},
+    blocks,
+};
 
 // Control
 // Event
@@ -26,7 +30,7 @@ macro_rules! simple_typed_block_def {
     ( $(
         $(#[$attributes:meta])*
         $fn_name:ident( $(
-            $arg_name:ident: ($($arg_trait:tt)+)
+            $arg_name:ident: ($($arg_trait:tt)+) $(= $default:expr)?
         ),* ) -> $return_ty:ty
     )* ) => {
         $(
@@ -35,17 +39,20 @@ macro_rules! simple_typed_block_def {
                 $($arg_name: impl $($arg_trait)+),*
             ) -> $return_ty
             {
-                unsafe { TypedStackBuilder::assume_typed(
+                shaped(
                     blocks::$fn_name(
                         $(
-                            simple_typed_block_def!(@arg_thing ($($arg_trait)+) $arg_name)
+                            simple_typed_block_def!(@arg_thing ($($arg_trait)+) $arg_name $(, $default)?)
                         ),*
                     )
-                ).into() }
+                )
             }
         )*
     };
 
+    (@arg_thing (IntoInput<$t:ty>) $arg:ident, $default:expr) => {
+        $arg.into_input_over(<$t as ShadowInput>::shadow($default))
+    };
     (@arg_thing (IntoInput $($o:tt)*) $arg:expr) => {
         $arg.into_input()
     };
@@ -59,35 +66,29 @@ macro_rules! simple_typed_block_def {
 
 // Control =====================================================================
 simple_typed_block_def! {
-    wait(duration: (IntoInput<PositiveNumber>)) -> StackBlock
+    wait(duration: (IntoInput<PositiveNumber>) = 1) -> StackBlock
 }
 
 pub fn repeat(
     times: impl IntoInput<PositiveInteger>,
     to_repeat: Option<impl IntoInput<Stack>>,
 ) -> StackBlock {
-    unsafe {
-        TypedStackBuilder::assume_typed(blocks::repeat(
-            times.into_input(),
-            to_repeat.map(IntoInput::into_input),
-        ))
-    }
+    shaped(blocks::repeat(
+        times.into_input_over(PositiveInteger::shadow(10)),
+        to_repeat.map(IntoInput::into_input),
+    ))
 }
 
-pub fn forever(to_repeat: Option<impl IntoInput<Stack>>) -> StackBlock {
-    unsafe {
-        TypedStackBuilder::assume_typed(blocks::forever(to_repeat.map(IntoInput::into_input)))
-    }
+pub fn forever(to_repeat: Option<impl IntoInput<Stack>>) -> CapBlock {
+    shaped(blocks::forever(to_repeat.map(IntoInput::into_input)))
 }
 
 pub fn if_(condition: impl IntoInput<Bool>, if_true: impl IntoInput<Stack>) -> StackBlock {
-    unsafe {
-        TypedStackBuilder::assume_typed(blocks::if_(
-            condition.into_input(),
-            // shit ain't really work if you don't know the type
-            Some(if_true.into_input()),
-        ))
-    }
+    shaped(blocks::if_(
+        condition.into_input(),
+        // shit ain't really work if you don't know the type
+        Some(if_true.into_input()),
+    ))
 }
 
 pub fn if_else(
@@ -95,13 +96,11 @@ pub fn if_else(
     if_true: impl IntoInput<Stack>,
     if_false: impl IntoInput<Stack>,
 ) -> StackBlock {
-    unsafe {
-        TypedStackBuilder::assume_typed(blocks::if_else(
-            condition.into_input(),
-            Some(if_true.into_input()),
-            Some(if_false.into_input()),
-        ))
-    }
+    shaped(blocks::if_else(
+        condition.into_input(),
+        Some(if_true.into_input()),
+        Some(if_false.into_input()),
+    ))
 }
 
 simple_typed_block_def! {
@@ -112,22 +111,25 @@ pub fn repeat_until(
     condition: impl IntoInput<Bool>,
     to_repeat: Option<impl IntoInput<Stack>>,
 ) -> StackBlock {
-    unsafe {
-        TypedStackBuilder::assume_typed(blocks::repeat_until(
-            condition.into_input(),
-            to_repeat.map(IntoInput::into_input),
-        ))
-    }
+    shaped(blocks::repeat_until(
+        condition.into_input(),
+        to_repeat.map(IntoInput::into_input),
+    ))
 }
 
-/// <br/>
-///
-/// `stop_option` Accepts:
-///  - `"this script"` and `has_next` should be `false`
-///  - `"other scripts in sprite"` and `has_next` should be `true`
-///  - `"all"` and `has_next` should be `false`
-pub fn stop(stop_option: impl IntoField, has_next: bool) -> CapBlock {
-    unsafe { TypedStackBuilder::assume_typed(blocks::stop(stop_option.into_field(), has_next)) }
+/// Stop all or this script.
+/// For stopping other scripts in sprite, use [`stop_other_scripts`].
+pub fn stop(stop_option: impl IntoField<StopOption>) -> CapBlock {
+    shaped(blocks::stop(stop_option.into_field(), false))
+}
+
+/// Stop other scripts in sprite.
+/// Unlike [`stop`], blocks can be stacked below this.
+pub fn stop_other_scripts() -> StackBlock {
+    shaped(blocks::stop(
+        BlockFieldBuilder::new("other scripts in sprite".to_owned()),
+        true,
+    ))
 }
 
 simple_typed_block_def! {
@@ -138,14 +140,17 @@ simple_typed_block_def! {
     /// <br/>
     ///
     /// Accepts:
-    ///  - Sprite name
-    create_clone_of(sprite: (IntoInput<Text>)) -> StackBlock
+    ///  - [`SpriteHandle`]
+    ///  - [`Myself`]
+    ///  - Reporter or variable for sprite name computed at runtime
+    create_clone_of(sprite: (IntoInput<CloneTarget>)) -> StackBlock
 
     /// Uses as an argument for [`create_clone_of`]
     ///
     /// Accepts:
-    ///  - Sprite name
-    create_clone_of_menu(sprite: (IntoField)) -> MenuReporter
+    ///  - [`SpriteHandle`]
+    ///  - [`Myself`]
+    create_clone_of_menu(sprite: (IntoField<CloneTarget>)) -> MenuReporter<CloneTarget>
 
     delete_this_clone() -> CapBlock
 }
@@ -156,15 +161,8 @@ simple_typed_block_def! {
     /// <br/>
     ///
     /// Accepts:
-    ///  - `"any"`
-    ///  - `"space"`
-    ///  - `"left arrow"`
-    ///  - `"right arrow"`
-    ///  - `"up arrow"`
-    ///  - `"down arrow"`
-    ///  - Number 0 - 9
-    ///  - Letter a - z
-    when_key_pressed(key: (IntoField)) -> HatBlock
+    ///  - [`Key`]
+    when_key_pressed(key: (IntoField<Key>)) -> HatBlock
     when_this_sprite_clicked() -> HatBlock
     /// <br/>
     ///
@@ -174,156 +172,200 @@ simple_typed_block_def! {
     /// <br/>
     ///
     /// Accepts:
-    /// - `"LOUDNESS"`
-    /// - `"TIMER"`
-    when_greater_than(variable: (IntoField), value: (IntoInput<Number>)) -> HatBlock
+    ///  - [`GreaterThanOption`]
+    when_greater_than(variable: (IntoField<GreaterThanOption>), value: (IntoInput<Number>) = 10) -> HatBlock
+    /// <br/>
+    ///
+    /// Accepts:
+    ///  - [`BroadcastHandle`]
     when_broadcast_received(broadcast: (IntoField<Broadcast>)) -> HatBlock
+    /// <br/>
+    ///
+    /// Accepts:
+    ///  - [`BroadcastHandle`]
+    ///  - Text reporter for broadcast name computed at runtime
     broadcast(broadcast: (IntoInput<Broadcast>)) -> StackBlock
+    /// <br/>
+    ///
+    /// Accepts:
+    ///  - [`BroadcastHandle`]
+    ///  - Text reporter for broadcast name computed at runtime
     broadcast_and_wait(broadcast: (IntoInput<Broadcast>)) -> StackBlock
 }
 
+pub fn when_stage_clicked() -> HatBlock {
+    BlockShape::from_block(BlockNormalBuilder::new("event_whenstageclicked"))
+}
+
 // Looks =======================================================================
 simple_typed_block_def! {
-    say(message: (IntoInput<Text>)) -> StackBlock
-    say_for_secs(message: (IntoInput<Text>), secs: (IntoInput<Number>)) -> StackBlock
-    think(message: (IntoInput<Text>)) -> StackBlock
-    think_for_secs(message: (IntoInput<Text>), secs: (IntoInput<Number>)) -> StackBlock
+    say(message: (IntoInput<Text>) = "Hello!") -> StackBlock
+    say_for_secs(message: (IntoInput<Text>) = "Hello!", secs: (IntoInput<Number>) = 2) -> StackBlock
+    think(message: (IntoInput<Text>) = "Hmm...") -> StackBlock
+    think_for_secs(message: (IntoInput<Text>) = "Hmm...", secs: (IntoInput<Number>) = 2) -> StackBlock
     /// <br/>
     ///
     /// Accepts:
     ///  - Costume name
-    switch_costume_to(costume: (IntoInput<Text>)) -> StackBlock
+    ///  - Reporter or variable for costume name or number computed at runtime
+    switch_costume_to(costume: (IntoInput<Costume>)) -> StackBlock
     /// Uses as an argument for [`switch_costume_to`]
     ///
     /// Accepts:
     ///  - Costume name
-    costume_menu(costume: (IntoField)) -> MenuReporter
+    costume_menu(costume: (IntoField<Costume>)) -> MenuReporter<Costume>
     next_costume() -> StackBlock
     /// <br/>
     ///
     /// Accepts:
-    ///  - Costume name
-    switch_backdrop_to(backdrop: (IntoInput<Text>)) -> StackBlock
-    /// Uses as an argument for [`switch_backdrop_to`]
+    ///  - Backdrop name
+    ///  - Reporter or variable for backdrop name or number computed at runtime
+    switch_backdrop_to(backdrop: (IntoInput<Backdrop>)) -> StackBlock
+    /// Uses as an argument for [`switch_backdrop_to`] and [`switch_backdrop_to_and_wait`]
     ///
     /// Accepts:
     ///  - Backdrop name
-    backdrop_menu(backdrop: (IntoField)) -> MenuReporter
+    backdrop_menu(backdrop: (IntoField<Backdrop>)) -> MenuReporter<Backdrop>
     next_backdrop() -> StackBlock
-    change_size_by(by: (IntoInput<Number>)) -> StackBlock
-    set_size_to(to: (IntoInput<Number>)) -> StackBlock
-    /// <br/>
-    ///
-    /// Accepts
-    ///  - `"COLOR"`
-    ///  - `"FISHEYE"`
-    ///  - `"WHIRL"`
-    ///  - `"PIXELATE"`
-    ///  - `"MOSAIC"`
-    ///  - `"BRIGHTNESS"`
-    ///  - `"GHOST"`
-    change_looks_effect_by(effect: (IntoField), by: (IntoInput<Number>)) -> StackBlock
-    /// <br/>
-    ///
-    /// Accepts
-    ///  - `"COLOR"`
-    ///  - `"FISHEYE"`
-    ///  - `"WHIRL"`
-    ///  - `"PIXELATE"`
-    ///  - `"MOSAIC"`
-    ///  - `"BRIGHTNESS"`
-    ///  - `"GHOST"`
-    set_looks_effect_to(effect: (IntoField), to: (IntoInput<Number>)) -> StackBlock
+    change_size_by(by: (IntoInput<Number>) = 10) -> StackBlock
+    set_size_to(to: (IntoInput<Number>) = 100) -> StackBlock
+    /// <br/>
+    ///
+    /// Accepts:
+    ///  - [`LooksEffect`]
+    change_looks_effect_by(effect: (IntoField<LooksEffect>), by: (IntoInput<Number>) = 25) -> StackBlock
+    /// <br/>
+    ///
+    /// Accepts:
+    ///  - [`LooksEffect`]
+    set_looks_effect_to(effect: (IntoField<LooksEffect>), to: (IntoInput<Number>) = 0) -> StackBlock
     clear_graphic_effects() -> StackBlock
     show() -> StackBlock
     hide() -> StackBlock
     /// <br/>
     ///
     /// Accepts:
-    ///  - `"front"`
-    ///  - `"back"`
-    go_to_layer(layer: (IntoField)) -> StackBlock
-    /// <br/>
-    ///
-    /// `layer` Accepts:
-    ///  - `"foward"`
-    ///  - `"backward"`
-    change_layer(layer: (IntoField), by: (IntoInput<Integer>)) -> StackBlock
+    ///  - [`LayerPosition`]
+    go_to_layer(layer: (IntoField<LayerPosition>)) -> StackBlock
     /// <br/>
     ///
     /// Accepts:
-    /// - `"number"`
-    /// - `"name"`
-    costume(return_type: (IntoField)) -> JustReporter<Value>
+    ///  - [`NumberName`]
+    costume(return_type: (IntoField<NumberName>)) -> JustReporter<Value>
     /// <br/>
     ///
     /// Accepts:
-    /// - `"number"`
-    /// - `"name"`
-    backdrop(return_type: (IntoField)) -> JustReporter<Value>
+    ///  - [`NumberName`]
+    backdrop(return_type: (IntoField<NumberName>)) -> JustReporter<Value>
     size() -> JustReporter<Number>
 }
 
+/// <br/>
+///
+/// Accepts:
+///  - Backdrop name
+///  - Reporter or variable for backdrop name or number computed at runtime
+pub fn switch_backdrop_to_and_wait(backdrop: impl IntoInput<Backdrop>) -> StackBlock {
+    let mut b = BlockNormalBuilder::new("looks_switchbackdroptoandwait");
+    b.add_input("BACKDROP", backdrop.into_input());
+    BlockShape::from_block(b)
+}
+
+/// <br/>
+///
+/// `layer` Accepts:
+///  - [`LayerDirection`]
+pub fn change_layer(
+    layer: impl IntoField<LayerDirection>,
+    by: impl IntoInput<Integer>,
+) -> StackBlock {
+    let mut b = BlockNormalBuilder::new("looks_goforwardbackwardlayers");
+    b.add_field("FORWARD_BACKWARD", layer.into_field());
+    b.add_input("NUM", by.into_input_over(Integer::shadow(1)));
+    BlockShape::from_block(b)
+}
+
+/// [`costume`] with [`NumberName::Number`]
+pub fn costume_number() -> JustReporter<PositiveInteger> {
+    shaped(blocks::costume(NumberName::Number.into_field()))
+}
+
+/// [`costume`] with [`NumberName::Name`]
+pub fn costume_name() -> JustReporter<Text> {
+    shaped(blocks::costume(NumberName::Name.into_field()))
+}
+
+/// [`backdrop`] with [`NumberName::Number`]
+pub fn backdrop_number() -> JustReporter<PositiveInteger> {
+    shaped(blocks::backdrop(NumberName::Number.into_field()))
+}
+
+/// [`backdrop`] with [`NumberName::Name`]
+pub fn backdrop_name() -> JustReporter<Text> {
+    shaped(blocks::backdrop(NumberName::Name.into_field()))
+}
+
 // Motion ======================================================================
 simple_typed_block_def! {
-    move_steps(steps: (IntoInput<Number>)) -> StackBlock
-    turn_right(degress: (IntoInput<Number>)) -> StackBlock
-    turn_left(degress: (IntoInput<Number>)) -> StackBlock
+    move_steps(steps: (IntoInput<Number>) = 10) -> StackBlock
+    turn_right(degress: (IntoInput<Number>) = 15) -> StackBlock
+    turn_left(degress: (IntoInput<Number>) = 15) -> StackBlock
     /// <br/>
     ///
     /// Accepts:
-    ///  - Sprite name
-    ///  - `"_mouse_"` go to mouse position
-    ///  - `"_random_"` go to random position
-    go_to(to: (IntoInput<Text>)) -> StackBlock
+    ///  - [`SpriteHandle`]
+    ///  - [`Mouse`] go to mouse position
+    ///  - [`Random`] go to random position
+    ///  - Reporter or variable for sprite name computed at runtime
+    go_to(to: (IntoInput<GoToTarget>)) -> StackBlock
     /// Uses as an argument for [`go_to`]
     ///
     /// Accepts:
-    ///  - Sprite name
-    ///  - `"_mouse_"` go to mouse position
-    ///  - `"_random_"` go to random position
-    go_to_menu(to: (IntoField)) -> MenuReporter
-    goto_xy(x: (IntoInput<Number>), y: (IntoInput<Number>)) -> StackBlock
+    ///  - [`SpriteHandle`]
+    ///  - [`Mouse`] go to mouse position
+    ///  - [`Random`] go to random position
+    go_to_menu(to: (IntoField<GoToTarget>)) -> MenuReporter<GoToTarget>
+    goto_xy(x: (IntoInput<Number>) = 0, y: (IntoInput<Number>) = 0) -> StackBlock
     /// <br/>
     ///
-    /// Accepts:
-    ///  - Sprite name
-    ///  - `"_mouse_"` glide to mouse position
-    ///  - `"_random_"` glide to random position
-    glide_to(duration_secs: (IntoInput<Number>), to: (IntoInput<Text>)) -> StackBlock
+    /// `to` Accepts:
+    ///  - [`SpriteHandle`]
+    ///  - [`Mouse`] glide to mouse position
+    ///  - [`Random`] glide to random position
+    ///  - Reporter or variable for sprite name computed at runtime
+    glide_to(duration_secs: (IntoInput<Number>) = 1, to: (IntoInput<GlideToTarget>)) -> StackBlock
     /// Uses as an argument for [`glide_to`] in `to`
     ///
     /// Accepts:
-    ///  - Sprite name
-    ///  - `"_mouse_"` glide to mouse position
-    ///  - `"_random_"` glide to random position
-    glide_to_menu(to: (IntoField)) -> MenuReporter
-    glide_to_xy(dur: (IntoInput<Number>), x: (IntoInput<Number>), y: (IntoInput<Number>)) -> StackBlock
-    point_in_direction(direction: (IntoInput<Angle>)) -> StackBlock
+    ///  - [`SpriteHandle`]
+    ///  - [`Mouse`] glide to mouse position
+    ///  - [`Random`] glide to random position
+    glide_to_menu(to: (IntoField<GlideToTarget>)) -> MenuReporter<GlideToTarget>
+    glide_to_xy(dur: (IntoInput<Number>) = 1, x: (IntoInput<Number>) = 0, y: (IntoInput<Number>) = 0) -> StackBlock
+    point_in_direction(direction: (IntoInput<Angle>) = 90) -> StackBlock
     /// <br/>
     ///
     /// Accepts:
-    ///  - Sprite name
-    ///  - `"_mouse_"` glide to mouse position
-    point_towards(towards: (IntoInput<Text>)) -> StackBlock
+    ///  - [`SpriteHandle`]
+    ///  - [`Mouse`] point towards mouse position
+    ///  - Reporter or variable for sprite name computed at runtime
+    point_towards(towards: (IntoInput<PointTowardsTarget>)) -> StackBlock
     /// Uses as an argument for [`point_towards`]
     ///
     /// Accepts:
-    ///  - Sprite name
-    ///  - `"_mouse_"` glide to mouse position
-    point_towards_menu(towards: (IntoField)) -> MenuReporter
-    set_x(x: (IntoInput<Number>)) -> StackBlock
-    change_x_by(by: (IntoInput<Number>)) -> StackBlock
-    set_y(y: (IntoInput<Number>)) -> StackBlock
-    change_y_by(by: (IntoInput<Number>)) -> StackBlock
+    ///  - [`SpriteHandle`]
+    ///  - [`Mouse`] point towards mouse position
+    point_towards_menu(towards: (IntoField<PointTowardsTarget>)) -> MenuReporter<PointTowardsTarget>
+    set_x(x: (IntoInput<Number>) = 0) -> StackBlock
+    change_x_by(by: (IntoInput<Number>) = 10) -> StackBlock
+    set_y(y: (IntoInput<Number>) = 0) -> StackBlock
+    change_y_by(by: (IntoInput<Number>) = 10) -> StackBlock
     if_on_edge_bounce() -> StackBlock
     /// <br/>
     ///
     /// Accepts:
-    ///  - `"left-right"`
-    ///  - `"don't rotate"`
-    ///  - `"all around"`
-    set_rotation_style(style: (IntoField)) -> StackBlock
+    ///  - [`RotationStyle`]
+    set_rotation_style(style: (IntoField<RotationStyle>)) -> StackBlock
     direction() -> JustReporter<Number>
     y_position() -> JustReporter<Number>
     x_position() -> JustReporter<Number>
@@ -331,41 +373,28 @@ simple_typed_block_def! {
 
 // Operators ===================================================================
 simple_typed_block_def! {
-    add(lhs: (IntoInput<Number>), rhs: (IntoInput<Number>)) -> JustReporter<Number>
-    sub(lhs: (IntoInput<Number>), rhs: (IntoInput<Number>)) -> JustReporter<Number>
-    mul(lhs: (IntoInput<Number>), rhs: (IntoInput<Number>)) -> JustReporter<Number>
-    div(lhs: (IntoInput<Number>), rhs: (IntoInput<Number>)) -> JustReporter<Number>
-    random(from: (IntoInput<Number>), to: (IntoInput<Number>)) -> JustReporter<Bool>
-    less_than(lhs: (IntoInput<Value>), rhs: (IntoInput<Value>)) -> JustReporter<Bool>
-    greater_than(lhs: (IntoInput<Value>), rhs: (IntoInput<Value>)) -> JustReporter<Bool>
-    equals(lhs: (IntoInput<Value>), rhs: (IntoInput<Value>)) -> JustReporter<Bool>
+    add(lhs: (IntoInput<Number>) = "", rhs: (IntoInput<Number>) = "") -> JustReporter<Number>
+    sub(lhs: (IntoInput<Number>) = "", rhs: (IntoInput<Number>) = "") -> JustReporter<Number>
+    mul(lhs: (IntoInput<Number>) = "", rhs: (IntoInput<Number>) = "") -> JustReporter<Number>
+    div(lhs: (IntoInput<Number>) = "", rhs: (IntoInput<Number>) = "") -> JustReporter<Number>
+    random(from: (IntoInput<Number>) = 1, to: (IntoInput<Number>) = 10) -> JustReporter<Number>
+    less_than(lhs: (IntoInput<Value>) = "", rhs: (IntoInput<Value>) = 50) -> JustReporter<Bool>
+    greater_than(lhs: (IntoInput<Value>) = "", rhs: (IntoInput<Value>) = 50) -> JustReporter<Bool>
+    equals(lhs: (IntoInput<Value>) = "", rhs: (IntoInput<Value>) = 50) -> JustReporter<Bool>
     and(a: (IntoInput<Bool>), b: (IntoInput<Bool>)) -> JustReporter<Bool>
     or(a: (IntoInput<Bool>), b: (IntoInput<Bool>)) -> JustReporter<Bool>
     not(val: (IntoInput<Bool>)) -> JustReporter<Bool>
-    join(a: (IntoInput<Text>), b: (IntoInput<Text>)) -> JustReporter<Text>
-    letter_of(idx: (IntoInput<PositiveInteger>), text: (IntoInput<Text>)) -> JustReporter<Text>
-    length_of(text: (IntoInput<Text>)) -> JustReporter<PositiveInteger>
-    contains(text: (IntoInput<Text>), contains: (IntoInput<Text>)) -> JustReporter<Bool>
-    modulo(dividend: (IntoInput<Number>), divisor: (IntoInput<Number>)) -> JustReporter<Number>
-    round(val: (IntoInput<Number>)) -> JustReporter<Number>
+    join(a: (IntoInput<Text>) = "apple ", b: (IntoInput<Text>) = "banana") -> JustReporter<Text>
+    letter_of(idx: (IntoInput<PositiveInteger>) = 1, text: (IntoInput<Text>) = "apple") -> JustReporter<Text>
+    length_of(text: (IntoInput<Text>) = "apple") -> JustReporter<PositiveInteger>
+    contains(text: (IntoInput<Text>) = "apple", contains: (IntoInput<Text>) = "a") -> JustReporter<Bool>
+    modulo(dividend: (IntoInput<Number>) = "", divisor: (IntoInput<Number>) = "") -> JustReporter<Number>
+    round(val: (IntoInput<Number>) = "") -> JustReporter<Number>
     /// <br/>
     ///
     /// `op` Accepts:
-    ///  - `"abs"`
-    ///  - `"floor"`
-    ///  - `"ceiling"`
-    ///  - `"sqrt"`
-    ///  - `"sin"`
-    ///  - `"cos"`
-    ///  - `"tan"`
-    ///  - `"asin"`
-    ///  - `"acos"`
-    ///  - `"atan"`
-    ///  - `"ln"`
-    ///  - `"log"`
-    ///  - `"e ^"`
-    ///  - `"10 ^"`
-    math_op(op: (IntoField), val: (IntoInput<Number>)) -> JustReporter<Number>
+    ///  - [`MathOp`]
+    math_op(op: (IntoField<MathOp>), val: (IntoInput<Number>) = "") -> JustReporter<Number>
 }
 
 // Sensing =====================================================================
@@ -373,75 +402,59 @@ simple_typed_block_def! {
     /// <br/>
     ///
     /// Accepts:
-    ///  - Sprite name
-    ///  - `"_mouse_"`
-    ///  - `"_edge_"`
-    touching(what: (IntoInput<Text>)) -> JustReporter<Bool>
+    ///  - [`SpriteHandle`]
+    ///  - [`Mouse`]
+    ///  - [`Edge`]
+    ///  - Reporter or variable for sprite name computed at runtime
+    touching(what: (IntoInput<TouchingTarget>)) -> JustReporter<Bool>
     /// Uses as an argument for [`touching`]
     ///
     /// Accepts:
-    ///  - Sprite name
-    ///  - `"_mouse_"`
-    ///  - `"_edge_"`
-    touching_menu(what: (IntoField)) -> MenuReporter
-    touching_color(color: (IntoInput<Color>)) -> JustReporter<Bool>
-    color_touching_color(color_a: (IntoInput<Color>), color_b: (IntoInput<Color>)) -> JustReporter<Bool>
+    ///  - [`SpriteHandle`]
+    ///  - [`Mouse`]
+    ///  - [`Edge`]
+    touching_menu(what: (IntoField<TouchingTarget>)) -> MenuReporter<TouchingTarget>
+    touching_color(color: (IntoInput<Color>) = "#9966ff") -> JustReporter<Bool>
+    color_touching_color(color_a: (IntoInput<Color>) = "#9966ff", color_b: (IntoInput<Color>) = "#ffab19") -> JustReporter<Bool>
     /// <br/>
     ///
     /// Accepts:
-    ///  - Sprite name
-    ///  - `"_mouse_"`
-    distance_to(what: (IntoInput<Text>)) -> JustReporter<Number>
+    ///  - [`SpriteHandle`]
+    ///  - [`Mouse`]
+    ///  - Reporter or variable for sprite name computed at runtime
+    distance_to(what: (IntoInput<DistanceToTarget>)) -> JustReporter<Number>
     /// Uses as an argument for [`distance_to`]
     ///
     /// Accepts:
-    ///  - Sprite name
-    ///  - `"_mouse_"`
-    distance_to_menu(what: (IntoField)) -> MenuReporter
-    ask_and_wait(prompt_message: (IntoInput<Text>)) -> StackBlock
+    ///  - [`SpriteHandle`]
+    ///  - [`Mouse`]
+    distance_to_menu(what: (IntoField<DistanceToTarget>)) -> MenuReporter<DistanceToTarget>
+    ask_and_wait(prompt_message: (IntoInput<Text>) = "What's your name?") -> StackBlock
     answer() -> JustReporter<Text>
     /// <br/>
     ///
     /// Accepts:
-    ///  - `"any"`
-    ///  - `"space"`
-    ///  - `"left arrow"`
-    ///  - `"right arrow"`
-    ///  - `"up arrow"`
-    ///  - `"down arrow"`
-    ///  - Number 0 - 9
-    ///  - Letter a - z
-    key_pressed(key: (IntoInput<Text>)) -> JustReporter<Bool>
-    /// Uses as an argument for [`key_pressed`]
-    ///
-    /// Accepts:
-    ///  - `"any"`
-    ///  - `"space"`
-    ///  - `"left arrow"`
-    ///  - `"right arrow"`
-    ///  - `"up arrow"`
-    ///  - `"down arrow"`
-    ///  - Number 0 - 9
-    ///  - Letter a - z
-    key_menu(key: (IntoInput<Text>)) -> MenuReporter
+    ///  - [`Key`]
+    ///  - Reporter or variable for key computed at runtime
+    key_pressed(key: (IntoInput<Key>)) -> JustReporter<Bool>
     mouse_down() -> JustReporter<Bool>
     mouse_x() -> JustReporter<Number>
     /// <br/>
     ///
     /// Accepts:
-    ///  - `"not draggable"`
-    ///  - `"draggable"`
-    set_drag_mode(mode: (IntoField)) -> StackBlock
+    ///  - [`DragMode`]
+    set_drag_mode(mode: (IntoField<DragMode>)) -> StackBlock
     loudness() -> JustReporter<Number>
     timer() -> JustReporter<Number>
     reset_timer() -> StackBlock
     /// <br/>
     ///
     /// `what` Accepts:
-    ///   - Sprite name
-    ///   - `"_stage_"`
+    ///   - [`SpriteHandle`]
+    ///   - [`Stage`]
+    ///   - Text reporter for sprite name computed at runtime
     ///
-    /// If `what` is `"_stage_"`
+    /// If `what` is [`Stage`]
     /// <br/>
     ///
     ///    `var` Accepts:
@@ -462,28 +475,42 @@ simple_typed_block_def! {
     ///      - `"costume name"`
     ///      - `"size"`
     ///      - `"volume"`
-    var_of(var: (IntoField), what: (IntoInput<Text>)) -> JustReporter<Value>
+    var_of(var: (IntoField), what: (IntoInput<ObjectTarget>)) -> JustReporter<Value>
     /// Uses as an argument for [`var_of`]
     ///
     /// `what` Accepts:
-    ///   - Sprite name
-    ///   - `"_stage_"`
-    var_of_object_menu(what: (IntoField)) -> MenuReporter
+    ///   - [`SpriteHandle`]
+    ///   - [`Stage`]
+    var_of_object_menu(what: (IntoField<ObjectTarget>)) -> MenuReporter<ObjectTarget>
     /// <br/>
     ///
     /// Accepts:
-    ///  - `"SECOND"`
-    ///  - `"MINUTE"`
-    ///  - `"HOUR"`
-    ///  - `"DAYOFWEEK"`
-    ///  - `"DATE"`
-    ///  - `"MONTH"`
-    ///  - `"YEAR"`
-    current_datetime(format: (IntoField)) -> JustReporter<PositiveInteger>
+    ///  - [`DateTimeUnit`]
+    current_datetime(format: (IntoField<DateTimeUnit>)) -> JustReporter<PositiveInteger>
     days_since_2000() -> JustReporter<Number>
     username() -> JustReporter<Text>
 }
 
+/// Uses as an argument for [`key_pressed`]
+///
+/// Accepts:
+///  - [`Key`]
+pub fn key_menu(key: impl IntoField<Key>) -> MenuReporter<Key> {
+    let mut b = BlockNormalBuilder::new("sensing_keyoptions");
+    b.add_field("KEY_OPTION", key.into_field());
+    b.set_shadow(true);
+    BlockShape::from_block(b)
+}
+
+pub fn mouse_y() -> JustReporter<Number> {
+    BlockShape::from_block(BlockNormalBuilder::new("sensing_mousey"))
+}
+
+/// Whether [`loudness`] is over 10
+pub fn loud() -> JustReporter<Bool> {
+    BlockShape::from_block(BlockNormalBuilder::new("sensing_loud"))
+}
+
 // Sound =======================================================================
 
 simple_typed_block_def! {
@@ -492,56 +519,163 @@ simple_typed_block_def! {
     ///
     /// Accepts:
     ///  - Sound name
-    play_sound_until_done(sound: (IntoInput<Text>)) -> StackBlock
+    ///  - Reporter or variable for sound name computed at runtime
+    play_sound_until_done(sound: (IntoInput<Sound>)) -> StackBlock
     /// <br/>
     ///
     /// Accepts:
     ///  - Sound name
-    play_sound(sound: (IntoInput<Text>)) -> StackBlock
+    ///  - Reporter or variable for sound name computed at runtime
+    play_sound(sound: (IntoInput<Sound>)) -> StackBlock
     /// Uses as an argument for [`play_sound_until_done`] and [`play_sound`]
     ///
     /// Accepts:
     ///  - Sound name
-    sound_menu(sound: (IntoField)) -> MenuReporter
+    sound_menu(sound: (IntoField<Sound>)) -> MenuReporter<Sound>
     stop_all_sound() -> StackBlock
     /// <br/>
     ///
     /// Accepts:
-    ///  - `"PITCH"`
-    ///  - `"PAN"`
-    change_sound_effect_by(effect: (IntoField), by: (IntoInput<Number>)) -> StackBlock
+    ///  - [`SoundEffect`]
+    change_sound_effect_by(effect: (IntoField<SoundEffect>), by: (IntoInput<Number>) = 10) -> StackBlock
     /// <br/>
     ///
     /// Accepts:
-    ///  - `"PITCH"`
-    ///  - `"PAN"`
-    set_sound_effect_to(effect: (IntoField), to: (IntoInput<Number>)) -> StackBlock
+    ///  - [`SoundEffect`]
+    set_sound_effect_to(effect: (IntoField<SoundEffect>), to: (IntoInput<Number>) = 100) -> StackBlock
     clear_sound_effects() -> StackBlock
-    set_volume_to(volume: (IntoInput<Number>)) -> StackBlock
-    change_volume_by(by: (IntoInput<Number>)) -> StackBlock
+    set_volume_to(volume: (IntoInput<Number>) = 100) -> StackBlock
+    change_volume_by(by: (IntoInput<Number>) = -10) -> StackBlock
     volume() -> JustReporter<Number>
 }
 
 // Data ========================================================================
 
-simple_typed_block_def! {
-    sprite_var(name: (Into<String>)) -> JustReporter<Value>
-    sprite_list(name: (Into<String>)) -> JustReporter<Value>
-    global_var(name: (Into<String>)) -> JustReporter<Value>
-    global_list(name: (Into<String>)) -> JustReporter<Value>
-    set_var_to(var: (IntoField<Variable>), to: (IntoInput<Value>)) -> StackBlock
-    change_var_by(var: (IntoField<Variable>), by: (IntoInput<Value>)) -> StackBlock
-    show_var(var: (IntoField<Variable>)) -> StackBlock
-    hide_var(var: (IntoField<Variable>)) -> StackBlock
-    add_to_list(item: (IntoInput<Value>)) -> StackBlock
-    delete_in_list(list: (IntoField<List>), idx: (IntoInput<Integer>)) -> StackBlock
-    delete_all_in_list(list: (IntoField<List>)) -> StackBlock
-    insert_in_list(list: (IntoField<List>), idx: (IntoInput<Integer>), item: (IntoInput<Value>)) -> StackBlock
-    replace_in_list(list: (IntoField<List>), idx: (IntoInput<Integer>), item: (IntoInput<Value>)) -> StackBlock
-    item_in_list(list: (IntoField<List>), idx: (IntoInput<Integer>)) -> JustReporter<Value>
-    count_of_item_in_list(list: (IntoField<List>), item: (IntoInput<Value>)) -> JustReporter<Integer>
-    length_of_list(list: (IntoField<List>)) -> JustReporter<Integer>
-    list_contains(list: (IntoField<List>), item: (IntoInput<Value>)) -> JustReporter<Bool>
-    show_list(list: (IntoField<List>)) -> StackBlock
-    hide_list(list: (IntoField<List>)) -> StackBlock
+/// Reporter of the variable's value
+pub fn var<T>(var: &VarHandle<T>) -> JustReporter<T> {
+    let b = match var.scope() {
+        Scope::Global => blocks::global_var(var.field_name()),
+        Scope::Sprite => blocks::sprite_var(var.field_name()),
+    };
+    shaped(b)
+}
+
+/// Reporter of the list's items joined together
+pub fn list<T>(list: &ListHandle<T>) -> JustReporter<ListContents> {
+    let b = match list.scope() {
+        Scope::Global => blocks::global_list(list.field_name()),
+        Scope::Sprite => blocks::sprite_list(list.field_name()),
+    };
+    shaped(b)
+}
+
+pub fn set_var_to<T>(var: &VarHandle<T>, to: impl IntoInput<T>) -> StackBlock {
+    shaped(blocks::set_var_to(
+        var.into_field(),
+        to.into_input_over(Text::shadow(0)),
+    ))
 }
+
+pub fn change_var_by<T>(var: &VarHandle<T>, by: impl IntoInput<Number>) -> StackBlock {
+    shaped(blocks::change_var_by(
+        var.into_field(),
+        by.into_input_over(Number::shadow(1)),
+    ))
+}
+
+pub fn show_var<T>(var: &VarHandle<T>) -> StackBlock {
+    shaped(blocks::show_var(var.into_field()))
+}
+
+pub fn hide_var<T>(var: &VarHandle<T>) -> StackBlock {
+    shaped(blocks::hide_var(var.into_field()))
+}
+
+pub fn add_to_list<T>(list: &ListHandle<T>, item: impl IntoInput<T>) -> StackBlock {
+    let mut b = BlockNormalBuilder::new("data_addtolist");
+    b.add_input("ITEM", item.into_input_over(Text::shadow("thing")));
+    b.add_field("LIST", list.into_field());
+    BlockShape::from_block(b)
+}
+
+pub fn delete_in_list<T>(list: &ListHandle<T>, idx: impl IntoInput<Integer>) -> StackBlock {
+    shaped(blocks::delete_in_list(
+        list.into_field(),
+        idx.into_input_over(Integer::shadow(1)),
+    ))
+}
+
+pub fn delete_all_in_list<T>(list: &ListHandle<T>) -> StackBlock {
+    shaped(blocks::delete_all_in_list(list.into_field()))
+}
+
+pub fn insert_in_list<T>(
+    list: &ListHandle<T>,
+    idx: impl IntoInput<Integer>,
+    item: impl IntoInput<T>,
+) -> StackBlock {
+    shaped(blocks::insert_in_list(
+        list.into_field(),
+        idx.into_input_over(Integer::shadow(1)),
+        item.into_input_over(Text::shadow("thing")),
+    ))
+}
+
+pub fn replace_in_list<T>(
+    list: &ListHandle<T>,
+    idx: impl IntoInput<Integer>,
+    item: impl IntoInput<T>,
+) -> StackBlock {
+    shaped(blocks::replace_in_list(
+        list.into_field(),
+        idx.into_input_over(Integer::shadow(1)),
+        item.into_input_over(Text::shadow("thing")),
+    ))
+}
+
+pub fn item_in_list<T>(list: &ListHandle<T>, idx: impl IntoInput<Integer>) -> JustReporter<T> {
+    shaped(blocks::item_in_list(
+        list.into_field(),
+        idx.into_input_over(Integer::shadow(1)),
+    ))
+}
+
+pub fn count_of_item_in_list<T>(
+    list: &ListHandle<T>,
+    item: impl IntoInput<T>,
+) -> JustReporter<Integer> {
+    shaped(blocks::count_of_item_in_list(
+        list.into_field(),
+        item.into_input_over(Text::shadow("thing")),
+    ))
+}
+
+pub fn length_of_list<T>(list: &ListHandle<T>) -> JustReporter<Integer> {
+    shaped(blocks::length_of_list(list.into_field()))
+}
+
+pub fn list_contains<T>(list: &ListHandle<T>, item: impl IntoInput<T>) -> JustReporter<Bool> {
+    shaped(blocks::list_contains(
+        list.into_field(),
+        item.into_input_over(Text::shadow("thing")),
+    ))
+}
+
+pub fn show_list<T>(list: &ListHandle<T>) -> StackBlock {
+    shaped(blocks::show_list(list.into_field()))
+}
+
+pub fn hide_list<T>(list: &ListHandle<T>) -> StackBlock {
+    shaped(blocks::hide_list(list.into_field()))
+}
+
+// Extensions ==================================================================
+
+pub mod music;
+pub mod pen;
+pub mod text_to_speech;
+pub mod translate;
+pub mod video_sensing;
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,114 @@
+//! Music extension blocks.
+//!
+//! Using any of these adds `"music"` to the project's extensions when it's built.
+
+use sb_itchy::{
+    block::{BlockFieldBuilder, BlockInputBuilder, BlockNormalBuilder},
+    stack::StackBuilder,
+};
+
+use super::menu_input;
+use crate::scripting::{arg::*, menu::*, stack::*};
+
+simple_typed_block_def! {
+    play_drum_for_beats(drum: (IntoField<Drum>), beats: (IntoInput<Number>) = 0.25) -> StackBlock
+    rest_for_beats(beats: (IntoInput<Number>) = 0.25) -> StackBlock
+    play_note_for_beats(note: (IntoInput<Note>) = 60, beats: (IntoInput<Number>) = 0.25) -> StackBlock
+    set_instrument(instrument: (IntoField<Instrument>)) -> StackBlock
+    set_tempo(tempo: (IntoInput<Number>) = 60) -> StackBlock
+    change_tempo(tempo: (IntoInput<Number>) = 20) -> StackBlock
+    tempo() -> JustReporter<Number>
+}
+
+/// MIDI note number, `60` is middle C.
+///
+/// Scratch only plays notes from `0` to `130`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct MidiNote(u8);
+
+impl MidiNote {
+    pub const MIDDLE_C: MidiNote = MidiNote(60);
+
+    /// Highest note Scratch plays
+    pub const HIGHEST: MidiNote = MidiNote(130);
+
+    /// Note of MIDI number `note`, `None` if it's above [`MidiNote::HIGHEST`]
+    pub fn new(note: u8) -> Option<MidiNote> {
+        Some(MidiNote(note)).filter(|note| *note <= MidiNote::HIGHEST)
+    }
+
+    /// Note from octave and semitone above C, e.g. `MidiNote::from_octave(4, 0)` is middle C.
+    ///
+    /// `None` if `semitone` is above `11` or the note is above [`MidiNote::HIGHEST`].
+    pub fn from_octave(octave: u8, semitone: u8) -> Option<MidiNote> {
+        if semitone > 11 {
+            return None;
+        }
+        let note = octave
+            .checked_add(1)?
+            .checked_mul(12)?
+            .checked_add(semitone)?;
+        MidiNote::new(note)
+    }
+
+    /// MIDI number of the note
+    pub fn number(self) -> u8 {
+        self.0
+    }
+}
+
+impl IntoInput<Note> for MidiNote {
+    fn into_input(self) -> BlockInputBuilder {
+        BlockInputBuilder::new().input_some(Note::shadow(self.0))
+    }
+}
+
+/// Untyped music blocks, in the same shape as [`sb_itchy::blocks`]
+mod blocks {
+    use super::*;
+
+    pub fn play_drum_for_beats(drum: BlockFieldBuilder, beats: BlockInputBuilder) -> StackBuilder {
+        let mut b = BlockNormalBuilder::new("music_playDrumForBeats");
+        b.add_input("DRUM", menu_input("music_menu_DRUM", "DRUM", drum));
+        b.add_input("BEATS", beats);
+        StackBuilder::start(b)
+    }
+
+    pub fn rest_for_beats(beats: BlockInputBuilder) -> StackBuilder {
+        let mut b = BlockNormalBuilder::new("music_restForBeats");
+        b.add_input("BEATS", beats);
+        StackBuilder::start(b)
+    }
+
+    pub fn play_note_for_beats(note: BlockInputBuilder, beats: BlockInputBuilder) -> StackBuilder {
+        let mut b = BlockNormalBuilder::new("music_playNoteForBeats");
+        b.add_input("NOTE", note);
+        b.add_input("BEATS", beats);
+        StackBuilder::start(b)
+    }
+
+    pub fn set_instrument(instrument: BlockFieldBuilder) -> StackBuilder {
+        let mut b = BlockNormalBuilder::new("music_setInstrument");
+        b.add_input(
+            "INSTRUMENT",
+            menu_input("music_menu_INSTRUMENT", "INSTRUMENT", instrument),
+        );
+        StackBuilder::start(b)
+    }
+
+    pub fn set_tempo(tempo: BlockInputBuilder) -> StackBuilder {
+        let mut b = BlockNormalBuilder::new("music_setTempo");
+        b.add_input("TEMPO", tempo);
+        StackBuilder::start(b)
+    }
+
+    pub fn change_tempo(tempo: BlockInputBuilder) -> StackBuilder {
+        let mut b = BlockNormalBuilder::new("music_changeTempo");
+        b.add_input("TEMPO", tempo);
+        StackBuilder::start(b)
+    }
+
+    pub fn tempo() -> StackBuilder {
+        StackBuilder::start(BlockNormalBuilder::new("music_getTempo"))
+    }
+}
@@ -0,0 +1,80 @@
+//! Pen extension blocks.
+//!
+//! Using any of these adds `"pen"` to the project's extensions when it's built.
+
+use sb_itchy::{
+    block::{BlockFieldBuilder, BlockInputBuilder, BlockNormalBuilder},
+    stack::StackBuilder,
+};
+
+use super::menu_input;
+use crate::scripting::{arg::*, menu::*, stack::*};
+
+simple_typed_block_def! {
+    erase_all() -> StackBlock
+    stamp() -> StackBlock
+    pen_down() -> StackBlock
+    pen_up() -> StackBlock
+    set_pen_color_to(color: (IntoInput<Color>) = "#0000ff") -> StackBlock
+    change_pen_param_by(param: (IntoField<PenColorParam>), value: (IntoInput<Number>) = 10) -> StackBlock
+    set_pen_param_to(param: (IntoField<PenColorParam>), value: (IntoInput<Number>) = 50) -> StackBlock
+    change_pen_size_by(size: (IntoInput<Number>) = 1) -> StackBlock
+    set_pen_size_to(size: (IntoInput<Number>) = 1) -> StackBlock
+}
+
+/// Untyped pen blocks, in the same shape as [`sb_itchy::blocks`]
+mod blocks {
+    use super::*;
+
+    pub fn erase_all() -> StackBuilder {
+        StackBuilder::start(BlockNormalBuilder::new("pen_clear"))
+    }
+
+    pub fn stamp() -> StackBuilder {
+        StackBuilder::start(BlockNormalBuilder::new("pen_stamp"))
+    }
+
+    pub fn pen_down() -> StackBuilder {
+        StackBuilder::start(BlockNormalBuilder::new("pen_penDown"))
+    }
+
+    pub fn pen_up() -> StackBuilder {
+        StackBuilder::start(BlockNormalBuilder::new("pen_penUp"))
+    }
+
+    pub fn set_pen_color_to(color: BlockInputBuilder) -> StackBuilder {
+        let mut b = BlockNormalBuilder::new("pen_setPenColorToColor");
+        b.add_input("COLOR", color);
+        StackBuilder::start(b)
+    }
+
+    pub fn change_pen_param_by(param: BlockFieldBuilder, value: BlockInputBuilder) -> StackBuilder {
+        let mut b = BlockNormalBuilder::new("pen_changePenColorParamBy");
+        b.add_input("COLOR_PARAM", color_param_menu(param));
+        b.add_input("VALUE", value);
+        StackBuilder::start(b)
+    }
+
+    pub fn set_pen_param_to(param: BlockFieldBuilder, value: BlockInputBuilder) -> StackBuilder {
+        let mut b = BlockNormalBuilder::new("pen_setPenColorParamTo");
+        b.add_input("COLOR_PARAM", color_param_menu(param));
+        b.add_input("VALUE", value);
+        StackBuilder::start(b)
+    }
+
+    pub fn change_pen_size_by(size: BlockInputBuilder) -> StackBuilder {
+        let mut b = BlockNormalBuilder::new("pen_changePenSizeBy");
+        b.add_input("SIZE", size);
+        StackBuilder::start(b)
+    }
+
+    pub fn set_pen_size_to(size: BlockInputBuilder) -> StackBuilder {
+        let mut b = BlockNormalBuilder::new("pen_setPenSizeTo");
+        b.add_input("SIZE", size);
+        StackBuilder::start(b)
+    }
+
+    fn color_param_menu(param: BlockFieldBuilder) -> BlockInputBuilder {
+        menu_input("pen_menu_colorParam", "colorParam", param)
+    }
+}
@@ -0,0 +1,1088 @@
+//! Opcode, inputs and fields of every block in `project.json`
+
+use serde_json::{Map, Value as Json};
+
+use super::{music::*, pen::*, text_to_speech::*, translate::*, video_sensing::*, *};
+use crate::project::{ProjectBuilder, SpriteBuilder, StageBuilder, TargetBuilder};
+
+/// Opcode, input names and field names of a block
+#[derive(Debug, PartialEq)]
+struct Shape {
+    opcode: String,
+    inputs: Vec<String>,
+    fields: Vec<String>,
+}
+
+fn shape(opcode: &str, inputs: &[&str], fields: &[&str]) -> Shape {
+    let mut inputs: Vec<String> = inputs.iter().map(|s| s.to_string()).collect();
+    let mut fields: Vec<String> = fields.iter().map(|s| s.to_string()).collect();
+    inputs.sort();
+    fields.sort();
+    Shape {
+        opcode: opcode.to_owned(),
+        inputs,
+        fields,
+    }
+}
+
+fn shape_of(block: &Json) -> Shape {
+    let keys = |key: &str| -> Vec<String> {
+        block[key]
+            .as_object()
+            .into_iter()
+            .flatten()
+            .map(|(k, _)| k.clone())
+            .collect()
+    };
+    let mut inputs = keys("inputs");
+    let mut fields = keys("fields");
+    inputs.sort();
+    fields.sort();
+    Shape {
+        opcode: block["opcode"].as_str().unwrap().to_owned(),
+        inputs,
+        fields,
+    }
+}
+
+/// Variable `v` of the sprite, every script is built with it
+fn v() -> VarHandle<Number> {
+    VarHandle::new("v".to_owned(), Scope::Sprite)
+}
+
+/// List `l` of the sprite, every script is built with it
+fn l() -> ListHandle<Number> {
+    ListHandle::new("l".to_owned(), Scope::Sprite)
+}
+
+/// Broadcast `b` of the stage, every script is built with it
+fn b() -> BroadcastHandle {
+    BroadcastHandle::new("b".to_owned())
+}
+
+/// `project.json` with the script as the only script of a sprite
+fn project_json<S, E>(script: TypedStackBuilder<S, E>) -> Json {
+    let mut stage = StageBuilder::new(TargetBuilder::new("Stage"));
+    stage.declare_broadcast("b");
+    let mut cat = SpriteBuilder::new(TargetBuilder::new("Cat"));
+    cat.declare_var::<Number>("v", 0.);
+    cat.declare_list::<Number, f64>("l", []);
+    let project = ProjectBuilder::new()
+        .set_stage(stage)
+        .add_sprite(cat.add_script(script))
+        .build()
+        .unwrap();
+    serde_json::to_value(project.project()).unwrap()
+}
+
+/// Blocks of the script as the only script of a sprite, and the id of its top block
+fn build<S, E>(script: TypedStackBuilder<S, E>) -> (Map<String, Json>, String) {
+    let json = project_json(script);
+    let blocks = json["targets"][1]["blocks"].as_object().unwrap().clone();
+    let top = blocks
+        .iter()
+        .find(|(_, b)| b["topLevel"] == true)
+        .map(|(id, _)| id.clone())
+        .unwrap();
+    (blocks, top)
+}
+
+/// `extensions` of the project with the script as the only script of a sprite
+fn extensions<S, E>(script: TypedStackBuilder<S, E>) -> Json {
+    project_json(script)["extensions"].clone()
+}
+
+fn top<S, E>(script: TypedStackBuilder<S, E>) -> Shape {
+    let (blocks, top) = build(script);
+    shape_of(&blocks[&top])
+}
+
+/// The block that is in the input of the top block.
+/// Variable and list reporters can be stored as `[12, name, id]` and `[13, name, id]` instead.
+fn in_input<S, E>(script: TypedStackBuilder<S, E>, name: &str) -> Shape {
+    let (blocks, top) = build(script);
+    let value = &blocks[&top]["inputs"][name][1];
+    match value[0].as_u64() {
+        Some(12) => shape("data_variable", &[], &["VARIABLE"]),
+        Some(13) => shape("data_listcontents", &[], &["LIST"]),
+        _ => shape_of(&blocks[value.as_str().unwrap()]),
+    }
+}
+
+#[test]
+fn control() {
+    assert_eq!(top(wait(1u32)), shape("control_wait", &["DURATION"], &[]));
+    assert_eq!(
+        top(repeat(10u32, Some(move_steps(1)))),
+        shape("control_repeat", &["TIMES", "SUBSTACK"], &[])
+    );
+    assert_eq!(
+        top(forever(Some(move_steps(1)))),
+        shape("control_forever", &["SUBSTACK"], &[])
+    );
+    assert_eq!(
+        top(if_(mouse_down(), move_steps(1))),
+        shape("control_if", &["CONDITION", "SUBSTACK"], &[])
+    );
+    assert_eq!(
+        top(if_else(mouse_down(), move_steps(1), move_steps(2))),
+        shape(
+            "control_if_else",
+            &["CONDITION", "SUBSTACK", "SUBSTACK2"],
+            &[]
+        )
+    );
+    assert_eq!(
+        top(wait_until(mouse_down())),
+        shape("control_wait_until", &["CONDITION"], &[])
+    );
+    assert_eq!(
+        top(repeat_until(mouse_down(), Some(move_steps(1)))),
+        shape("control_repeat_until", &["CONDITION", "SUBSTACK"], &[])
+    );
+    assert_eq!(
+        top(stop(StopOption::All)),
+        shape("control_stop", &[], &["STOP_OPTION"])
+    );
+    assert_eq!(
+        top(stop_other_scripts()),
+        shape("control_stop", &[], &["STOP_OPTION"])
+    );
+    assert_eq!(
+        top(when_i_start_as_a_clone()),
+        shape("control_start_as_clone", &[], &[])
+    );
+    assert_eq!(
+        top(create_clone_of(create_clone_of_menu(Myself))),
+        shape("control_create_clone_of", &["CLONE_OPTION"], &[])
+    );
+    assert_eq!(
+        top(create_clone_of_menu(Myself).0),
+        shape("control_create_clone_of_menu", &[], &["CLONE_OPTION"])
+    );
+    assert_eq!(
+        top(delete_this_clone()),
+        shape("control_delete_this_clone", &[], &[])
+    );
+}
+
+#[test]
+fn event() {
+    assert_eq!(
+        top(when_flag_clicked()),
+        shape("event_whenflagclicked", &[], &[])
+    );
+    assert_eq!(
+        top(when_key_pressed(Key::Space)),
+        shape("event_whenkeypressed", &[], &["KEY_OPTION"])
+    );
+    assert_eq!(
+        top(when_this_sprite_clicked()),
+        shape("event_whenthisspriteclicked", &[], &[])
+    );
+    assert_eq!(
+        top(when_stage_clicked()),
+        shape("event_whenstageclicked", &[], &[])
+    );
+    assert_eq!(
+        top(when_backdrop_switches_to("backdrop1")),
+        shape("event_whenbackdropswitchesto", &[], &["BACKDROP"])
+    );
+    assert_eq!(
+        top(when_greater_than(GreaterThanOption::Timer, 10)),
+        shape(
+            "event_whengreaterthan",
+            &["VALUE"],
+            &["WHENGREATERTHANMENU"]
+        )
+    );
+    assert_eq!(
+        top(when_broadcast_received(&b())),
+        shape("event_whenbroadcastreceived", &[], &["BROADCAST_OPTION"])
+    );
+    assert_eq!(
+        top(broadcast(&b())),
+        shape("event_broadcast", &["BROADCAST_INPUT"], &[])
+    );
+    assert_eq!(
+        top(broadcast_and_wait(answer())),
+        shape("event_broadcastandwait", &["BROADCAST_INPUT"], &[])
+    );
+}
+
+#[test]
+fn broadcast_handles() {
+    let json = project_json(when_broadcast_received(&b()).next(broadcast(&b())));
+    let broadcasts = json["targets"][0]["broadcasts"].as_object().unwrap();
+    assert_eq!(broadcasts.len(), 1);
+    let (id, name) = broadcasts.iter().next().unwrap();
+    assert_eq!(name, "b");
+
+    let blocks = json["targets"][1]["blocks"].as_object().unwrap();
+    let hat = blocks.values().find(|b| b["topLevel"] == true).unwrap();
+    assert_eq!(
+        hat["fields"]["BROADCAST_OPTION"],
+        serde_json::json!(["b", id])
+    );
+    let sender = &blocks[hat["next"].as_str().unwrap()];
+    let input = &sender["inputs"]["BROADCAST_INPUT"];
+    assert_eq!(input[0], 1);
+    let menu = &blocks[input[1].as_str().unwrap()];
+    assert_eq!(
+        shape_of(menu),
+        shape("event_broadcast_menu", &[], &["BROADCAST_OPTION"])
+    );
+    assert_eq!(menu["shadow"], true);
+    assert_eq!(
+        menu["fields"]["BROADCAST_OPTION"],
+        serde_json::json!(["b", id])
+    );
+}
+
+#[test]
+fn looks() {
+    assert_eq!(top(say("hi")), shape("looks_say", &["MESSAGE"], &[]));
+    assert_eq!(
+        top(say_for_secs("hi", 2)),
+        shape("looks_sayforsecs", &["MESSAGE", "SECS"], &[])
+    );
+    assert_eq!(top(think("hm")), shape("looks_think", &["MESSAGE"], &[]));
+    assert_eq!(
+        top(think_for_secs("hm", 2)),
+        shape("looks_thinkforsecs", &["MESSAGE", "SECS"], &[])
+    );
+    assert_eq!(
+        top(switch_costume_to(costume_menu("costume1"))),
+        shape("looks_switchcostumeto", &["COSTUME"], &[])
+    );
+    assert_eq!(
+        top(costume_menu("costume1").0),
+        shape("looks_costume", &[], &["COSTUME"])
+    );
+    assert_eq!(top(next_costume()), shape("looks_nextcostume", &[], &[]));
+    assert_eq!(
+        top(switch_backdrop_to(backdrop_menu("backdrop1"))),
+        shape("looks_switchbackdropto", &["BACKDROP"], &[])
+    );
+    assert_eq!(
+        top(switch_backdrop_to_and_wait(backdrop_menu("backdrop1"))),
+        shape("looks_switchbackdroptoandwait", &["BACKDROP"], &[])
+    );
+    assert_eq!(
+        top(backdrop_menu("backdrop1").0),
+        shape("looks_backdrops", &[], &["BACKDROP"])
+    );
+    assert_eq!(top(next_backdrop()), shape("looks_nextbackdrop", &[], &[]));
+    assert_eq!(
+        top(change_size_by(10)),
+        shape("looks_changesizeby", &["CHANGE"], &[])
+    );
+    assert_eq!(
+        top(set_size_to(100)),
+        shape("looks_setsizeto", &["SIZE"], &[])
+    );
+    assert_eq!(
+        top(change_looks_effect_by(LooksEffect::Color, 25)),
+        shape("looks_changeeffectby", &["CHANGE"], &["EFFECT"])
+    );
+    assert_eq!(
+        top(set_looks_effect_to(LooksEffect::Color, 0)),
+        shape("looks_seteffectto", &["VALUE"], &["EFFECT"])
+    );
+    assert_eq!(
+        top(clear_graphic_effects()),
+        shape("looks_cleargraphiceffects", &[], &[])
+    );
+    assert_eq!(top(show()), shape("looks_show", &[], &[]));
+    assert_eq!(top(hide()), shape("looks_hide", &[], &[]));
+    assert_eq!(
+        top(go_to_layer(LayerPosition::Front)),
+        shape("looks_gotofrontback", &[], &["FRONT_BACK"])
+    );
+    assert_eq!(
+        top(change_layer(LayerDirection::Forward, 1)),
+        shape(
+            "looks_goforwardbackwardlayers",
+            &["NUM"],
+            &["FORWARD_BACKWARD"]
+        )
+    );
+    let costume_number_name = shape("looks_costumenumbername", &[], &["NUMBER_NAME"]);
+    assert_eq!(top(costume(NumberName::Name).0), costume_number_name);
+    assert_eq!(top(costume_number().0), costume_number_name);
+    assert_eq!(top(costume_name().0), costume_number_name);
+    let backdrop_number_name = shape("looks_backdropnumbername", &[], &["NUMBER_NAME"]);
+    assert_eq!(top(backdrop(NumberName::Name).0), backdrop_number_name);
+    assert_eq!(top(backdrop_number().0), backdrop_number_name);
+    assert_eq!(top(backdrop_name().0), backdrop_number_name);
+    assert_eq!(top(size().0), shape("looks_size", &[], &[]));
+}
+
+#[test]
+fn motion() {
+    assert_eq!(
+        top(move_steps(10)),
+        shape("motion_movesteps", &["STEPS"], &[])
+    );
+    assert_eq!(
+        top(turn_right(15)),
+        shape("motion_turnright", &["DEGREES"], &[])
+    );
+    assert_eq!(
+        top(turn_left(15)),
+        shape("motion_turnleft", &["DEGREES"], &[])
+    );
+    assert_eq!(
+        top(go_to(go_to_menu(Mouse))),
+        shape("motion_goto", &["TO"], &[])
+    );
+    assert_eq!(
+        top(go_to_menu(Mouse).0),
+        shape("motion_goto_menu", &[], &["TO"])
+    );
+    assert_eq!(top(goto_xy(0, 0)), shape("motion_gotoxy", &["X", "Y"], &[]));
+    assert_eq!(
+        top(glide_to(1, glide_to_menu(Random))),
+        shape("motion_glideto", &["SECS", "TO"], &[])
+    );
+    assert_eq!(
+        top(glide_to_menu(Random).0),
+        shape("motion_glideto_menu", &[], &["TO"])
+    );
+    assert_eq!(
+        top(glide_to_xy(1, 0, 0)),
+        shape("motion_glidesecstoxy", &["SECS", "X", "Y"], &[])
+    );
+    assert_eq!(
+        top(point_in_direction(90)),
+        shape("motion_pointindirection", &["DIRECTION"], &[])
+    );
+    assert_eq!(
+        top(point_towards(point_towards_menu(Mouse))),
+        shape("motion_pointtowards", &["TOWARDS"], &[])
+    );
+    assert_eq!(
+        top(point_towards_menu(Mouse).0),
+        shape("motion_pointtowards_menu", &[], &["TOWARDS"])
+    );
+    assert_eq!(top(set_x(0)), shape("motion_setx", &["X"], &[]));
+    assert_eq!(
+        top(change_x_by(10)),
+        shape("motion_changexby", &["DX"], &[])
+    );
+    assert_eq!(top(set_y(0)), shape("motion_sety", &["Y"], &[]));
+    assert_eq!(
+        top(change_y_by(10)),
+        shape("motion_changeyby", &["DY"], &[])
+    );
+    assert_eq!(
+        top(if_on_edge_bounce()),
+        shape("motion_ifonedgebounce", &[], &[])
+    );
+    assert_eq!(
+        top(set_rotation_style(RotationStyle::LeftRight)),
+        shape("motion_setrotationstyle", &[], &["STYLE"])
+    );
+    assert_eq!(top(direction().0), shape("motion_direction", &[], &[]));
+    assert_eq!(top(x_position().0), shape("motion_xposition", &[], &[]));
+    assert_eq!(top(y_position().0), shape("motion_yposition", &[], &[]));
+}
+
+#[test]
+fn operators() {
+    let num1_num2 = |opcode| shape(opcode, &["NUM1", "NUM2"], &[]);
+    assert_eq!(top(add(1, 2).0), num1_num2("operator_add"));
+    assert_eq!(top(sub(1, 2).0), num1_num2("operator_subtract"));
+    assert_eq!(top(mul(1, 2).0), num1_num2("operator_multiply"));
+    assert_eq!(top(div(1, 2).0), num1_num2("operator_divide"));
+    assert_eq!(top(modulo(1, 2).0), num1_num2("operator_mod"));
+    assert_eq!(
+        top(random(1, 10).0),
+        shape("operator_random", &["FROM", "TO"], &[])
+    );
+    let operand1_operand2 = |opcode| shape(opcode, &["OPERAND1", "OPERAND2"], &[]);
+    assert_eq!(top(less_than("a", "b").0), operand1_operand2("operator_lt"));
+    assert_eq!(
+        top(greater_than("a", "b").0),
+        operand1_operand2("operator_gt")
+    );
+    assert_eq!(
+        top(equals("a", "b").0),
+        operand1_operand2("operator_equals")
+    );
+    assert_eq!(
+        top(and(mouse_down(), loud()).0),
+        operand1_operand2("operator_and")
+    );
+    assert_eq!(
+        top(or(mouse_down(), loud()).0),
+        operand1_operand2("operator_or")
+    );
+    assert_eq!(
+        top(not(mouse_down()).0),
+        shape("operator_not", &["OPERAND"], &[])
+    );
+    assert_eq!(
+        top(join("a", "b").0),
+        shape("operator_join", &["STRING1", "STRING2"], &[])
+    );
+    assert_eq!(
+        top(letter_of(1u32, "abc").0),
+        shape("operator_letter_of", &["LETTER", "STRING"], &[])
+    );
+    assert_eq!(
+        top(length_of("abc").0),
+        shape("operator_length", &["STRING"], &[])
+    );
+    assert_eq!(
+        top(contains("abc", "a").0),
+        shape("operator_contains", &["STRING1", "STRING2"], &[])
+    );
+    assert_eq!(top(round(1.5).0), shape("operator_round", &["NUM"], &[]));
+    assert_eq!(
+        top(math_op(MathOp::Abs, -1).0),
+        shape("operator_mathop", &["NUM"], &["OPERATOR"])
+    );
+}
+
+#[test]
+fn operator_overloads() {
+    let num1_num2 = |opcode| shape(opcode, &["NUM1", "NUM2"], &[]);
+    let expr = || (x_position() * 2 + 10).0;
+    assert_eq!(top(expr()), num1_num2("operator_add"));
+    assert_eq!(in_input(expr(), "NUM1"), num1_num2("operator_multiply"));
+    let diff: JustReporter<Number> = 100 - y_position();
+    assert_eq!(top(diff.0), num1_num2("operator_subtract"));
+    assert_eq!(
+        in_input((-x_position()).0, "NUM2"),
+        shape("motion_xposition", &[], &[])
+    );
+    assert_eq!(top((x_position() / 2).0), num1_num2("operator_divide"));
+    assert_eq!(top((x_position() % 2).0), num1_num2("operator_mod"));
+
+    let operand1_operand2 = |opcode| shape(opcode, &["OPERAND1", "OPERAND2"], &[]);
+    assert_eq!(
+        top((mouse_down() & loud()).0),
+        operand1_operand2("operator_and")
+    );
+    assert_eq!(
+        top((mouse_down() | loud()).0),
+        operand1_operand2("operator_or")
+    );
+    assert_eq!(
+        top((!mouse_down()).0),
+        shape("operator_not", &["OPERAND"], &[])
+    );
+
+    assert_eq!(top(x_position().lt(1).0), operand1_operand2("operator_lt"));
+    assert_eq!(top(x_position().gt(1).0), operand1_operand2("operator_gt"));
+    assert_eq!(
+        top(x_position().eq_(1).0),
+        operand1_operand2("operator_equals")
+    );
+    for (cmp, lowered) in [
+        (x_position().le(1), "operator_lt"),
+        (x_position().ge(1), "operator_lt"),
+        (x_position().ne_(1), "operator_equals"),
+    ] {
+        assert_eq!(top(cmp.0.clone()), shape("operator_not", &["OPERAND"], &[]));
+        assert_eq!(in_input(cmp.0, "OPERAND"), operand1_operand2(lowered));
+    }
+    // `x <= 1` is `not (1 < x)`
+    let (blocks, top) = build(x_position().le(1).0);
+    let lt = &blocks[blocks[&top]["inputs"]["OPERAND"][1].as_str().unwrap()];
+    assert_eq!(lt["inputs"]["OPERAND1"][1], serde_json::json!([4, "1"]));
+}
+
+#[test]
+fn sensing() {
+    assert_eq!(
+        top(touching(touching_menu(Edge)).0),
+        shape("sensing_touchingobject", &["TOUCHINGOBJECTMENU"], &[])
+    );
+    assert_eq!(
+        top(touching_menu(Edge).0),
+        shape("sensing_touchingobjectmenu", &[], &["TOUCHINGOBJECTMENU"])
+    );
+    assert_eq!(
+        top(touching_color("#ff0000").0),
+        shape("sensing_touchingcolor", &["COLOR"], &[])
+    );
+    assert_eq!(
+        top(color_touching_color("#ff0000", "#00ff00").0),
+        shape("sensing_coloristouchingcolor", &["COLOR", "COLOR2"], &[])
+    );
+    assert_eq!(
+        top(distance_to(distance_to_menu(Mouse)).0),
+        shape("sensing_distanceto", &["DISTANCETOMENU"], &[])
+    );
+    assert_eq!(
+        top(distance_to_menu(Mouse).0),
+        shape("sensing_distancetomenu", &[], &["DISTANCETOMENU"])
+    );
+    assert_eq!(
+        top(ask_and_wait("name?")),
+        shape("sensing_askandwait", &["QUESTION"], &[])
+    );
+    assert_eq!(top(answer().0), shape("sensing_answer", &[], &[]));
+    assert_eq!(
+        top(key_pressed(key_menu(Key::Space)).0),
+        shape("sensing_keypressed", &["KEY_OPTION"], &[])
+    );
+    assert_eq!(
+        in_input(key_pressed(key_menu(Key::Space)).0, "KEY_OPTION"),
+        shape("sensing_keyoptions", &[], &["KEY_OPTION"])
+    );
+    assert_eq!(top(mouse_down().0), shape("sensing_mousedown", &[], &[]));
+    assert_eq!(top(mouse_x().0), shape("sensing_mousex", &[], &[]));
+    assert_eq!(top(mouse_y().0), shape("sensing_mousey", &[], &[]));
+    assert_eq!(
+        top(set_drag_mode(DragMode::Draggable)),
+        shape("sensing_setdragmode", &[], &["DRAG_MODE"])
+    );
+    assert_eq!(top(loudness().0), shape("sensing_loudness", &[], &[]));
+    assert_eq!(top(loud().0), shape("sensing_loud", &[], &[]));
+    assert_eq!(top(timer().0), shape("sensing_timer", &[], &[]));
+    assert_eq!(top(reset_timer()), shape("sensing_resettimer", &[], &[]));
+    assert_eq!(
+        top(var_of("x position", var_of_object_menu(Stage)).0),
+        shape("sensing_of", &["OBJECT"], &["PROPERTY"])
+    );
+    assert_eq!(
+        top(var_of_object_menu(Stage).0),
+        shape("sensing_of_object_menu", &[], &["OBJECT"])
+    );
+    assert_eq!(
+        top(current_datetime(DateTimeUnit::Year).0),
+        shape("sensing_current", &[], &["CURRENTMENU"])
+    );
+    assert_eq!(
+        top(days_since_2000().0),
+        shape("sensing_dayssince2000", &[], &[])
+    );
+    assert_eq!(top(username().0), shape("sensing_username", &[], &[]));
+}
+
+#[test]
+fn sound() {
+    assert_eq!(
+        top(play_sound_until_done("Meow")),
+        shape("sound_playuntildone", &["SOUND_MENU"], &[])
+    );
+    assert_eq!(
+        top(play_sound(sound_menu("Meow"))),
+        shape("sound_play", &["SOUND_MENU"], &[])
+    );
+    let sounds_menu = shape("sound_sounds_menu", &[], &["SOUND_MENU"]);
+    assert_eq!(in_input(play_sound("Meow"), "SOUND_MENU"), sounds_menu);
+    assert_eq!(
+        in_input(play_sound_until_done("Meow"), "SOUND_MENU"),
+        sounds_menu
+    );
+    assert_eq!(top(sound_menu("Meow").0), sounds_menu);
+    assert_eq!(
+        top(stop_all_sound()),
+        shape("sound_stopallsounds", &[], &[])
+    );
+    assert_eq!(
+        top(change_sound_effect_by(SoundEffect::Pitch, 10)),
+        shape("sound_changeeffectby", &["VALUE"], &["EFFECT"])
+    );
+    assert_eq!(
+        top(set_sound_effect_to(SoundEffect::Pitch, 100)),
+        shape("sound_seteffectto", &["VALUE"], &["EFFECT"])
+    );
+    assert_eq!(
+        top(clear_sound_effects()),
+        shape("sound_cleareffects", &[], &[])
+    );
+    assert_eq!(
+        top(set_volume_to(100)),
+        shape("sound_setvolumeto", &["VOLUME"], &[])
+    );
+    assert_eq!(
+        top(change_volume_by(-10)),
+        shape("sound_changevolumeby", &["VOLUME"], &[])
+    );
+    assert_eq!(top(volume().0), shape("sound_volume", &[], &[]));
+}
+
+#[test]
+fn data() {
+    let (v, l) = (v(), l());
+    assert_eq!(
+        in_input(set_var_to(&v, var(&v)), "VALUE"),
+        shape("data_variable", &[], &["VARIABLE"])
+    );
+    assert_eq!(
+        in_input(say(list(&l)), "MESSAGE"),
+        shape("data_listcontents", &[], &["LIST"])
+    );
+    assert_eq!(
+        top(set_var_to(&v, 1)),
+        shape("data_setvariableto", &["VALUE"], &["VARIABLE"])
+    );
+    assert_eq!(
+        top(change_var_by(&v, 1)),
+        shape("data_changevariableby", &["VALUE"], &["VARIABLE"])
+    );
+    assert_eq!(
+        top(show_var(&v)),
+        shape("data_showvariable", &[], &["VARIABLE"])
+    );
+    assert_eq!(
+        top(hide_var(&v)),
+        shape("data_hidevariable", &[], &["VARIABLE"])
+    );
+    assert_eq!(
+        top(add_to_list(&l, 1)),
+        shape("data_addtolist", &["ITEM"], &["LIST"])
+    );
+    assert_eq!(
+        top(delete_in_list(&l, 1)),
+        shape("data_deleteoflist", &["INDEX"], &["LIST"])
+    );
+    assert_eq!(
+        top(delete_all_in_list(&l)),
+        shape("data_deletealloflist", &[], &["LIST"])
+    );
+    assert_eq!(
+        top(insert_in_list(&l, 1, 2)),
+        shape("data_insertatlist", &["INDEX", "ITEM"], &["LIST"])
+    );
+    assert_eq!(
+        top(replace_in_list(&l, 1, 2)),
+        shape("data_replaceitemoflist", &["INDEX", "ITEM"], &["LIST"])
+    );
+    assert_eq!(
+        top(item_in_list(&l, 1).0),
+        shape("data_itemoflist", &["INDEX"], &["LIST"])
+    );
+    assert_eq!(
+        top(count_of_item_in_list(&l, 2).0),
+        shape("data_itemnumoflist", &["ITEM"], &["LIST"])
+    );
+    assert_eq!(
+        top(length_of_list(&l).0),
+        shape("data_lengthoflist", &[], &["LIST"])
+    );
+    assert_eq!(
+        top(list_contains(&l, 2).0),
+        shape("data_listcontainsitem", &["ITEM"], &["LIST"])
+    );
+    assert_eq!(top(show_list(&l)), shape("data_showlist", &[], &["LIST"]));
+    assert_eq!(top(hide_list(&l)), shape("data_hidelist", &[], &["LIST"]));
+}
+
+#[test]
+fn pen_extension() {
+    assert_eq!(top(erase_all()), shape("pen_clear", &[], &[]));
+    assert_eq!(top(stamp()), shape("pen_stamp", &[], &[]));
+    assert_eq!(top(pen_down()), shape("pen_penDown", &[], &[]));
+    assert_eq!(top(pen_up()), shape("pen_penUp", &[], &[]));
+    assert_eq!(
+        top(set_pen_color_to("#ff0000")),
+        shape("pen_setPenColorToColor", &["COLOR"], &[])
+    );
+    let color_param_menu = shape("pen_menu_colorParam", &[], &["colorParam"]);
+    assert_eq!(
+        top(change_pen_param_by(PenColorParam::Color, 10)),
+        shape("pen_changePenColorParamBy", &["COLOR_PARAM", "VALUE"], &[])
+    );
+    assert_eq!(
+        in_input(change_pen_param_by(PenColorParam::Color, 10), "COLOR_PARAM"),
+        color_param_menu
+    );
+    assert_eq!(
+        top(set_pen_param_to(PenColorParam::Color, 50)),
+        shape("pen_setPenColorParamTo", &["COLOR_PARAM", "VALUE"], &[])
+    );
+    assert_eq!(
+        in_input(set_pen_param_to(PenColorParam::Color, 50), "COLOR_PARAM"),
+        color_param_menu
+    );
+    assert_eq!(
+        top(change_pen_size_by(1)),
+        shape("pen_changePenSizeBy", &["SIZE"], &[])
+    );
+    assert_eq!(
+        top(set_pen_size_to(1)),
+        shape("pen_setPenSizeTo", &["SIZE"], &[])
+    );
+    assert_eq!(extensions(pen_down()), serde_json::json!(["pen"]));
+    assert_eq!(extensions(say("hi")), serde_json::json!([]));
+}
+
+#[test]
+fn music_extension() {
+    assert_eq!(
+        top(play_drum_for_beats(Drum::SnareDrum, 0.25)),
+        shape("music_playDrumForBeats", &["DRUM", "BEATS"], &[])
+    );
+    assert_eq!(
+        in_input(play_drum_for_beats(Drum::SnareDrum, 0.25), "DRUM"),
+        shape("music_menu_DRUM", &[], &["DRUM"])
+    );
+    assert_eq!(
+        top(rest_for_beats(0.25)),
+        shape("music_restForBeats", &["BEATS"], &[])
+    );
+    assert_eq!(
+        top(play_note_for_beats(MidiNote::MIDDLE_C, 0.25)),
+        shape("music_playNoteForBeats", &["NOTE", "BEATS"], &[])
+    );
+    assert_eq!(
+        in_input(play_note_for_beats(MidiNote::MIDDLE_C, 0.25), "NOTE"),
+        shape("note", &[], &["NOTE"])
+    );
+    assert_eq!(
+        top(set_instrument(Instrument::Piano)),
+        shape("music_setInstrument", &["INSTRUMENT"], &[])
+    );
+    assert_eq!(
+        in_input(set_instrument(Instrument::Piano), "INSTRUMENT"),
+        shape("music_menu_INSTRUMENT", &[], &["INSTRUMENT"])
+    );
+    assert_eq!(top(set_tempo(60)), shape("music_setTempo", &["TEMPO"], &[]));
+    assert_eq!(
+        top(change_tempo(20)),
+        shape("music_changeTempo", &["TEMPO"], &[])
+    );
+    assert_eq!(top(tempo().0), shape("music_getTempo", &[], &[]));
+}
+
+#[test]
+fn midi_note_from_octave() {
+    assert_eq!(MidiNote::from_octave(4, 0), Some(MidiNote::MIDDLE_C));
+    assert_eq!(MidiNote::from_octave(0, 0), MidiNote::new(12));
+    assert_eq!(MidiNote::from_octave(4, 11), MidiNote::new(71));
+    assert_eq!(MidiNote::from_octave(9, 10), Some(MidiNote::HIGHEST));
+    assert_eq!(MidiNote::from_octave(9, 11), None);
+    assert_eq!(MidiNote::from_octave(4, 12), None);
+    assert_eq!(MidiNote::from_octave(21, 0), None);
+    assert_eq!(MidiNote::from_octave(u8::MAX, 0), None);
+}
+
+#[test]
+fn midi_note_new() {
+    assert_eq!(MidiNote::new(60), Some(MidiNote::MIDDLE_C));
+    assert_eq!(MidiNote::new(130).map(MidiNote::number), Some(130));
+    assert_eq!(MidiNote::new(131), None);
+    assert_eq!(MidiNote::new(u8::MAX), None);
+}
+
+#[test]
+fn text_to_speech_extension() {
+    assert_eq!(
+        top(speak_and_wait("hello")),
+        shape("text2speech_speakAndWait", &["WORDS"], &[])
+    );
+    assert_eq!(
+        top(set_voice(Voice::Alto)),
+        shape("text2speech_setVoice", &["VOICE"], &[])
+    );
+    assert_eq!(
+        in_input(set_voice(Voice::Alto), "VOICE"),
+        shape("text2speech_menu_voices", &[], &["voices"])
+    );
+    assert_eq!(
+        top(set_language(SpeechLanguage::Arabic)),
+        shape("text2speech_setLanguage", &["LANGUAGE"], &[])
+    );
+    assert_eq!(
+        in_input(set_language(SpeechLanguage::Arabic), "LANGUAGE"),
+        shape("text2speech_menu_languages", &[], &["languages"])
+    );
+    assert_eq!(
+        extensions(speak_and_wait("hello")),
+        serde_json::json!(["text2speech"])
+    );
+}
+
+#[test]
+fn translate_extension() {
+    assert_eq!(
+        top(translate("hello", TranslateLanguage::Amharic).0),
+        shape("translate_getTranslate", &["WORDS", "LANGUAGE"], &[])
+    );
+    assert_eq!(
+        in_input(translate("hello", TranslateLanguage::Amharic).0, "LANGUAGE"),
+        shape("translate_menu_languages", &[], &["languages"])
+    );
+    assert_eq!(
+        top(viewer_language().0),
+        shape("translate_getViewerLanguage", &[], &[])
+    );
+    assert_eq!(
+        extensions(say(translate("hello", TranslateLanguage::Amharic))),
+        serde_json::json!(["translate"])
+    );
+    assert_eq!(
+        extensions(speak_and_wait(join(viewer_language(), "!"))),
+        serde_json::json!(["text2speech", "translate"])
+    );
+}
+
+#[test]
+fn video_sensing_extension() {
+    assert_eq!(
+        top(when_motion_greater_than(10)),
+        shape("videoSensing_whenMotionGreaterThan", &["REFERENCE"], &[])
+    );
+    let video_on = || video_on(VideoAttribute::Motion, VideoSubject::Sprite).0;
+    assert_eq!(
+        top(video_on()),
+        shape("videoSensing_videoOn", &["ATTRIBUTE", "SUBJECT"], &[])
+    );
+    assert_eq!(
+        in_input(video_on(), "ATTRIBUTE"),
+        shape("videoSensing_menu_ATTRIBUTE", &[], &["ATTRIBUTE"])
+    );
+    assert_eq!(
+        in_input(video_on(), "SUBJECT"),
+        shape("videoSensing_menu_SUBJECT", &[], &["SUBJECT"])
+    );
+    assert_eq!(
+        top(turn_video(VideoState::Off)),
+        shape("videoSensing_videoToggle", &["VIDEO_STATE"], &[])
+    );
+    assert_eq!(
+        in_input(turn_video(VideoState::Off), "VIDEO_STATE"),
+        shape("videoSensing_menu_VIDEO_STATE", &[], &["VIDEO_STATE"])
+    );
+    assert_eq!(
+        top(set_video_transparency(50)),
+        shape("videoSensing_setVideoTransparency", &["TRANSPARENCY"], &[])
+    );
+    assert_eq!(
+        extensions(when_motion_greater_than(10).next(say("moved"))),
+        serde_json::json!(["videoSensing"])
+    );
+}
+
+#[test]
+fn menu_inputs() {
+    /// The input of the top block, with the blocks it refers to
+    fn menu<S, E>(script: TypedStackBuilder<S, E>, name: &str) -> Vec<Json> {
+        let (blocks, top) = build(script);
+        let input = blocks[&top]["inputs"][name].as_array().unwrap().clone();
+        input
+            .into_iter()
+            .map(|v| match v.as_str() {
+                Some(id) => blocks[id].clone(),
+                None => v,
+            })
+            .collect()
+    }
+
+    let cat = SpriteHandle::new("Cat".to_owned());
+    let input = menu(go_to(&cat), "TO");
+    assert_eq!(input[0], 1);
+    assert_eq!(shape_of(&input[1]), shape("motion_goto_menu", &[], &["TO"]));
+    assert_eq!(input[1]["fields"]["TO"][0], "Cat");
+    assert_eq!(input[1]["shadow"], true);
+
+    let targets = [
+        menu(go_to(Random), "TO")[1]["fields"]["TO"][0].clone(),
+        menu(glide_to(1, Mouse), "TO")[1]["fields"]["TO"][0].clone(),
+        menu(point_towards(Mouse), "TOWARDS")[1]["fields"]["TOWARDS"][0].clone(),
+        menu(touching(Edge).0, "TOUCHINGOBJECTMENU")[1]["fields"]["TOUCHINGOBJECTMENU"][0].clone(),
+        menu(distance_to(Mouse).0, "DISTANCETOMENU")[1]["fields"]["DISTANCETOMENU"][0].clone(),
+        menu(create_clone_of(Myself), "CLONE_OPTION")[1]["fields"]["CLONE_OPTION"][0].clone(),
+        menu(var_of("volume", Stage).0, "OBJECT")[1]["fields"]["OBJECT"][0].clone(),
+        menu(key_pressed(Key::Space).0, "KEY_OPTION")[1]["fields"]["KEY_OPTION"][0].clone(),
+        menu(switch_costume_to("costume2"), "COSTUME")[1]["fields"]["COSTUME"][0].clone(),
+    ];
+    assert_eq!(
+        targets,
+        [
+            "_random_", "_mouse_", "_mouse_", "_edge_", "_mouse_", "_myself_", "_stage_", "space",
+            "costume2"
+        ]
+    );
+
+    // Reporter covers the shadow menu
+    let input = menu(go_to(answer()), "TO");
+    assert_eq!(input[0], 3);
+    assert_eq!(input[1]["opcode"], "sensing_answer");
+    assert_eq!(shape_of(&input[2]), shape("motion_goto_menu", &[], &["TO"]));
+    assert_eq!(input[2]["fields"]["TO"][0], "_random_");
+    assert_eq!(input[2]["shadow"], true);
+
+    // Number reporter covers the costume menu
+    let input = menu(switch_costume_to(random(1, 3)), "COSTUME");
+    assert_eq!(input[0], 3);
+    assert_eq!(input[1]["opcode"], "operator_random");
+    assert_eq!(
+        shape_of(&input[2]),
+        shape("looks_costume", &[], &["COSTUME"])
+    );
+    assert_eq!(input[2]["fields"]["COSTUME"][0], "costume1");
+
+    // So do variables and custom block arguments
+    let value = VarHandle::<Value>::new("v".to_owned(), Scope::Sprite);
+    let input = menu(create_clone_of(&value), "CLONE_OPTION");
+    assert_eq!(input[0], 3);
+    assert_eq!(input[1]["opcode"], "data_variable");
+    assert_eq!(input[2]["fields"]["CLONE_OPTION"][0], "_myself_");
+    let jump = crate::scripting::custom_block::CustomBlockBuilder::new()
+        .text("jump to")
+        .arg::<Value>("target")
+        .build();
+    let input = menu(go_to(jump.arg_string_number("target")), "TO");
+    assert_eq!(input[0], 3);
+    assert_eq!(input[1]["opcode"], "argument_reporter_string_number");
+    assert_eq!(input[2]["fields"]["TO"][0], "_random_");
+
+    // Menu reporter is the shadow menu
+    let input = menu(play_sound(sound_menu("Meow")), "SOUND_MENU");
+    assert_eq!(input[0], 1);
+    assert_eq!(input[1]["fields"]["SOUND_MENU"][0], "Meow");
+}
+
+#[test]
+fn menu_options() {
+    /// Value of the field of the top block
+    fn field<S, E>(script: TypedStackBuilder<S, E>, name: &str) -> Json {
+        let (blocks, top) = build(script);
+        blocks[&top]["fields"][name][0].clone()
+    }
+
+    assert_eq!(
+        field(stop(StopOption::ThisScript), "STOP_OPTION"),
+        "this script"
+    );
+    assert_eq!(
+        field(set_rotation_style(RotationStyle::LeftRight), "STYLE"),
+        "left-right"
+    );
+    assert_eq!(
+        field(change_looks_effect_by(LooksEffect::Ghost, 10), "EFFECT"),
+        "GHOST"
+    );
+    // Raw strings are put in as is
+    assert_eq!(
+        field(change_looks_effect_by(RawField("foward"), 10), "EFFECT"),
+        "foward"
+    );
+
+    assert_eq!(Key::parse("left arrow"), Some(Key::LeftArrow));
+    assert_eq!(Key::parse("foward"), None);
+    assert_eq!(MathOp::parse(MathOp::Sqrt.as_str()), Some(MathOp::Sqrt));
+}
+
+#[test]
+fn obscured_shadows() {
+    let input = |script: StackBlock, name: &str| {
+        let (blocks, top) = build(script);
+        blocks[&top]["inputs"][name].clone()
+    };
+
+    // Literal is the shadow
+    assert_eq!(
+        input(move_steps(5), "STEPS"),
+        serde_json::json!([1, [4, "5"]])
+    );
+    // Reporter keeps the default shadow of the block under it
+    let steps = input(move_steps(x_position()), "STEPS");
+    assert_eq!(steps[0], 3);
+    assert!(steps[1].is_string());
+    assert_eq!(steps[2], serde_json::json!([4, "10"]));
+
+    let message = input(say(answer()), "MESSAGE");
+    assert_eq!(message[0], 3);
+    assert_eq!(message[2], serde_json::json!([10, "Hello!"]));
+
+    let value = input(set_var_to(&v(), &v()), "VALUE");
+    assert_eq!(value[0], 3);
+    assert_eq!(value[2], serde_json::json!([10, "0"]));
+
+    let times = input(repeat(costume_number(), Some(move_steps(1))), "TIMES");
+    assert_eq!(times[0], 3);
+    assert_eq!(times[2], serde_json::json!([6, "10"]));
+
+    // Shadow of a note is a block
+    let (blocks, top) = build(play_note_for_beats(tempo(), 1));
+    let note = &blocks[&top]["inputs"]["NOTE"];
+    assert_eq!(note[0], 3);
+    let shadow = &blocks[note[2].as_str().unwrap()];
+    assert_eq!(shape_of(shadow), shape("note", &[], &["NOTE"]));
+    assert_eq!(shadow["fields"]["NOTE"][0], "60");
+
+    // Broadcast computed at runtime keeps the broadcast menu under it
+    let (blocks, top) = build(broadcast(answer()));
+    let message = &blocks[&top]["inputs"]["BROADCAST_INPUT"];
+    assert_eq!(message[0], 3);
+    let shadow = &blocks[message[2].as_str().unwrap()];
+    assert_eq!(
+        shape_of(shadow),
+        shape("event_broadcast_menu", &[], &["BROADCAST_OPTION"])
+    );
+    // the first broadcast of the stage is selected in it
+    assert_eq!(shadow["fields"]["BROADCAST_OPTION"][0], "b");
+
+    // Text arguments of custom blocks have an empty text shadow, boolean ones have none
+    let jump = crate::scripting::custom_block::CustomBlockBuilder::new()
+        .text("jump")
+        .arg::<Value>("height")
+        .arg::<Bool>("spin")
+        .build();
+    let (blocks, top) = build(jump.call((answer(), mouse_down())));
+    let arg = |opcode: &str| {
+        let inputs = blocks[&top]["inputs"].as_object().unwrap();
+        let is_input_of = |input: &&Json| blocks[input[1].as_str().unwrap()]["opcode"] == opcode;
+        inputs.values().find(is_input_of).unwrap().clone()
+    };
+    let height = arg("sensing_answer");
+    assert_eq!(height[0], 3);
+    assert_eq!(height[2], serde_json::json!([10, ""]));
+    assert_eq!(arg("sensing_mousedown")[0], 2);
+}
+
+crate::extension_blocks! {
+    extension "lights";
+
+    #[opcode("whenButtonPressed")]
+    fn when_button_pressed(#[field("BUTTON")] button: (IntoField)) -> HatBlock;
+
+    #[opcode("setColor")]
+    fn set_light_color(
+        #[menu("LIGHT", "lights")] light: (IntoField),
+        #[input("COLOR")] color: (IntoInput<Color>) = "#ff0000"
+    ) -> StackBlock;
+
+    #[opcode("colorOf")]
+    fn light_color(#[menu("LIGHT", "lights")] light: (IntoField)) -> JustReporter<Color>;
+}
+
+#[test]
+fn custom_extension() {
+    assert_eq!(
+        top(when_button_pressed("A")),
+        shape("lights_whenButtonPressed", &[], &["BUTTON"])
+    );
+    let set_color = || set_light_color("left", light_color("right"));
+    assert_eq!(
+        top(set_color()),
+        shape("lights_setColor", &["COLOR", "LIGHT"], &[])
+    );
+    assert_eq!(
+        in_input(set_color(), "LIGHT"),
+        shape("lights_menu_lights", &[], &["lights"])
+    );
+    assert_eq!(
+        in_input(set_color(), "COLOR"),
+        shape("lights_colorOf", &["LIGHT"], &[])
+    );
+    let (blocks, top) = build(set_color());
+    assert_eq!(
+        blocks[&top]["inputs"]["COLOR"][2],
+        serde_json::json!([9, "#ff0000"])
+    );
+
+    let cat = SpriteBuilder::new(TargetBuilder::new("Cat"))
+        .add_script(when_button_pressed("A").next(set_color()));
+    let project = ProjectBuilder::new().add_sprite(cat).build().unwrap();
+    let json = serde_json::to_value(project.project()).unwrap();
+    assert_eq!(json["extensions"], serde_json::json!(["lights"]));
+}
@@ -0,0 +1,46 @@
+//! Text to Speech extension blocks.
+//!
+//! Using any of these adds `"text2speech"` to the project's extensions when it's built.
+
+use sb_itchy::{
+    block::{BlockFieldBuilder, BlockInputBuilder, BlockNormalBuilder},
+    stack::StackBuilder,
+};
+
+use super::menu_input;
+use crate::scripting::{arg::*, menu::*, stack::*};
+
+simple_typed_block_def! {
+    speak_and_wait(words: (IntoInput<Text>) = "hello") -> StackBlock
+    set_voice(voice: (IntoField<Voice>)) -> StackBlock
+    set_language(language: (IntoField<SpeechLanguage>)) -> StackBlock
+}
+
+/// Untyped text to speech blocks, in the same shape as [`sb_itchy::blocks`]
+mod blocks {
+    use super::*;
+
+    pub fn speak_and_wait(words: BlockInputBuilder) -> StackBuilder {
+        let mut b = BlockNormalBuilder::new("text2speech_speakAndWait");
+        b.add_input("WORDS", words);
+        StackBuilder::start(b)
+    }
+
+    pub fn set_voice(voice: BlockFieldBuilder) -> StackBuilder {
+        let mut b = BlockNormalBuilder::new("text2speech_setVoice");
+        b.add_input(
+            "VOICE",
+            menu_input("text2speech_menu_voices", "voices", voice),
+        );
+        StackBuilder::start(b)
+    }
+
+    pub fn set_language(language: BlockFieldBuilder) -> StackBuilder {
+        let mut b = BlockNormalBuilder::new("text2speech_setLanguage");
+        b.add_input(
+            "LANGUAGE",
+            menu_input("text2speech_menu_languages", "languages", language),
+        );
+        StackBuilder::start(b)
+    }
+}
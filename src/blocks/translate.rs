@@ -0,0 +1,36 @@
+//! Translate extension blocks.
+//!
+//! Using any of these adds `"translate"` to the project's extensions when it's built.
+
+use sb_itchy::{
+    block::{BlockFieldBuilder, BlockInputBuilder, BlockNormalBuilder},
+    stack::StackBuilder,
+};
+
+use super::menu_input;
+use crate::scripting::{arg::*, menu::*, stack::*};
+
+simple_typed_block_def! {
+    translate(words: (IntoInput<Text>) = "hello", language: (IntoField<TranslateLanguage>)) -> JustReporter<Text>
+    /// Language of the project editor's interface
+    viewer_language() -> JustReporter<Text>
+}
+
+/// Untyped translate blocks, in the same shape as [`sb_itchy::blocks`]
+mod blocks {
+    use super::*;
+
+    pub fn translate(words: BlockInputBuilder, language: BlockFieldBuilder) -> StackBuilder {
+        let mut b = BlockNormalBuilder::new("translate_getTranslate");
+        b.add_input("WORDS", words);
+        b.add_input(
+            "LANGUAGE",
+            menu_input("translate_menu_languages", "languages", language),
+        );
+        StackBuilder::start(b)
+    }
+
+    pub fn viewer_language() -> StackBuilder {
+        StackBuilder::start(BlockNormalBuilder::new("translate_getViewerLanguage"))
+    }
+}
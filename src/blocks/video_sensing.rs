@@ -0,0 +1,58 @@
+//! Video Sensing extension blocks.
+//!
+//! Using any of these adds `"videoSensing"` to the project's extensions when it's built.
+
+use sb_itchy::{
+    block::{BlockFieldBuilder, BlockInputBuilder, BlockNormalBuilder},
+    stack::StackBuilder,
+};
+
+use super::menu_input;
+use crate::scripting::{arg::*, menu::*, stack::*};
+
+simple_typed_block_def! {
+    when_motion_greater_than(reference: (IntoInput<Number>) = 10) -> HatBlock
+    /// Amount of motion or direction of motion on the sprite or the whole stage
+    video_on(attribute: (IntoField<VideoAttribute>), subject: (IntoField<VideoSubject>)) -> JustReporter<Number>
+    turn_video(state: (IntoField<VideoState>)) -> StackBlock
+    set_video_transparency(transparency: (IntoInput<Number>) = 50) -> StackBlock
+}
+
+/// Untyped video sensing blocks, in the same shape as [`sb_itchy::blocks`]
+mod blocks {
+    use super::*;
+
+    pub fn when_motion_greater_than(reference: BlockInputBuilder) -> StackBuilder {
+        let mut b = BlockNormalBuilder::new("videoSensing_whenMotionGreaterThan");
+        b.add_input("REFERENCE", reference);
+        StackBuilder::start(b)
+    }
+
+    pub fn video_on(attribute: BlockFieldBuilder, subject: BlockFieldBuilder) -> StackBuilder {
+        let mut b = BlockNormalBuilder::new("videoSensing_videoOn");
+        b.add_input(
+            "ATTRIBUTE",
+            menu_input("videoSensing_menu_ATTRIBUTE", "ATTRIBUTE", attribute),
+        );
+        b.add_input(
+            "SUBJECT",
+            menu_input("videoSensing_menu_SUBJECT", "SUBJECT", subject),
+        );
+        StackBuilder::start(b)
+    }
+
+    pub fn turn_video(state: BlockFieldBuilder) -> StackBuilder {
+        let mut b = BlockNormalBuilder::new("videoSensing_videoToggle");
+        b.add_input(
+            "VIDEO_STATE",
+            menu_input("videoSensing_menu_VIDEO_STATE", "VIDEO_STATE", state),
+        );
+        StackBuilder::start(b)
+    }
+
+    pub fn set_video_transparency(transparency: BlockInputBuilder) -> StackBuilder {
+        let mut b = BlockNormalBuilder::new("videoSensing_setVideoTransparency");
+        b.add_input("TRANSPARENCY", transparency);
+        StackBuilder::start(b)
+    }
+}
@@ -0,0 +1,385 @@
+//! Replacing random ids in a built project with stable ones
+//!
+//! # Explanation
+//!
+//! Ids are given to everything by a key that describes it without using any random id:
+//!  - Variables, lists and broadcasts by their target and name.
+//!  - Scripts by their target and contents, as the position of a script in a target isn't stable.
+//!    Identical scripts in the same target are told apart by their position on the canvas.
+//!  - Blocks by their script and path from the top of the script, e.g. `next/i:SUBSTACK/next`.
+//!
+//! [`IdStrategy::Hash`] hashes the key and [`IdStrategy::Counter`] counts up in the order of the keys.
+
+use std::collections::{HashMap, HashSet};
+
+use serde_json::{Map, Value as Json};
+
+use crate::project::IdStrategy;
+
+/// Characters of Scratch's ids
+const SOUP: &[u8] =
+    b"!#%()*+,-./:;=?@[]^_`{|}~ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+const ID_LENGTH: usize = 20;
+
+/// Replace ids of `project.json` following `strategy`.
+/// Does nothing for [`IdStrategy::Random`].
+pub(crate) fn assign_ids(project: &mut Json, strategy: IdStrategy) {
+    let mut generator = match strategy {
+        IdStrategy::Random => return,
+        IdStrategy::Hash => Generator::Hash {
+            used: HashSet::new(),
+        },
+        IdStrategy::Counter { seed } => Generator::Counter { next: seed },
+    };
+    let Some(targets) = project["targets"].as_array_mut() else {
+        return;
+    };
+
+    // Variables, lists and broadcasts are referred across targets so they're replaced first
+    let mut data_ids = HashMap::new();
+    for target in targets.iter_mut() {
+        let scope = target_key(target);
+        for (kind, key) in [
+            ("variable", "variables"),
+            ("list", "lists"),
+            ("broadcast", "broadcasts"),
+        ] {
+            let Some(map) = target[key].as_object_mut() else {
+                continue;
+            };
+            let mut entries = std::mem::take(map).into_iter().collect::<Vec<_>>();
+            entries.sort_by_cached_key(|(_, data)| data_name(data).to_owned());
+            for (old_id, data) in entries {
+                let new_id = generator.id(&format!("{scope}/{kind}:{}", data_name(&data)));
+                data_ids.insert(old_id, new_id.clone());
+                map.insert(new_id, data);
+            }
+        }
+    }
+
+    for target in targets.iter_mut() {
+        let scope = target_key(target);
+        let Some(blocks) = target["blocks"].as_object_mut() else {
+            continue;
+        };
+        let block_ids = block_ids(&scope, blocks, &mut generator);
+        let old_blocks = std::mem::take(blocks);
+        for (old_id, mut block) in old_blocks {
+            replace_block_refs(&mut block, &block_ids, &mut data_ids, &mut generator);
+            let new_id = block_ids.get(&old_id).cloned().unwrap_or(old_id);
+            blocks.insert(new_id, block);
+        }
+
+        let Some(comments) = target["comments"].as_object_mut() else {
+            continue;
+        };
+        let mut old_comments = std::mem::take(comments).into_iter().collect::<Vec<_>>();
+        for (_, comment) in &mut old_comments {
+            if let Some(block_id) = comment["blockId"].as_str() {
+                if let Some(new_id) = block_ids.get(block_id) {
+                    comment["blockId"] = Json::String(new_id.clone());
+                }
+            }
+        }
+        old_comments.sort_by_cached_key(|(_, comment)| canonical(comment));
+        let mut comment_ids = HashMap::new();
+        for (old_id, comment) in old_comments {
+            let new_id = generator.id(&format!("{scope}/comment:{}", canonical(&comment)));
+            comment_ids.insert(old_id, new_id.clone());
+            comments.insert(new_id, comment);
+        }
+        if let Some(blocks) = target["blocks"].as_object_mut() {
+            for block in blocks.values_mut() {
+                let new_id = block["comment"].as_str().and_then(|id| comment_ids.get(id));
+                if let Some(new_id) = new_id {
+                    block["comment"] = Json::String(new_id.clone());
+                }
+            }
+        }
+    }
+
+    if let Some(monitors) = project["monitors"].as_array_mut() {
+        for monitor in monitors {
+            let new_id = monitor["id"].as_str().and_then(|id| data_ids.get(id));
+            if let Some(new_id) = new_id {
+                monitor["id"] = Json::String(new_id.clone());
+            }
+        }
+    }
+}
+
+enum Generator {
+    Hash { used: HashSet<String> },
+    Counter { next: u64 },
+}
+
+impl Generator {
+    fn id(&mut self, key: &str) -> String {
+        match self {
+            Generator::Hash { used } => {
+                let mut digest = md5::compute(key);
+                let mut retry = 0;
+                // different keys having the same id is unlikely but ids must be unique
+                while used.contains(&encode(u128::from_be_bytes(digest.0))) {
+                    retry += 1;
+                    digest = md5::compute(format!("{key}#{retry}"));
+                }
+                let id = encode(u128::from_be_bytes(digest.0));
+                used.insert(id.clone());
+                id
+            }
+            Generator::Counter { next } => {
+                let id = encode(u128::from(*next));
+                *next = next.wrapping_add(1);
+                id
+            }
+        }
+    }
+}
+
+/// Encode a number with characters of Scratch's ids
+fn encode(mut n: u128) -> String {
+    let base = SOUP.len() as u128;
+    let mut id = vec![SOUP[0]; ID_LENGTH];
+    for c in id.iter_mut().rev() {
+        *c = SOUP[(n % base) as usize];
+        n /= base;
+    }
+    String::from_utf8(id).expect("soup is ascii")
+}
+
+fn target_key(target: &Json) -> String {
+    if target["isStage"].as_bool().unwrap_or(false) {
+        "stage".to_owned()
+    } else {
+        format!("sprite:{}", target["name"].as_str().unwrap_or_default())
+    }
+}
+
+/// Name of a variable `[name, value]`, list `[name, items]` or broadcast `name`
+fn data_name(data: &Json) -> &str {
+    match data {
+        Json::String(name) => name,
+        data => data[0].as_str().unwrap_or_default(),
+    }
+}
+
+/// New id of every block in a target by old id
+fn block_ids(
+    scope: &str,
+    blocks: &Map<String, Json>,
+    generator: &mut Generator,
+) -> HashMap<String, String> {
+    let mut scripts = blocks
+        .iter()
+        .filter(|(_, block)| block.is_array() || block["topLevel"].as_bool().unwrap_or(false))
+        .map(|(id, block)| {
+            let position = match block {
+                Json::Array(array) => (array.get(3).cloned(), array.get(4).cloned()),
+                block => (block.get("x").cloned(), block.get("y").cloned()),
+            };
+            let content = canonical(&resolve(id, blocks, &mut HashSet::new()));
+            let position = Json::Array(vec![
+                position.0.unwrap_or_default(),
+                position.1.unwrap_or_default(),
+            ]);
+            (content, canonical(&position), id)
+        })
+        .collect::<Vec<_>>();
+    scripts.sort();
+
+    let mut ids = HashMap::new();
+    let mut seen = HashSet::new();
+    let mut previous_content = None;
+    let mut duplicate = 0;
+    for (content, _, id) in scripts {
+        if previous_content.as_ref() == Some(&content) {
+            duplicate += 1;
+        } else {
+            duplicate = 0;
+        }
+        let script_key = format!("{scope}/script:{:x}#{duplicate}", md5::compute(&content));
+        assign_block_ids(id, script_key, blocks, generator, &mut ids, &mut seen);
+        previous_content = Some(content);
+    }
+
+    // Blocks that aren't in any script shouldn't exist but they still need ids
+    let mut orphans = blocks
+        .keys()
+        .filter(|id| !ids.contains_key(*id))
+        .map(|id| (canonical(&resolve(id, blocks, &mut HashSet::new())), id))
+        .collect::<Vec<_>>();
+    orphans.sort();
+    for (idx, (content, id)) in orphans.into_iter().enumerate() {
+        let key = format!("{scope}/orphan:{:x}#{idx}", md5::compute(content));
+        assign_block_ids(id, key, blocks, generator, &mut ids, &mut seen);
+    }
+    ids
+}
+
+/// Give ids to the block and every block below and inside it
+fn assign_block_ids(
+    id: &str,
+    path: String,
+    blocks: &Map<String, Json>,
+    generator: &mut Generator,
+    ids: &mut HashMap<String, String>,
+    seen: &mut HashSet<String>,
+) {
+    if !seen.insert(id.to_owned()) {
+        return;
+    }
+    ids.insert(id.to_owned(), generator.id(&path));
+    let Some(block) = blocks.get(id) else {
+        return;
+    };
+    if let Some(inputs) = block["inputs"].as_object() {
+        let mut inputs = inputs.iter().collect::<Vec<_>>();
+        inputs.sort_by_key(|(name, _)| *name);
+        for (name, input) in inputs {
+            let children = input.as_array().into_iter().flatten().skip(1);
+            for (idx, child) in children.enumerate() {
+                if let Some(child) = child.as_str().filter(|c| blocks.contains_key(*c)) {
+                    let path = format!("{path}/i:{name}:{idx}");
+                    assign_block_ids(child, path, blocks, generator, ids, seen);
+                }
+            }
+        }
+    }
+    if let Some(next) = block["next"].as_str() {
+        assign_block_ids(next, format!("{path}/next"), blocks, generator, ids, seen);
+    }
+}
+
+/// The block with every block it refers to put in place of their ids,
+/// and without ids of variables, lists and broadcasts
+fn resolve(id: &str, blocks: &Map<String, Json>, seen: &mut HashSet<String>) -> Json {
+    let Some(block) = blocks.get(id).filter(|_| seen.insert(id.to_owned())) else {
+        return Json::Null;
+    };
+    let mut block = block.clone();
+    match &mut block {
+        Json::Array(primitive) => primitive.truncate(2),
+        Json::Object(object) => {
+            for key in ["parent", "x", "y", "comment"] {
+                object.remove(key);
+            }
+            if let Some(Json::String(next)) = object.get("next").cloned() {
+                object.insert("next".to_owned(), resolve(&next, blocks, seen));
+            }
+            if let Some(Json::Object(inputs)) = object.get_mut("inputs") {
+                for input in inputs.values_mut().filter_map(Json::as_array_mut) {
+                    for value in input.iter_mut().skip(1) {
+                        match value {
+                            Json::String(child) => {
+                                let child = child.clone();
+                                *value = resolve(&child, blocks, seen);
+                            }
+                            Json::Array(primitive) => primitive.truncate(2),
+                            _ => {}
+                        }
+                    }
+                }
+            }
+            if let Some(Json::Object(fields)) = object.get_mut("fields") {
+                for field in fields.values_mut().filter_map(Json::as_array_mut) {
+                    field.truncate(1);
+                }
+            }
+        }
+        _ => {}
+    }
+    block
+}
+
+/// Json with sorted keys so the same value is always the same text
+fn canonical(json: &Json) -> String {
+    match json {
+        Json::Array(array) => {
+            let items = array.iter().map(canonical).collect::<Vec<_>>();
+            format!("[{}]", items.join(","))
+        }
+        Json::Object(object) => {
+            let mut entries = object
+                .iter()
+                .map(|(key, value)| format!("{}:{}", Json::from(key.as_str()), canonical(value)))
+                .collect::<Vec<_>>();
+            entries.sort();
+            format!("{{{}}}", entries.join(","))
+        }
+        other => other.to_string(),
+    }
+}
+
+/// Replace ids inside a block
+fn replace_block_refs(
+    block: &mut Json,
+    block_ids: &HashMap<String, String>,
+    data_ids: &mut HashMap<String, String>,
+    generator: &mut Generator,
+) {
+    let replace_block = |json: &mut Json| {
+        let new_id = json.as_str().and_then(|id| block_ids.get(id));
+        if let Some(new_id) = new_id {
+            *json = Json::String(new_id.clone());
+        }
+    };
+    let mut replace_data = |reference: &mut Vec<Json>, name_idx: usize, kind: &str| {
+        let Some(Json::String(old_id)) = reference.get(name_idx + 1) else {
+            return;
+        };
+        let new_id = data_ids.get(old_id).cloned().unwrap_or_else(|| {
+            // refers to something that isn't declared, Scratch will create it when loading
+            let name = reference[name_idx].as_str().unwrap_or_default();
+            let new_id = generator.id(&format!("undeclared/{kind}:{name}"));
+            data_ids.insert(old_id.clone(), new_id.clone());
+            new_id
+        });
+        reference[name_idx + 1] = Json::String(new_id);
+    };
+
+    match block {
+        Json::Array(primitive) => {
+            let kind = primitive_kind(primitive);
+            replace_data(primitive, 1, kind);
+        }
+        Json::Object(object) => {
+            for key in ["next", "parent"] {
+                if let Some(json) = object.get_mut(key) {
+                    replace_block(json);
+                }
+            }
+            if let Some(Json::Object(inputs)) = object.get_mut("inputs") {
+                for input in inputs.values_mut().filter_map(Json::as_array_mut) {
+                    for value in input.iter_mut().skip(1) {
+                        match value {
+                            Json::String(_) => replace_block(value),
+                            Json::Array(primitive) => {
+                                let kind = primitive_kind(primitive);
+                                replace_data(primitive, 1, kind);
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+            }
+            if let Some(Json::Object(fields)) = object.get_mut("fields") {
+                for (name, field) in fields.iter_mut() {
+                    if let Some(field) = field.as_array_mut() {
+                        replace_data(field, 0, &name.to_lowercase());
+                    }
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn primitive_kind(primitive: &[Json]) -> &'static str {
+    match primitive.first().and_then(Json::as_u64) {
+        Some(11) => "broadcast",
+        Some(12) => "variable",
+        Some(13) => "list",
+        _ => "value",
+    }
+}
@@ -0,0 +1,1352 @@
+//! Running blocks
+
+use std::{collections::HashMap, rc::Rc};
+
+use super::load::{Block, Code, Field, Input};
+use super::*;
+
+/// Stage size, the center is (0, 0)
+const STAGE_WIDTH: f64 = 480.;
+const STAGE_HEIGHT: f64 = 360.;
+/// How many loop iterations and custom block calls a "run without screen refresh" script
+/// can run in a frame before yielding anyway
+const WARP_LIMIT: u32 = 100_000;
+
+/// What's needed to evaluate a block
+pub(super) struct Ctx<'a> {
+    pub code: &'a Code,
+    pub instance: u64,
+    pub params: &'a HashMap<String, Value>,
+}
+
+/// What to do after running a block
+enum Flow {
+    /// Go to the block below
+    Next,
+    /// Run a substack or a custom block body
+    Push(Frame),
+    /// Stop for this frame and run the same block again in the next frame
+    Yield,
+    /// Stop this script, or return from the custom block if it's inside one
+    StopThisScript,
+    /// The thread is done
+    Done,
+}
+
+enum ListIndex {
+    All,
+    Invalid,
+    /// Index starting from 0
+    Index(usize),
+}
+
+impl Interpreter {
+    pub(super) fn run_thread(&mut self, thread: &mut Thread) {
+        let Some(code) = self
+            .instance(thread.instance)
+            .map(|i| self.code[i.target].clone())
+        else {
+            thread.done = true;
+            return;
+        };
+        let mut warp_budget = WARP_LIMIT;
+        while !thread.done {
+            let Some(frame) = thread.frames.last_mut() else {
+                thread.done = true;
+                return;
+            };
+            let Some(block_id) = frame.block.clone() else {
+                let finished = thread.frames.pop().expect("checked above");
+                let Some(parent) = thread.frames.last_mut() else {
+                    thread.done = true;
+                    return;
+                };
+                if finished.kind == FrameKind::LoopBody {
+                    // the loop block runs again, loops yield at the end of every iteration
+                    warp_budget = warp_budget.saturating_sub(1);
+                    if !parent.warp || warp_budget == 0 {
+                        return;
+                    }
+                } else {
+                    advance(parent, &code);
+                }
+                continue;
+            };
+            let Some(block) = code.blocks.get(&block_id) else {
+                frame.block = None;
+                continue;
+            };
+            match self.exec(thread, &code, block) {
+                Flow::Next => advance(thread.frames.last_mut().expect("checked above"), &code),
+                Flow::Push(frame) => {
+                    let is_recursive = frame.procedure.is_some()
+                        && thread.frames.iter().any(|f| f.procedure == frame.procedure);
+                    let warp = frame.warp;
+                    let is_call = frame.kind == FrameKind::Procedure;
+                    thread.frames.push(frame);
+                    if is_call {
+                        // like Scratch, a custom block that calls itself yields
+                        // so a script that never stops calling itself doesn't hang
+                        warp_budget = warp_budget.saturating_sub(1);
+                        if (!warp && is_recursive) || warp_budget == 0 {
+                            return;
+                        }
+                    }
+                }
+                Flow::Yield => return,
+                Flow::StopThisScript => loop {
+                    let Some(frame) = thread.frames.pop() else {
+                        thread.done = true;
+                        return;
+                    };
+                    if frame.kind == FrameKind::Procedure {
+                        match thread.frames.last_mut() {
+                            Some(parent) => advance(parent, &code),
+                            None => thread.done = true,
+                        }
+                        break;
+                    }
+                },
+                Flow::Done => thread.done = true,
+            }
+        }
+    }
+
+    /// Run a stack block
+    fn exec(&mut self, thread: &mut Thread, code: &Code, block: &Block) -> Flow {
+        let instance = thread.instance;
+        if self.instance(instance).is_none() {
+            return Flow::Done;
+        }
+        let frame = thread.frames.last_mut().expect("running frame exists");
+        let warp = frame.warp;
+        let params = frame.params.clone();
+        let ctx = Ctx {
+            code,
+            instance,
+            params: &params,
+        };
+        let state = &mut frame.state;
+        let substack = |name: &str, kind: FrameKind| {
+            let start = match block.inputs.get(name) {
+                Some(Input::Block(id)) => Some(id.clone()),
+                _ => None,
+            };
+            Flow::Push(Frame::new(start, kind, warp, params.clone()))
+        };
+
+        match block.opcode.as_str() {
+            // Control ===============================================================
+            "control_forever" => substack("SUBSTACK", FrameKind::LoopBody),
+            "control_repeat" => {
+                let remaining = match state.take() {
+                    Some(BlockState::Repeat(remaining)) => remaining,
+                    _ => self.input(&ctx, block, "TIMES").to_number().round() as i64,
+                };
+                if remaining <= 0 {
+                    return Flow::Next;
+                }
+                *state = Some(BlockState::Repeat(remaining - 1));
+                substack("SUBSTACK", FrameKind::LoopBody)
+            }
+            "control_repeat_until" => {
+                if self.input(&ctx, block, "CONDITION").to_bool() {
+                    Flow::Next
+                } else {
+                    substack("SUBSTACK", FrameKind::LoopBody)
+                }
+            }
+            "control_while" => {
+                if self.input(&ctx, block, "CONDITION").to_bool() {
+                    substack("SUBSTACK", FrameKind::LoopBody)
+                } else {
+                    Flow::Next
+                }
+            }
+            "control_for_each" => {
+                let done = match state.take() {
+                    Some(BlockState::Repeat(done)) => done,
+                    _ => 0,
+                };
+                if done as f64 >= self.input(&ctx, block, "VALUE").to_number() {
+                    return Flow::Next;
+                }
+                *state = Some(BlockState::Repeat(done + 1));
+                if let Some(var) = block.fields.get("VARIABLE") {
+                    self.var_mut(instance, var).value = Value::Number((done + 1) as f64);
+                }
+                substack("SUBSTACK", FrameKind::LoopBody)
+            }
+            "control_if" => {
+                if self.input(&ctx, block, "CONDITION").to_bool() {
+                    substack("SUBSTACK", FrameKind::Substack)
+                } else {
+                    Flow::Next
+                }
+            }
+            "control_if_else" => {
+                if self.input(&ctx, block, "CONDITION").to_bool() {
+                    substack("SUBSTACK", FrameKind::Substack)
+                } else {
+                    substack("SUBSTACK2", FrameKind::Substack)
+                }
+            }
+            "control_wait" => {
+                let duration = self.input(&ctx, block, "DURATION").to_number();
+                self.wait(state, duration)
+            }
+            "control_wait_until" => {
+                if self.input(&ctx, block, "CONDITION").to_bool() {
+                    Flow::Next
+                } else {
+                    Flow::Yield
+                }
+            }
+            "control_stop" => match field(block, "STOP_OPTION") {
+                "all" => {
+                    self.stop_all();
+                    Flow::Done
+                }
+                "other scripts in sprite" | "other scripts in stage" => {
+                    for other in &mut self.threads {
+                        if other.instance == instance {
+                            other.done = true;
+                        }
+                    }
+                    Flow::Next
+                }
+                _ => Flow::StopThisScript,
+            },
+            "control_create_clone_of" => {
+                let option = self.input(&ctx, block, "CLONE_OPTION").to_text();
+                let parent = if option == "_myself_" {
+                    Some(instance)
+                } else {
+                    self.original(&option).map(|i| i.id)
+                };
+                if let Some(parent) = parent {
+                    self.create_clone(parent);
+                }
+                Flow::Next
+            }
+            "control_delete_this_clone" => {
+                if self.instance(instance).is_some_and(|i| i.is_clone) {
+                    self.delete_clone(instance);
+                    Flow::Done
+                } else {
+                    Flow::Next
+                }
+            }
+
+            // Event =================================================================
+            "event_broadcast" => {
+                let name = self.input(&ctx, block, "BROADCAST_INPUT").to_text();
+                self.start_broadcast(&name);
+                Flow::Next
+            }
+            "event_broadcastandwait" => {
+                if state.is_none() {
+                    let name = self.input(&ctx, block, "BROADCAST_INPUT").to_text();
+                    *state = Some(BlockState::Threads(self.start_broadcast(&name)));
+                }
+                self.wait_threads(state)
+            }
+
+            // Looks =================================================================
+            "looks_say" | "looks_think" => {
+                let text = self.input(&ctx, block, "MESSAGE").to_text();
+                self.set_bubble(instance, bubble_kind(&block.opcode), text);
+                Flow::Next
+            }
+            "looks_sayforsecs" | "looks_thinkforsecs" => {
+                let kind = bubble_kind(&block.opcode);
+                let text = self.input(&ctx, block, "MESSAGE").to_text();
+                if state.is_none() {
+                    self.set_bubble(instance, kind, text.clone());
+                }
+                let duration = self.input(&ctx, block, "SECS").to_number();
+                let flow = self.wait(state, duration);
+                if let Flow::Next = flow {
+                    let state = &mut self.instance_state(instance);
+                    if state.bubble.as_ref().is_some_and(|b| b.text == text) {
+                        state.bubble = None;
+                    }
+                }
+                flow
+            }
+            "looks_switchcostumeto" => {
+                let costume = self.input(&ctx, block, "COSTUME");
+                self.set_costume(instance, &costume);
+                Flow::Next
+            }
+            "looks_nextcostume" => {
+                let costume = self.instance_state(instance).costume;
+                self.set_costume(instance, &Value::Number(costume as f64 + 2.));
+                Flow::Next
+            }
+            "looks_switchbackdropto" => {
+                let backdrop = self.input(&ctx, block, "BACKDROP");
+                self.set_costume(0, &backdrop);
+                self.start_backdrop_hats();
+                Flow::Next
+            }
+            "looks_switchbackdroptoandwait" => {
+                if state.is_none() {
+                    let backdrop = self.input(&ctx, block, "BACKDROP");
+                    self.set_costume(0, &backdrop);
+                    *state = Some(BlockState::Threads(self.start_backdrop_hats()));
+                }
+                self.wait_threads(state)
+            }
+            "looks_nextbackdrop" => {
+                let backdrop = self.instance_state(0).costume;
+                self.set_costume(0, &Value::Number(backdrop as f64 + 2.));
+                self.start_backdrop_hats();
+                Flow::Next
+            }
+            "looks_changesizeby" => {
+                let by = self.input(&ctx, block, "CHANGE").to_number();
+                let state = self.instance_state(instance);
+                state.size = (state.size + by).max(0.);
+                Flow::Next
+            }
+            "looks_setsizeto" => {
+                let size = self.input(&ctx, block, "SIZE").to_number();
+                self.instance_state(instance).size = size.max(0.);
+                Flow::Next
+            }
+            "looks_changeeffectby" | "looks_seteffectto" => {
+                let input = if block.opcode == "looks_changeeffectby" {
+                    "CHANGE"
+                } else {
+                    "VALUE"
+                };
+                let value = self.input(&ctx, block, input).to_number();
+                let Some(effect) = LooksEffect::parse(&field(block, "EFFECT").to_uppercase())
+                else {
+                    return Flow::Next;
+                };
+                let effects = &mut self.instance_state(instance).looks_effects;
+                let current = effects.entry(effect).or_insert(0.);
+                *current = if input == "CHANGE" {
+                    *current + value
+                } else {
+                    value
+                };
+                *current = match effect {
+                    LooksEffect::Ghost => current.clamp(0., 100.),
+                    LooksEffect::Brightness => current.clamp(-100., 100.),
+                    _ => *current,
+                };
+                Flow::Next
+            }
+            "looks_cleargraphiceffects" => {
+                self.instance_state(instance).looks_effects.clear();
+                Flow::Next
+            }
+            "looks_show" | "looks_hide" => {
+                self.instance_state(instance).visible = block.opcode == "looks_show";
+                Flow::Next
+            }
+            "looks_gotofrontback" => {
+                if let Some(idx) = self.layers.iter().position(|l| *l == instance) {
+                    self.layers.remove(idx);
+                    match field(block, "FRONT_BACK") {
+                        "back" => self.layers.insert(0, instance),
+                        _ => self.layers.push(instance),
+                    }
+                }
+                Flow::Next
+            }
+            "looks_goforwardbackwardlayers" | "looks_goforwardbackward" => {
+                let by = self.input(&ctx, block, "NUM").to_number().trunc() as i64;
+                let by = match field(block, "FORWARD_BACKWARD") {
+                    "backward" => -by,
+                    _ => by,
+                };
+                if let Some(idx) = self.layers.iter().position(|l| *l == instance) {
+                    self.layers.remove(idx);
+                    let idx = (idx as i64 + by).clamp(0, self.layers.len() as i64);
+                    self.layers.insert(idx as usize, instance);
+                }
+                Flow::Next
+            }
+
+            // Motion ================================================================
+            "motion_movesteps" => {
+                let steps = self.input(&ctx, block, "STEPS").to_number();
+                let state = self.instance_state(instance);
+                let radians = (90. - state.direction).to_radians();
+                state.x += steps * radians.cos();
+                state.y += steps * radians.sin();
+                Flow::Next
+            }
+            "motion_turnright" | "motion_turnleft" => {
+                let mut degrees = self.input(&ctx, block, "DEGREES").to_number();
+                if block.opcode == "motion_turnleft" {
+                    degrees = -degrees;
+                }
+                let state = self.instance_state(instance);
+                state.direction = wrap_direction(state.direction + degrees);
+                Flow::Next
+            }
+            "motion_goto" => {
+                let to = self.input(&ctx, block, "TO").to_text();
+                if let Some((x, y)) = self.position_of(&to) {
+                    let state = self.instance_state(instance);
+                    state.x = x;
+                    state.y = y;
+                }
+                Flow::Next
+            }
+            "motion_gotoxy" => {
+                let x = self.input(&ctx, block, "X").to_number();
+                let y = self.input(&ctx, block, "Y").to_number();
+                let state = self.instance_state(instance);
+                state.x = x;
+                state.y = y;
+                Flow::Next
+            }
+            "motion_glideto" | "motion_glidesecstoxy" => {
+                if state.is_none() {
+                    let to = if block.opcode == "motion_glideto" {
+                        let to = self.input(&ctx, block, "TO").to_text();
+                        self.position_of(&to)
+                    } else {
+                        Some((
+                            self.input(&ctx, block, "X").to_number(),
+                            self.input(&ctx, block, "Y").to_number(),
+                        ))
+                    };
+                    let Some(to) = to else {
+                        return Flow::Next;
+                    };
+                    let duration = self.input(&ctx, block, "SECS").to_number();
+                    let target = self.instance_state(instance);
+                    let from = (target.x, target.y);
+                    *state = Some(BlockState::Glide {
+                        start: self.time(),
+                        duration,
+                        from,
+                        to,
+                    });
+                    return Flow::Yield;
+                }
+                let Some(BlockState::Glide {
+                    start,
+                    duration,
+                    from,
+                    to,
+                }) = *state
+                else {
+                    return Flow::Next;
+                };
+                let progress = (self.time() - start) / duration;
+                let target = self.instance_state(instance);
+                if progress >= 1. || duration <= 0. {
+                    (target.x, target.y) = to;
+                    Flow::Next
+                } else {
+                    target.x = from.0 + (to.0 - from.0) * progress;
+                    target.y = from.1 + (to.1 - from.1) * progress;
+                    Flow::Yield
+                }
+            }
+            "motion_pointindirection" => {
+                let direction = self.input(&ctx, block, "DIRECTION").to_number();
+                self.instance_state(instance).direction = wrap_direction(direction);
+                Flow::Next
+            }
+            "motion_pointtowards" => {
+                let towards = self.input(&ctx, block, "TOWARDS").to_text();
+                let direction = if towards == "_random_" {
+                    Some((self.random() * 360.).round() - 180.)
+                } else {
+                    self.position_of(&towards).map(|(x, y)| {
+                        let state = self.instance_state(instance);
+                        90. - (y - state.y).atan2(x - state.x).to_degrees()
+                    })
+                };
+                if let Some(direction) = direction {
+                    self.instance_state(instance).direction = wrap_direction(direction);
+                }
+                Flow::Next
+            }
+            "motion_setx" | "motion_changexby" | "motion_sety" | "motion_changeyby" => {
+                let input = match block.opcode.as_str() {
+                    "motion_setx" => "X",
+                    "motion_changexby" => "DX",
+                    "motion_sety" => "Y",
+                    _ => "DY",
+                };
+                let value = self.input(&ctx, block, input).to_number();
+                let state = self.instance_state(instance);
+                match input {
+                    "X" => state.x = value,
+                    "DX" => state.x += value,
+                    "Y" => state.y = value,
+                    _ => state.y += value,
+                }
+                Flow::Next
+            }
+            "motion_ifonedgebounce" => {
+                self.bounce(instance);
+                Flow::Next
+            }
+            "motion_setrotationstyle" => {
+                if let Some(style) = RotationStyle::parse(field(block, "STYLE")) {
+                    self.instance_state(instance).rotation_style = style;
+                }
+                Flow::Next
+            }
+
+            // Sensing ===============================================================
+            "sensing_askandwait" => {
+                if state.is_none() {
+                    let question = self.input(&ctx, block, "QUESTION").to_text();
+                    self.questions.push_back((thread.id, question.clone()));
+                    if instance != 0 && self.instance_state(instance).visible {
+                        self.set_bubble(instance, BubbleKind::Say, question);
+                    }
+                    *state = Some(BlockState::Asking);
+                    return Flow::Yield;
+                }
+                let is_asking = self.questions.front().map(|(id, _)| *id) == Some(thread.id);
+                match self.answers.front() {
+                    Some(_) if is_asking => {
+                        self.answer = self.answers.pop_front().unwrap_or_default();
+                        self.questions.pop_front();
+                        self.instance_state(instance).bubble = None;
+                        Flow::Next
+                    }
+                    _ => Flow::Yield,
+                }
+            }
+            "sensing_resettimer" => {
+                self.timer_start = self.time();
+                Flow::Next
+            }
+            "sensing_setdragmode" => {
+                self.instance_state(instance).draggable = field(block, "DRAG_MODE") == "draggable";
+                Flow::Next
+            }
+
+            // Sound =================================================================
+            "sound_play" | "sound_stopallsounds" => Flow::Next,
+            "sound_playuntildone" => {
+                let sound = self.input(&ctx, block, "SOUND_MENU");
+                let duration =
+                    sound_index(code, &sound).map_or(0., |idx| code.sounds[idx].duration);
+                self.wait(state, duration)
+            }
+            "sound_changeeffectby" | "sound_seteffectto" => {
+                let value = self.input(&ctx, block, "VALUE").to_number();
+                let Some(effect) = SoundEffect::parse(&field(block, "EFFECT").to_uppercase())
+                else {
+                    return Flow::Next;
+                };
+                let effects = &mut self.instance_state(instance).sound_effects;
+                let current = effects.entry(effect).or_insert(0.);
+                *current = if block.opcode == "sound_changeeffectby" {
+                    *current + value
+                } else {
+                    value
+                };
+                *current = match effect {
+                    SoundEffect::Pitch => current.clamp(-360., 360.),
+                    SoundEffect::Pan => current.clamp(-100., 100.),
+                };
+                Flow::Next
+            }
+            "sound_cleareffects" => {
+                self.instance_state(instance).sound_effects.clear();
+                Flow::Next
+            }
+            "sound_setvolumeto" | "sound_changevolumeby" => {
+                let value = self.input(&ctx, block, "VOLUME").to_number();
+                let state = self.instance_state(instance);
+                state.volume = if block.opcode == "sound_changevolumeby" {
+                    state.volume + value
+                } else {
+                    value
+                }
+                .clamp(0., 100.);
+                Flow::Next
+            }
+
+            // Data ==================================================================
+            "data_setvariableto" | "data_changevariableby" => {
+                let value = self.input(&ctx, block, "VALUE");
+                let Some(var) = block.fields.get("VARIABLE") else {
+                    return Flow::Next;
+                };
+                let var = self.var_mut(instance, var);
+                var.value = if block.opcode == "data_changevariableby" {
+                    Value::Number(var.value.to_number() + value.to_number())
+                } else {
+                    value
+                };
+                Flow::Next
+            }
+            "data_showvariable" | "data_hidevariable" | "data_showlist" | "data_hidelist" => {
+                Flow::Next
+            }
+            "data_addtolist" => {
+                let item = self.input(&ctx, block, "ITEM");
+                if let Some(list) = block.fields.get("LIST") {
+                    self.list_mut(instance, list).items.push(item);
+                }
+                Flow::Next
+            }
+            "data_deleteoflist" => {
+                let index = self.input(&ctx, block, "INDEX");
+                let Some(list) = block.fields.get("LIST") else {
+                    return Flow::Next;
+                };
+                let len = self.list_mut(instance, list).items.len();
+                let index = self.list_index(&index, len, true);
+                let items = &mut self.list_mut(instance, list).items;
+                match index {
+                    ListIndex::All => items.clear(),
+                    ListIndex::Index(idx) => {
+                        items.remove(idx);
+                    }
+                    ListIndex::Invalid => {}
+                }
+                Flow::Next
+            }
+            "data_deletealloflist" => {
+                if let Some(list) = block.fields.get("LIST") {
+                    self.list_mut(instance, list).items.clear();
+                }
+                Flow::Next
+            }
+            "data_insertatlist" | "data_replaceitemoflist" => {
+                let index = self.input(&ctx, block, "INDEX");
+                let item = self.input(&ctx, block, "ITEM");
+                let Some(list) = block.fields.get("LIST") else {
+                    return Flow::Next;
+                };
+                let insert = block.opcode == "data_insertatlist";
+                let len = self.list_mut(instance, list).items.len();
+                let index = self.list_index(&index, if insert { len + 1 } else { len }, false);
+                let items = &mut self.list_mut(instance, list).items;
+                if let ListIndex::Index(idx) = index {
+                    if insert {
+                        items.insert(idx, item);
+                    } else {
+                        items[idx] = item;
+                    }
+                }
+                Flow::Next
+            }
+
+            // Custom block ==========================================================
+            "procedures_call" => {
+                let Some(proccode) = block.mutation.as_ref().and_then(|m| m["proccode"].as_str())
+                else {
+                    return Flow::Next;
+                };
+                let Some(procedure) = code.procedures.get(proccode) else {
+                    return Flow::Next;
+                };
+                let mut args = HashMap::new();
+                for (id, name) in procedure.arg_ids.iter().zip(&procedure.arg_names) {
+                    args.insert(name.clone(), self.input(&ctx, block, id));
+                }
+                let mut frame = Frame::new(
+                    procedure.body.clone(),
+                    FrameKind::Procedure,
+                    warp || procedure.warp,
+                    Rc::new(args),
+                );
+                frame.procedure = Some(proccode.to_owned());
+                Flow::Push(frame)
+            }
+
+            opcode => {
+                self.unsupported.insert(opcode.to_owned());
+                Flow::Next
+            }
+        }
+    }
+
+    /// Evaluate a reporter
+    fn eval(&mut self, ctx: &Ctx, block: &Block) -> Value {
+        let instance = ctx.instance;
+        match block.opcode.as_str() {
+            // Looks =================================================================
+            "looks_costumenumbername" | "looks_backdropnumbername" => {
+                let target = if block.opcode == "looks_costumenumbername" {
+                    instance
+                } else {
+                    0
+                };
+                let costume = self.instance_state(target).costume;
+                match field(block, "NUMBER_NAME") {
+                    "name" => Value::Text(self.costume_name_of(target, costume)),
+                    _ => Value::Number(costume as f64 + 1.),
+                }
+            }
+            "looks_size" => Value::Number(self.instance_state(instance).size.round()),
+
+            // Motion ================================================================
+            "motion_xposition" => Value::Number(limit_precision(self.instance_state(instance).x)),
+            "motion_yposition" => Value::Number(limit_precision(self.instance_state(instance).y)),
+            "motion_direction" => Value::Number(self.instance_state(instance).direction),
+
+            // Operators =============================================================
+            "operator_add" | "operator_subtract" | "operator_multiply" | "operator_divide"
+            | "operator_mod" => {
+                let a = self.input(ctx, block, "NUM1").to_number();
+                let b = self.input(ctx, block, "NUM2").to_number();
+                Value::Number(match block.opcode.as_str() {
+                    "operator_add" => a + b,
+                    "operator_subtract" => a - b,
+                    "operator_multiply" => a * b,
+                    "operator_divide" => a / b,
+                    _ => {
+                        let result = a % b;
+                        if result / b < 0. {
+                            result + b
+                        } else {
+                            result
+                        }
+                    }
+                })
+            }
+            "operator_random" => {
+                let from = self.input(ctx, block, "FROM");
+                let to = self.input(ctx, block, "TO");
+                let (a, b) = (from.to_number(), to.to_number());
+                let (low, high) = if a <= b { (a, b) } else { (b, a) };
+                if low == high {
+                    Value::Number(low)
+                } else if from.is_int() && to.is_int() {
+                    Value::Number(low + (self.random() * (high - low + 1.)).floor())
+                } else {
+                    Value::Number(low + self.random() * (high - low))
+                }
+            }
+            "operator_lt" | "operator_gt" | "operator_equals" => {
+                let a = self.input(ctx, block, "OPERAND1");
+                let b = self.input(ctx, block, "OPERAND2");
+                let ordering = a.compare(&b);
+                Value::Bool(match block.opcode.as_str() {
+                    "operator_lt" => ordering.is_lt(),
+                    "operator_gt" => ordering.is_gt(),
+                    _ => ordering.is_eq(),
+                })
+            }
+            "operator_and" => Value::Bool(
+                self.input(ctx, block, "OPERAND1").to_bool()
+                    && self.input(ctx, block, "OPERAND2").to_bool(),
+            ),
+            "operator_or" => Value::Bool(
+                self.input(ctx, block, "OPERAND1").to_bool()
+                    || self.input(ctx, block, "OPERAND2").to_bool(),
+            ),
+            "operator_not" => Value::Bool(!self.input(ctx, block, "OPERAND").to_bool()),
+            "operator_join" => {
+                let a = self.input(ctx, block, "STRING1").to_text();
+                let b = self.input(ctx, block, "STRING2").to_text();
+                Value::Text(a + &b)
+            }
+            "operator_letter_of" => {
+                let idx = self.input(ctx, block, "LETTER").to_number() - 1.;
+                let text = self.input(ctx, block, "STRING").to_text();
+                if idx < 0. {
+                    return Value::Text(String::new());
+                }
+                Value::Text(
+                    text.chars()
+                        .nth(idx as usize)
+                        .map(String::from)
+                        .unwrap_or_default(),
+                )
+            }
+            "operator_length" => {
+                Value::Number(self.input(ctx, block, "STRING").to_text().chars().count() as f64)
+            }
+            "operator_contains" => {
+                let text = self.input(ctx, block, "STRING1").to_text().to_lowercase();
+                let contains = self.input(ctx, block, "STRING2").to_text().to_lowercase();
+                Value::Bool(text.contains(&contains))
+            }
+            "operator_round" => Value::Number(js_round(self.input(ctx, block, "NUM").to_number())),
+            "operator_mathop" => {
+                let n = self.input(ctx, block, "NUM").to_number();
+                Value::Number(math_op(field(block, "OPERATOR"), n))
+            }
+
+            // Sensing ===============================================================
+            "sensing_touchingobject" => {
+                let what = self.input(ctx, block, "TOUCHINGOBJECTMENU").to_text();
+                Value::Bool(self.is_touching(instance, &what))
+            }
+            "sensing_touchingcolor" | "sensing_coloristouchingcolor" => Value::Bool(false),
+            "sensing_distanceto" => {
+                let what = self.input(ctx, block, "DISTANCETOMENU").to_text();
+                let state = self.instance_state(instance);
+                let (x, y) = (state.x, state.y);
+                let distance = match self.position_of(&what) {
+                    Some(_) if what == "_random_" => 10000.,
+                    Some((tx, ty)) if instance != 0 => (tx - x).hypot(ty - y),
+                    _ => 10000.,
+                };
+                Value::Number(distance)
+            }
+            "sensing_answer" => Value::Text(self.answer.clone()),
+            "sensing_keypressed" => {
+                let key = self
+                    .input(ctx, block, "KEY_OPTION")
+                    .to_text()
+                    .to_lowercase();
+                Value::Bool(match key.as_str() {
+                    "any" => !self.keys_pressed.is_empty(),
+                    " " => self.keys_pressed.contains("space"),
+                    key => self.keys_pressed.contains(key),
+                })
+            }
+            "sensing_mousedown" => Value::Bool(self.mouse_down),
+            "sensing_mousex" => Value::Number(self.mouse.0),
+            "sensing_mousey" => Value::Number(self.mouse.1),
+            "sensing_loudness" => Value::Number(self.loudness),
+            "sensing_loud" => Value::Bool(self.loudness > 10.),
+            "sensing_timer" => Value::Number(self.timer()),
+            "sensing_of" => {
+                let object = self.input(ctx, block, "OBJECT").to_text();
+                self.property_of(&object, field(block, "PROPERTY"))
+            }
+            "sensing_current" => {
+                let clock = self.clock;
+                Value::Number(match field(block, "CURRENTMENU").to_uppercase().as_str() {
+                    "YEAR" => clock.year,
+                    "MONTH" => clock.month,
+                    "DATE" => clock.date,
+                    "DAYOFWEEK" => clock.day_of_week,
+                    "HOUR" => clock.hour,
+                    "MINUTE" => clock.minute,
+                    "SECOND" => clock.second,
+                    _ => 0,
+                } as f64)
+            }
+            "sensing_dayssince2000" => Value::Number(self.clock.days_since_2000),
+            "sensing_username" => Value::Text(self.username.clone()),
+
+            // Sound =================================================================
+            "sound_volume" => Value::Number(self.instance_state(instance).volume),
+
+            // Data ==================================================================
+            "data_variable" => match block.fields.get("VARIABLE") {
+                Some(var) => self.var_mut(instance, var).value.clone(),
+                None => Value::default(),
+            },
+            "data_listcontents" => match block.fields.get("LIST") {
+                Some(list) => list_contents(&self.list_mut(instance, list).items),
+                None => Value::Text(String::new()),
+            },
+            "data_itemoflist" => {
+                let index = self.input(ctx, block, "INDEX");
+                let Some(list) = block.fields.get("LIST") else {
+                    return Value::Text(String::new());
+                };
+                let len = self.list_mut(instance, list).items.len();
+                match self.list_index(&index, len, false) {
+                    ListIndex::Index(idx) => self.list_mut(instance, list).items[idx].clone(),
+                    _ => Value::Text(String::new()),
+                }
+            }
+            "data_itemnumoflist" => {
+                let item = self.input(ctx, block, "ITEM");
+                let Some(list) = block.fields.get("LIST") else {
+                    return Value::Number(0.);
+                };
+                let position = self
+                    .list_mut(instance, list)
+                    .items
+                    .iter()
+                    .position(|i| i.compare(&item).is_eq());
+                Value::Number(position.map_or(0., |p| p as f64 + 1.))
+            }
+            "data_lengthoflist" => match block.fields.get("LIST") {
+                Some(list) => Value::Number(self.list_mut(instance, list).items.len() as f64),
+                None => Value::Number(0.),
+            },
+            "data_listcontainsitem" => {
+                let item = self.input(ctx, block, "ITEM");
+                let Some(list) = block.fields.get("LIST") else {
+                    return Value::Bool(false);
+                };
+                Value::Bool(
+                    self.list_mut(instance, list)
+                        .items
+                        .iter()
+                        .any(|i| i.compare(&item).is_eq()),
+                )
+            }
+
+            // Custom block ==========================================================
+            "argument_reporter_string_number" | "argument_reporter_boolean" => ctx
+                .params
+                .get(field(block, "VALUE"))
+                .cloned()
+                .unwrap_or(Value::Number(0.)),
+
+            // Menus have the value in their only field
+            _ if block.shadow => block
+                .fields
+                .values()
+                .next()
+                .map(|f| Value::Text(f.value.clone()))
+                .unwrap_or_else(|| Value::Text(String::new())),
+
+            opcode => {
+                self.unsupported.insert(opcode.to_owned());
+                Value::Text(String::new())
+            }
+        }
+    }
+
+    /// Evaluate an input of a block
+    pub(super) fn input(&mut self, ctx: &Ctx, block: &Block, name: &str) -> Value {
+        match block.inputs.get(name) {
+            None | Some(Input::Empty) => Value::Text(String::new()),
+            Some(Input::Literal(value)) => value.clone(),
+            Some(Input::Broadcast(name)) => Value::Text(name.clone()),
+            Some(Input::Variable(var)) => self.var_mut(ctx.instance, var).value.clone(),
+            Some(Input::List(list)) => list_contents(&self.list_mut(ctx.instance, list).items),
+            Some(Input::Block(id)) => match ctx.code.blocks.get(id) {
+                Some(reporter) => self.eval(ctx, reporter),
+                None => Value::Text(String::new()),
+            },
+        }
+    }
+
+    // Helpers =====================================================================
+
+    fn instance_state(&mut self, id: u64) -> &mut TargetState {
+        &mut self
+            .instance_mut(id)
+            .expect("instance of running thread exists")
+            .state
+    }
+
+    /// Wait for `duration` seconds, yields at least once
+    fn wait(&self, state: &mut Option<BlockState>, duration: f64) -> Flow {
+        match state {
+            Some(BlockState::WaitUntil(until)) => {
+                // a little bit of tolerance for adding up frame durations
+                if self.time() + 1e-9 >= *until {
+                    Flow::Next
+                } else {
+                    Flow::Yield
+                }
+            }
+            _ => {
+                *state = Some(BlockState::WaitUntil(self.time() + duration));
+                Flow::Yield
+            }
+        }
+    }
+
+    /// Wait for threads in the state to be done
+    fn wait_threads(&self, state: &mut Option<BlockState>) -> Flow {
+        let Some(BlockState::Threads(ids)) = state else {
+            return Flow::Next;
+        };
+        let running = self.threads.iter().any(|t| !t.done && ids.contains(&t.id));
+        if running {
+            Flow::Yield
+        } else {
+            Flow::Next
+        }
+    }
+
+    fn set_bubble(&mut self, instance: u64, kind: BubbleKind, text: String) {
+        if instance == 0 {
+            return;
+        }
+        self.instance_state(instance).bubble = if text.is_empty() {
+            None
+        } else {
+            Some(Bubble { kind, text })
+        };
+    }
+
+    /// Switch costume by name or number like Scratch.
+    /// Also accepts next/previous costume and backdrop, and random backdrop.
+    fn set_costume(&mut self, instance: u64, costume: &Value) {
+        let Some(target) = self.instance(instance).map(|i| i.target) else {
+            return;
+        };
+        let code = self.code[target].clone();
+        let count = code.costumes.len();
+        if count == 0 {
+            return;
+        }
+        let current = self.instance_state(instance).costume;
+        let by_number = |n: f64| ((n.round() as i64 - 1).rem_euclid(count as i64)) as usize;
+        let idx = match costume {
+            Value::Number(n) => Some(by_number(*n)),
+            Value::Bool(_) => None,
+            Value::Text(name) => match code.costumes.iter().position(|c| &c.name == name) {
+                Some(idx) => Some(idx),
+                None => match name.as_str() {
+                    "next costume" | "next backdrop" => Some((current + 1) % count),
+                    "previous costume" | "previous backdrop" => Some((current + count - 1) % count),
+                    "random backdrop" if count > 1 => {
+                        let offset = 1 + (self.random() * (count - 1) as f64) as usize;
+                        Some((current + offset) % count)
+                    }
+                    name if !name.trim().is_empty() => {
+                        let n = costume.to_number();
+                        (n != 0. || name.trim() == "0").then(|| by_number(n))
+                    }
+                    _ => None,
+                },
+            },
+        };
+        if let Some(idx) = idx {
+            self.instance_state(instance).costume = idx;
+        }
+    }
+
+    fn costume_name_of(&self, instance: u64, costume: usize) -> String {
+        self.instance(instance)
+            .and_then(|i| self.code[i.target].costumes.get(costume))
+            .map(|c| c.name.clone())
+            .unwrap_or_default()
+    }
+
+    /// Position of `"_mouse_"`, `"_random_"` or a sprite
+    fn position_of(&mut self, what: &str) -> Option<(f64, f64)> {
+        match what {
+            "_mouse_" => Some(self.mouse),
+            "_random_" => {
+                let x = (self.random() * STAGE_WIDTH - STAGE_WIDTH / 2.).round();
+                let y = (self.random() * STAGE_HEIGHT - STAGE_HEIGHT / 2.).round();
+                Some((x, y))
+            }
+            name => self
+                .original(name)
+                .filter(|i| i.id != 0)
+                .map(|i| (i.state.x, i.state.y)),
+        }
+    }
+
+    /// Left, right, bottom and top.
+    /// Assuming the rotation center is at the middle of the costume.
+    fn bounds(&self, instance: &Instance) -> (f64, f64, f64, f64) {
+        let state = &instance.state;
+        let (half_width, half_height) = match self.code[instance.target].costumes.get(state.costume)
+        {
+            Some(costume) => {
+                let scale = state.size / 100. / costume.bitmap_resolution;
+                (
+                    costume.rotation_center.0 * scale,
+                    costume.rotation_center.1 * scale,
+                )
+            }
+            None => (0., 0.),
+        };
+        (
+            state.x - half_width,
+            state.x + half_width,
+            state.y - half_height,
+            state.y + half_height,
+        )
+    }
+
+    fn is_touching(&self, instance: u64, what: &str) -> bool {
+        let Some(this) = self
+            .instance(instance)
+            .filter(|i| i.id != 0 && i.state.visible)
+        else {
+            return false;
+        };
+        let (left, right, bottom, top) = self.bounds(this);
+        match what {
+            "_mouse_" => {
+                let (x, y) = self.mouse;
+                left <= x && x <= right && bottom <= y && y <= top
+            }
+            "_edge_" => {
+                left <= -STAGE_WIDTH / 2.
+                    || right >= STAGE_WIDTH / 2.
+                    || bottom <= -STAGE_HEIGHT / 2.
+                    || top >= STAGE_HEIGHT / 2.
+            }
+            name => self
+                .instances
+                .iter()
+                .filter(|i| i.id != instance && i.id != 0 && i.state.visible)
+                .filter(|i| self.code[i.target].name == name)
+                .any(|other| {
+                    let (o_left, o_right, o_bottom, o_top) = self.bounds(other);
+                    left <= o_right && o_left <= right && bottom <= o_top && o_bottom <= top
+                }),
+        }
+    }
+
+    fn bounce(&mut self, instance: u64) {
+        let Some(this) = self.instance(instance).filter(|i| i.id != 0) else {
+            return;
+        };
+        let (left, right, bottom, top) = self.bounds(this);
+        let distances = [
+            ("left", (STAGE_WIDTH / 2. + left).max(0.)),
+            ("top", (STAGE_HEIGHT / 2. - top).max(0.)),
+            ("right", (STAGE_WIDTH / 2. - right).max(0.)),
+            ("bottom", (STAGE_HEIGHT / 2. + bottom).max(0.)),
+        ];
+        let (nearest, distance) =
+            distances.into_iter().fold(
+                ("", f64::INFINITY),
+                |min, d| if d.1 < min.1 { d } else { min },
+            );
+        if distance > 0. {
+            return;
+        }
+        let state = self.instance_state(instance);
+        let radians = (90. - state.direction).to_radians();
+        let (mut dx, mut dy) = (radians.cos(), radians.sin());
+        match nearest {
+            "left" => dx = dx.abs().max(0.2),
+            "top" => dy = -dy.abs().max(0.2),
+            "right" => dx = -dx.abs().max(0.2),
+            _ => dy = dy.abs().max(0.2),
+        }
+        state.direction = wrap_direction(90. - dy.atan2(dx).to_degrees());
+        // keep inside the stage
+        if left < -STAGE_WIDTH / 2. {
+            state.x += -STAGE_WIDTH / 2. - left;
+        }
+        if right > STAGE_WIDTH / 2. {
+            state.x -= right - STAGE_WIDTH / 2.;
+        }
+        if bottom < -STAGE_HEIGHT / 2. {
+            state.y += -STAGE_HEIGHT / 2. - bottom;
+        }
+        if top > STAGE_HEIGHT / 2. {
+            state.y -= top - STAGE_HEIGHT / 2.;
+        }
+    }
+
+    /// `sensing_of`
+    fn property_of(&self, object: &str, property: &str) -> Value {
+        let target = if object == "_stage_" {
+            self.instances.first()
+        } else {
+            self.original(object).filter(|i| i.id != 0)
+        };
+        let Some(target) = target else {
+            return Value::Number(0.);
+        };
+        let state = &target.state;
+        let costume_name = || self.costume_name_of(target.id, state.costume);
+        let value = match (target.id == 0, property) {
+            (true, "backdrop #") | (false, "costume #") => {
+                Some(Value::Number(state.costume as f64 + 1.))
+            }
+            (true, "backdrop name") | (false, "costume name") => Some(Value::Text(costume_name())),
+            (_, "volume") => Some(Value::Number(state.volume)),
+            (false, "x position") => Some(Value::Number(limit_precision(state.x))),
+            (false, "y position") => Some(Value::Number(limit_precision(state.y))),
+            (false, "direction") => Some(Value::Number(state.direction)),
+            (false, "size") => Some(Value::Number(state.size.round())),
+            _ => None,
+        };
+        value
+            .or_else(|| {
+                target
+                    .variables
+                    .values()
+                    .find(|v| v.name == property)
+                    .map(|v| v.value.clone())
+            })
+            .unwrap_or(Value::Number(0.))
+    }
+
+    /// Find a variable like Scratch: by id then name, in the sprite then the stage.
+    /// Creates it in the sprite if it doesn't exist.
+    fn var_mut(&mut self, instance: u64, var: &Field) -> &mut Variable {
+        let (idx, key) = self.find_in_scope(
+            instance,
+            var,
+            |i, key| i.variables.contains_key(key),
+            |i| {
+                i.variables
+                    .iter()
+                    .find(|(_, v)| v.name == var.value)
+                    .map(|(id, _)| id.clone())
+            },
+        );
+        self.instances[idx]
+            .variables
+            .entry(key)
+            .or_insert_with(|| Variable {
+                name: var.value.clone(),
+                value: Value::default(),
+            })
+    }
+
+    /// Find a list like Scratch: by id then name, in the sprite then the stage.
+    /// Creates it in the sprite if it doesn't exist.
+    fn list_mut(&mut self, instance: u64, list: &Field) -> &mut List {
+        let (idx, key) = self.find_in_scope(
+            instance,
+            list,
+            |i, key| i.lists.contains_key(key),
+            |i| {
+                i.lists
+                    .iter()
+                    .find(|(_, l)| l.name == list.value)
+                    .map(|(id, _)| id.clone())
+            },
+        );
+        self.instances[idx]
+            .lists
+            .entry(key)
+            .or_insert_with(|| List {
+                name: list.value.clone(),
+                items: vec![],
+            })
+    }
+
+    /// Index of the instance and the id the variable or list is in
+    fn find_in_scope(
+        &self,
+        instance: u64,
+        field: &Field,
+        has_id: impl Fn(&Instance, &str) -> bool,
+        find_name: impl Fn(&Instance) -> Option<String>,
+    ) -> (usize, String) {
+        let this = self
+            .instances
+            .iter()
+            .position(|i| i.id == instance)
+            .expect("instance of running thread exists");
+        let scopes = [this, 0];
+        if let Some(id) = &field.id {
+            for idx in scopes {
+                if has_id(&self.instances[idx], id) {
+                    return (idx, id.clone());
+                }
+            }
+        }
+        for idx in scopes {
+            if let Some(id) = find_name(&self.instances[idx]) {
+                return (idx, id);
+            }
+        }
+        (
+            this,
+            field.id.clone().unwrap_or_else(|| field.value.clone()),
+        )
+    }
+
+    /// List index like Scratch, accepting `"last"`, `"random"` and `"all"` if `accept_all`
+    fn list_index(&mut self, index: &Value, len: usize, accept_all: bool) -> ListIndex {
+        if let Value::Text(text) = index {
+            match text.as_str() {
+                "all" if accept_all => return ListIndex::All,
+                "all" => return ListIndex::Invalid,
+                "last" if len > 0 => return ListIndex::Index(len - 1),
+                "random" | "any" if len > 0 => {
+                    return ListIndex::Index((self.random() * len as f64) as usize)
+                }
+                "last" | "random" | "any" => return ListIndex::Invalid,
+                _ => {}
+            }
+        }
+        let index = index.to_number().floor();
+        if index < 1. || index > len as f64 {
+            ListIndex::Invalid
+        } else {
+            ListIndex::Index(index as usize - 1)
+        }
+    }
+}
+
+fn advance(frame: &mut Frame, code: &Code) {
+    frame.block = frame
+        .block
+        .as_ref()
+        .and_then(|id| code.blocks.get(id))
+        .and_then(|b| b.next.clone());
+    frame.state = None;
+}
+
+fn bubble_kind(opcode: &str) -> BubbleKind {
+    if opcode.starts_with("looks_think") {
+        BubbleKind::Think
+    } else {
+        BubbleKind::Say
+    }
+}
+
+fn sound_index(code: &Code, sound: &Value) -> Option<usize> {
+    let count = code.sounds.len();
+    if count == 0 {
+        return None;
+    }
+    let by_name = code.sounds.iter().position(|s| s.name == sound.to_text());
+    by_name.or_else(|| {
+        let n = sound.to_number().round() as i64;
+        Some(((n - 1).rem_euclid(count as i64)) as usize)
+    })
+}
+
+/// Items joined with space, or without if every item is a single letter
+fn list_contents(items: &[Value]) -> Value {
+    let texts = items.iter().map(Value::to_text).collect::<Vec<_>>();
+    let all_single_letter = texts.iter().all(|t| t.chars().count() == 1);
+    Value::Text(texts.join(if all_single_letter { "" } else { " " }))
+}
+
+/// Direction in range (-180, 180]
+fn wrap_direction(direction: f64) -> f64 {
+    direction - ((direction + 179.) / 360.).floor() * 360.
+}
+
+/// Hide floating point error from motion, e.g. `9.999999999` to `10`
+fn limit_precision(n: f64) -> f64 {
+    let rounded = n.round();
+    if (n - rounded).abs() < 1e-9 {
+        rounded
+    } else {
+        n
+    }
+}
+
+/// Javascript's `Math.round`, rounds half up
+fn js_round(n: f64) -> f64 {
+    (n + 0.5).floor()
+}
+
+fn math_op(op: &str, n: f64) -> f64 {
+    // trigonometry in Scratch is in degrees and rounded to 10 decimal places
+    let round10 = |n: f64| (n * 1e10).round() / 1e10;
+    match op {
+        "abs" => n.abs(),
+        "floor" => n.floor(),
+        "ceiling" => n.ceil(),
+        "sqrt" => n.sqrt(),
+        "sin" => round10(n.to_radians().sin()),
+        "cos" => round10(n.to_radians().cos()),
+        "tan" => match n.rem_euclid(360.) {
+            90. => f64::INFINITY,
+            270. => f64::NEG_INFINITY,
+            _ => round10(n.to_radians().tan()),
+        },
+        "asin" => n.asin().to_degrees(),
+        "acos" => n.acos().to_degrees(),
+        "atan" => n.atan().to_degrees(),
+        "ln" => n.ln(),
+        "log" => n.log10(),
+        "e ^" => n.exp(),
+        "10 ^" => 10f64.powf(n),
+        _ => 0.,
+    }
+}
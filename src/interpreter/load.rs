@@ -0,0 +1,297 @@
+//! Reading `project.json` into what the interpreter runs
+
+use std::collections::HashMap;
+
+use serde_json::Value as Json;
+
+use super::{Instance, InterpreterError, List, TargetState, Value, Variable};
+use crate::scripting::menu::RotationStyle;
+
+/// Blocks and assets of a target, shared between the target and its clones
+#[derive(Debug)]
+pub(super) struct Code {
+    pub name: String,
+    pub is_stage: bool,
+    pub blocks: HashMap<String, Block>,
+    /// Id of top level blocks, sorted by id.
+    /// Scratch uses the order they are in the file but `serde_json` doesn't keep it,
+    /// so hats of a target may start in a different order than in Scratch.
+    pub scripts: Vec<String>,
+    /// Custom block definitions by proccode
+    pub procedures: HashMap<String, Procedure>,
+    pub costumes: Vec<Costume>,
+    pub sounds: Vec<Sound>,
+}
+
+#[derive(Debug)]
+pub(super) struct Block {
+    pub opcode: String,
+    pub next: Option<String>,
+    pub inputs: HashMap<String, Input>,
+    pub fields: HashMap<String, Field>,
+    pub mutation: Option<Json>,
+    pub shadow: bool,
+}
+
+#[derive(Debug)]
+pub(super) enum Input {
+    Block(String),
+    Literal(Value),
+    Broadcast(String),
+    Variable(Field),
+    List(Field),
+    Empty,
+}
+
+#[derive(Debug, Clone)]
+pub(super) struct Field {
+    pub value: String,
+    pub id: Option<String>,
+}
+
+#[derive(Debug)]
+pub(super) struct Procedure {
+    /// First block below the definition hat
+    pub body: Option<String>,
+    pub arg_ids: Vec<String>,
+    pub arg_names: Vec<String>,
+    pub warp: bool,
+}
+
+#[derive(Debug)]
+pub(super) struct Costume {
+    pub name: String,
+    pub rotation_center: (f64, f64),
+    pub bitmap_resolution: f64,
+}
+
+#[derive(Debug)]
+pub(super) struct Sound {
+    pub name: String,
+    /// In seconds
+    pub duration: f64,
+}
+
+pub(super) fn load_target(json: &Json) -> Result<(Code, Instance), InterpreterError> {
+    let invalid = |what: &str| InterpreterError::InvalidProject(format!("target {}", what));
+    let name = json["name"]
+        .as_str()
+        .ok_or_else(|| invalid("has no name"))?;
+    let is_stage = json["isStage"].as_bool().unwrap_or(false);
+
+    let mut blocks = HashMap::new();
+    let mut scripts = vec![];
+    if let Some(json_blocks) = json["blocks"].as_object() {
+        for (id, block) in json_blocks {
+            // Floating variable reporters are stored as array, they never run
+            if !block.is_object() {
+                continue;
+            }
+            if block["topLevel"].as_bool().unwrap_or(false) {
+                scripts.push(id.clone());
+            }
+            blocks.insert(id.clone(), load_block(block));
+        }
+    }
+    let procedures = blocks
+        .values()
+        .filter(|b| b.opcode == "procedures_definition")
+        .filter_map(|definition| load_procedure(definition, &blocks))
+        .collect();
+
+    let costumes = json["costumes"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|c| Costume {
+            name: c["name"].as_str().unwrap_or_default().to_owned(),
+            rotation_center: (
+                c["rotationCenterX"].as_f64().unwrap_or(0.),
+                c["rotationCenterY"].as_f64().unwrap_or(0.),
+            ),
+            bitmap_resolution: c["bitmapResolution"].as_f64().unwrap_or(1.),
+        })
+        .collect();
+    let sounds = json["sounds"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|s| {
+            let rate = s["rate"].as_f64().unwrap_or(0.);
+            let sample_count = s["sampleCount"].as_f64().unwrap_or(0.);
+            Sound {
+                name: s["name"].as_str().unwrap_or_default().to_owned(),
+                duration: if rate > 0. { sample_count / rate } else { 0. },
+            }
+        })
+        .collect();
+
+    let variables = json["variables"]
+        .as_object()
+        .into_iter()
+        .flatten()
+        .map(|(id, var)| {
+            let variable = Variable {
+                name: var[0].as_str().unwrap_or_default().to_owned(),
+                value: Value::from_json(&var[1]),
+            };
+            (id.clone(), variable)
+        })
+        .collect();
+    let lists = json["lists"]
+        .as_object()
+        .into_iter()
+        .flatten()
+        .map(|(id, list)| {
+            let list = List {
+                name: list[0].as_str().unwrap_or_default().to_owned(),
+                items: list[1]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .map(Value::from_json)
+                    .collect(),
+            };
+            (id.clone(), list)
+        })
+        .collect();
+
+    let state = TargetState {
+        x: json["x"].as_f64().unwrap_or(0.),
+        y: json["y"].as_f64().unwrap_or(0.),
+        direction: json["direction"].as_f64().unwrap_or(90.),
+        size: json["size"].as_f64().unwrap_or(100.),
+        visible: json["visible"].as_bool().unwrap_or(true),
+        costume: json["currentCostume"].as_u64().unwrap_or(0) as usize,
+        rotation_style: json["rotationStyle"]
+            .as_str()
+            .and_then(RotationStyle::parse)
+            .unwrap_or_default(),
+        draggable: json["draggable"].as_bool().unwrap_or(false),
+        volume: json["volume"].as_f64().unwrap_or(100.),
+        ..Default::default()
+    };
+
+    let code = Code {
+        name: name.to_owned(),
+        is_stage,
+        blocks,
+        scripts,
+        procedures,
+        costumes,
+        sounds,
+    };
+    let instance = Instance {
+        id: 0,
+        target: 0,
+        is_clone: false,
+        variables,
+        lists,
+        state,
+    };
+    Ok((code, instance))
+}
+
+fn load_block(json: &Json) -> Block {
+    let inputs = json["inputs"]
+        .as_object()
+        .into_iter()
+        .flatten()
+        .map(|(name, input)| (name.clone(), load_input(&input[1])))
+        .collect();
+    let fields = json["fields"]
+        .as_object()
+        .into_iter()
+        .flatten()
+        .map(|(name, field)| (name.clone(), load_field(field)))
+        .collect();
+    Block {
+        opcode: json["opcode"].as_str().unwrap_or_default().to_owned(),
+        next: json["next"].as_str().map(str::to_owned),
+        inputs,
+        fields,
+        mutation: json.get("mutation").cloned(),
+        shadow: json["shadow"].as_bool().unwrap_or(false),
+    }
+}
+
+/// The value that's in the input, `[1, <this>]`, `[2, <this>]` or `[3, <this>, <shadow>]`
+fn load_input(json: &Json) -> Input {
+    match json {
+        Json::String(id) => Input::Block(id.clone()),
+        Json::Array(primitive) => {
+            // a malformed primitive without a value is treated as an empty input
+            let Some(value) = primitive.get(1) else {
+                return Input::Empty;
+            };
+            let field = || Field {
+                value: value.as_str().unwrap_or_default().to_owned(),
+                id: primitive.get(2).and_then(Json::as_str).map(str::to_owned),
+            };
+            match primitive.first().and_then(Json::as_u64) {
+                Some(4..=10) => Input::Literal(Value::from_json(value)),
+                Some(11) => Input::Broadcast(field().value),
+                Some(12) => Input::Variable(field()),
+                Some(13) => Input::List(field()),
+                _ => Input::Empty,
+            }
+        }
+        _ => Input::Empty,
+    }
+}
+
+fn load_field(json: &Json) -> Field {
+    Field {
+        value: match &json[0] {
+            Json::String(s) => s.clone(),
+            Json::Null => String::new(),
+            other => other.to_string(),
+        },
+        id: json[1].as_str().map(str::to_owned),
+    }
+}
+
+fn load_procedure(
+    definition: &Block,
+    blocks: &HashMap<String, Block>,
+) -> Option<(String, Procedure)> {
+    let prototype = match definition.inputs.get("custom_block")? {
+        Input::Block(id) => blocks.get(id)?,
+        _ => return None,
+    };
+    let mutation = prototype.mutation.as_ref()?;
+    let procedure = Procedure {
+        body: definition.next.clone(),
+        arg_ids: json_string_array(&mutation["argumentids"]),
+        arg_names: json_string_array(&mutation["argumentnames"]),
+        warp: json_bool(&mutation["warp"]),
+    };
+    Some((mutation["proccode"].as_str()?.to_owned(), procedure))
+}
+
+/// Scratch stores arrays in mutation as json encoded string
+fn json_string_array(json: &Json) -> Vec<String> {
+    let parsed;
+    let array = match json {
+        Json::String(s) => {
+            parsed = serde_json::from_str::<Json>(s).unwrap_or_default();
+            &parsed
+        }
+        other => other,
+    };
+    array
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|s| s.as_str().unwrap_or_default().to_owned())
+        .collect()
+}
+
+/// Scratch stores booleans in mutation as `"true"` or `"false"`
+fn json_bool(json: &Json) -> bool {
+    match json {
+        Json::Bool(b) => *b,
+        Json::String(s) => s == "true",
+        _ => false,
+    }
+}
@@ -0,0 +1,839 @@
+//! Headless interpreter to run projects without the Scratch editor
+//!
+//! # Explanation
+//!
+//! The interpreter runs `project.json` like Scratch does at 30 frames per second
+//! without rendering anything, so generated scripts can be tested in plain `cargo test`.
+//!
+//! Every frame, each running script (thread) runs once until it yields.
+//! A script yields at the end of each loop iteration and when a custom block calls itself
+//! (unless it's inside a "run without screen refresh" custom block)
+//! and while waiting, e.g. `wait`, `glide`, `ask and wait` or `broadcast and wait`.
+//! "Run without screen refresh" scripts still yield after a large number of loop iterations
+//! and custom block calls in a frame, so they can't hang the interpreter.
+//! Scripts run in the order they were started, new scripts started by a broadcast run in the same frame.
+//!
+//! Everything is deterministic: time only moves forward by [`Interpreter::step`],
+//! `pick random` uses a seeded generator and the current date is set by [`Interpreter::set_clock`].
+//!
+//! What's not modelled:
+//!  - Running scripts more than once a frame. Scratch keeps running the scripts again
+//!    in the same frame until something visible changes or most of the frame's time is used,
+//!    so a loop that only changes variables runs many iterations per frame in Scratch
+//!    depending on how fast the computer is.
+//!    Here it always runs one iteration per frame to be deterministic,
+//!    so frame counts only match Scratch for scripts that change something visible every iteration.
+//!  - Rendering. Sprites collide by bounding box derived from costume's rotation center,
+//!    `touching color` is always false and sprites are not fenced inside the stage.
+//!  - Sounds. `play sound until done` only waits for the duration of the sound.
+//!  - Extensions. Blocks that are not supported are recorded in [`Interpreter::unsupported_opcodes`]
+//!    and do nothing.
+//!
+//! # Usage
+//!
+//! ```
+//! # use sb_scratchy::blocks::*;
+//! # use sb_scratchy::project::*;
+//! # use sb_scratchy::scripting::arg::Number;
+//! # use sb_scratchy::interpreter::{Interpreter, Value};
+//! let mut stage = StageBuilder::default();
+//! let score = stage.declare_var::<Number>("score", 0.);
+//! let cat = SpriteBuilder::new(TargetBuilder::new("Cat")).add_script(
+//!     when_flag_clicked()
+//!         .next(repeat(10u32, Some(change_var_by(&score, 1).next(move_steps(5)))))
+//!         .next(say("done")),
+//! );
+//! let project = ProjectBuilder::new().set_stage(stage).add_sprite(cat).build().unwrap();
+//!
+//! let mut interpreter = Interpreter::new(&project).unwrap();
+//! interpreter.green_flag();
+//! interpreter.run_frames(5);
+//! assert_eq!(interpreter.variable("Stage", "score"), Some(&Value::Number(5.)));
+//!
+//! interpreter.run_frames(10);
+//! assert_eq!(interpreter.sprite("Cat").unwrap().x, 50.);
+//! assert_eq!(interpreter.say_bubble("Cat"), Some("done"));
+//! ```
+
+mod exec;
+mod load;
+mod value;
+
+#[cfg(test)]
+mod tests;
+
+use std::{
+    collections::{BTreeSet, HashMap, HashSet, VecDeque},
+    fmt,
+    rc::Rc,
+};
+
+use serde_json::Value as Json;
+
+use crate::project::Project;
+use crate::scripting::menu::{LooksEffect, RotationStyle, SoundEffect};
+use load::{Block, Code};
+
+pub use value::Value;
+
+/// Frames per second of Scratch
+pub const FRAME_RATE: f64 = 30.;
+/// Maximum number of clones at the same time, same as Scratch
+pub const CLONE_LIMIT: usize = 300;
+
+#[derive(Debug)]
+pub enum InterpreterError {
+    Json(serde_json::Error),
+    InvalidProject(String),
+}
+
+impl fmt::Display for InterpreterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InterpreterError::Json(e) => write!(f, "json error: {}", e),
+            InterpreterError::InvalidProject(msg) => write!(f, "invalid project: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for InterpreterError {}
+
+impl From<serde_json::Error> for InterpreterError {
+    fn from(e: serde_json::Error) -> Self {
+        InterpreterError::Json(e)
+    }
+}
+
+/// Speech bubble of a sprite
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bubble {
+    pub kind: BubbleKind,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BubbleKind {
+    Say,
+    Think,
+}
+
+/// State of the stage, a sprite or a clone.
+///
+/// Position and motion related fields are unused for the stage.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TargetState {
+    pub x: f64,
+    pub y: f64,
+    pub direction: f64,
+    pub size: f64,
+    pub visible: bool,
+    /// Index of the current costume or backdrop
+    pub costume: usize,
+    pub rotation_style: RotationStyle,
+    pub draggable: bool,
+    pub volume: f64,
+    pub bubble: Option<Bubble>,
+    pub looks_effects: HashMap<LooksEffect, f64>,
+    pub sound_effects: HashMap<SoundEffect, f64>,
+}
+
+impl Default for TargetState {
+    fn default() -> Self {
+        TargetState {
+            x: 0.,
+            y: 0.,
+            direction: 90.,
+            size: 100.,
+            visible: true,
+            costume: 0,
+            rotation_style: RotationStyle::AllAround,
+            draggable: false,
+            volume: 100.,
+            bubble: None,
+            looks_effects: HashMap::new(),
+            sound_effects: HashMap::new(),
+        }
+    }
+}
+
+/// Date and time reported by `current` and `days since 2000`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Clock {
+    pub year: u32,
+    pub month: u32,
+    pub date: u32,
+    /// 1 is Sunday
+    pub day_of_week: u32,
+    pub hour: u32,
+    pub minute: u32,
+    pub second: u32,
+    pub days_since_2000: f64,
+}
+
+impl Default for Clock {
+    /// 2000-01-01 00:00:00, a Saturday
+    fn default() -> Self {
+        Clock {
+            year: 2000,
+            month: 1,
+            date: 1,
+            day_of_week: 7,
+            hour: 0,
+            minute: 0,
+            second: 0,
+            days_since_2000: 0.,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Variable {
+    name: String,
+    value: Value,
+}
+
+#[derive(Debug, Clone)]
+struct List {
+    name: String,
+    items: Vec<Value>,
+}
+
+/// The stage, a sprite or a clone at runtime
+#[derive(Debug)]
+struct Instance {
+    id: u64,
+    /// Index in [`Interpreter::code`]
+    target: usize,
+    is_clone: bool,
+    variables: HashMap<String, Variable>,
+    lists: HashMap<String, List>,
+    state: TargetState,
+}
+
+/// A running script
+#[derive(Debug, Default)]
+struct Thread {
+    id: u64,
+    instance: u64,
+    hat: String,
+    frames: Vec<Frame>,
+    done: bool,
+}
+
+impl Thread {
+    fn new(id: u64, instance: u64, hat: String, start: Option<String>) -> Thread {
+        Thread {
+            id,
+            instance,
+            hat,
+            frames: vec![Frame::new(start, FrameKind::Script, false, Rc::default())],
+            done: false,
+        }
+    }
+
+    fn restart(&mut self, start: Option<String>) {
+        self.frames = vec![Frame::new(start, FrameKind::Script, false, Rc::default())];
+        self.done = false;
+    }
+}
+
+/// Position in a stack of blocks that's being run
+#[derive(Debug)]
+struct Frame {
+    block: Option<String>,
+    kind: FrameKind,
+    warp: bool,
+    /// Proccode of the custom block if this is its body
+    procedure: Option<String>,
+    /// Arguments of the custom block this is in
+    params: Rc<HashMap<String, Value>>,
+    /// State of `block` that runs across frames, e.g. how many times left to repeat
+    state: Option<BlockState>,
+}
+
+impl Frame {
+    fn new(
+        block: Option<String>,
+        kind: FrameKind,
+        warp: bool,
+        params: Rc<HashMap<String, Value>>,
+    ) -> Frame {
+        Frame {
+            block,
+            kind,
+            warp,
+            procedure: None,
+            params,
+            state: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FrameKind {
+    Script,
+    /// Substack of if. Continue below the if when it's done
+    Substack,
+    /// Substack of a loop. Run the loop block again when it's done
+    LoopBody,
+    /// Body of a custom block
+    Procedure,
+}
+
+#[derive(Debug)]
+enum BlockState {
+    Repeat(i64),
+    WaitUntil(f64),
+    Glide {
+        start: f64,
+        duration: f64,
+        from: (f64, f64),
+        to: (f64, f64),
+    },
+    Threads(Vec<u64>),
+    Asking,
+}
+
+/// Headless Scratch interpreter
+///
+/// See module documentation for usage.
+#[derive(Debug)]
+pub struct Interpreter {
+    code: Vec<Rc<Code>>,
+    /// The stage is always the first one
+    instances: Vec<Instance>,
+    /// Sprite instance ids from back to front
+    layers: Vec<u64>,
+    threads: Vec<Thread>,
+    next_instance_id: u64,
+    next_thread_id: u64,
+    frame: u64,
+    timer_start: f64,
+    answer: String,
+    /// Thread that's asking and the question
+    questions: VecDeque<(u64, String)>,
+    /// Answers given before the question was asked
+    answers: VecDeque<String>,
+    keys_pressed: HashSet<String>,
+    mouse: (f64, f64),
+    mouse_down: bool,
+    loudness: f64,
+    username: String,
+    clock: Clock,
+    rng: u64,
+    /// Last result of edge triggered hats, e.g. `when timer > 10`
+    edge_triggered: HashMap<(u64, String), bool>,
+    unsupported: BTreeSet<String>,
+    /// Id, instance and hat of the thread that's running, it's taken out of `threads` while running
+    current_thread: Option<(u64, u64, String)>,
+    restart_current: bool,
+}
+
+impl Interpreter {
+    /// Load a project built with this crate
+    pub fn new(project: &Project) -> Result<Interpreter, InterpreterError> {
+        Interpreter::from_json(&serde_json::to_value(project.project())?)
+    }
+
+    /// Load a parsed `project.json`
+    pub fn from_json(json: &Json) -> Result<Interpreter, InterpreterError> {
+        let targets = json["targets"]
+            .as_array()
+            .ok_or_else(|| InterpreterError::InvalidProject("no targets".to_owned()))?;
+        let mut loaded = targets
+            .iter()
+            .map(load::load_target)
+            .collect::<Result<Vec<_>, _>>()?;
+        let stage_idx = loaded
+            .iter()
+            .position(|(code, _)| code.is_stage)
+            .ok_or_else(|| InterpreterError::InvalidProject("no stage".to_owned()))?;
+        let stage = loaded.remove(stage_idx);
+        loaded.insert(0, stage);
+
+        let mut layer_orders = targets
+            .iter()
+            .filter(|t| !t["isStage"].as_bool().unwrap_or(false))
+            .map(|t| t["layerOrder"].as_u64().unwrap_or(0))
+            .enumerate()
+            .map(|(idx, layer)| (layer, idx as u64 + 1))
+            .collect::<Vec<_>>();
+        layer_orders.sort();
+
+        let mut code = vec![];
+        let mut instances = vec![];
+        for (idx, (target_code, mut instance)) in loaded.into_iter().enumerate() {
+            instance.id = idx as u64;
+            instance.target = idx;
+            code.push(Rc::new(target_code));
+            instances.push(instance);
+        }
+        Ok(Interpreter {
+            next_instance_id: instances.len() as u64,
+            code,
+            instances,
+            layers: layer_orders.into_iter().map(|(_, id)| id).collect(),
+            threads: vec![],
+            next_thread_id: 0,
+            frame: 0,
+            timer_start: 0.,
+            answer: String::new(),
+            questions: VecDeque::new(),
+            answers: VecDeque::new(),
+            keys_pressed: HashSet::new(),
+            mouse: (0., 0.),
+            mouse_down: false,
+            loudness: -1.,
+            username: String::new(),
+            clock: Clock::default(),
+            rng: 0x2545_f491_4f6c_dd1d,
+            edge_triggered: HashMap::new(),
+            unsupported: BTreeSet::new(),
+            current_thread: None,
+            restart_current: false,
+        })
+    }
+
+    // Input =======================================================================
+
+    /// Seed of `pick random`
+    pub fn set_seed(&mut self, seed: u64) {
+        // xorshift can't have 0 state
+        self.rng = seed.max(1);
+    }
+
+    /// Click the green flag. Stop everything, reset the timer and start `when flag clicked` scripts.
+    pub fn green_flag(&mut self) {
+        self.stop_all();
+        self.timer_start = self.time();
+        self.edge_triggered.clear();
+        self.start_hats("event_whenflagclicked", |_| true, None);
+    }
+
+    /// Click the stop sign. Stop all scripts and delete all clones.
+    pub fn stop_all(&mut self) {
+        for thread in &mut self.threads {
+            thread.done = true;
+        }
+        self.instances.retain(|i| !i.is_clone);
+        let instances = &self.instances;
+        self.layers
+            .retain(|id| instances.iter().any(|i| i.id == *id));
+        for instance in &mut self.instances {
+            instance.state.bubble = None;
+            instance.state.looks_effects.clear();
+            instance.state.sound_effects.clear();
+        }
+        self.questions.clear();
+    }
+
+    /// Broadcast a message from outside, like another sprite would
+    pub fn broadcast(&mut self, name: &str) {
+        self.start_broadcast(name);
+    }
+
+    /// Press a key, e.g. `"space"`, `"a"` or `"left arrow"`. The key stays pressed until released.
+    pub fn press_key(&mut self, key: &str) {
+        let key = key.to_lowercase();
+        self.keys_pressed.insert(key.clone());
+        self.start_hats(
+            "event_whenkeypressed",
+            |b| {
+                let option = field(b, "KEY_OPTION").to_lowercase();
+                option == key || option == "any"
+            },
+            None,
+        );
+    }
+
+    pub fn release_key(&mut self, key: &str) {
+        self.keys_pressed.remove(&key.to_lowercase());
+    }
+
+    /// Click a sprite. Starts `when this sprite clicked` of the original sprite, not the clones.
+    pub fn click_sprite(&mut self, name: &str) {
+        if let Some(instance) = self.original(name).filter(|i| i.id != 0).map(|i| i.id) {
+            self.start_hats("event_whenthisspriteclicked", |_| true, Some(instance));
+        }
+    }
+
+    pub fn click_stage(&mut self) {
+        self.start_hats("event_whenstageclicked", |_| true, Some(0));
+    }
+
+    /// Answer the question being asked.
+    /// If nothing is being asked, the answer is used for the next question.
+    pub fn answer(&mut self, answer: impl Into<String>) {
+        self.answers.push_back(answer.into());
+    }
+
+    pub fn set_mouse(&mut self, x: f64, y: f64) {
+        self.mouse = (x, y);
+    }
+
+    pub fn set_mouse_down(&mut self, mouse_down: bool) {
+        self.mouse_down = mouse_down;
+    }
+
+    /// Loudness of the microphone from 0 to 100, -1 if there's no microphone (default)
+    pub fn set_loudness(&mut self, loudness: f64) {
+        self.loudness = loudness;
+    }
+
+    pub fn set_username(&mut self, username: impl Into<String>) {
+        self.username = username.into();
+    }
+
+    pub fn set_clock(&mut self, clock: Clock) {
+        self.clock = clock;
+    }
+
+    // Running =====================================================================
+
+    /// Run a single frame
+    pub fn step(&mut self) {
+        self.start_edge_triggered_hats();
+        let mut idx = 0;
+        // threads started in this frame also run in this frame
+        while idx < self.threads.len() {
+            if !self.threads[idx].done {
+                self.step_thread(idx);
+            }
+            idx += 1;
+        }
+        self.threads.retain(|t| !t.done);
+        self.frame += 1;
+    }
+
+    pub fn run_frames(&mut self, frames: u64) {
+        for _ in 0..frames {
+            self.step();
+        }
+    }
+
+    /// Run until every script is done or `max_frames` has passed.
+    /// Returns if every script is done.
+    pub fn run_until_idle(&mut self, max_frames: u64) -> bool {
+        for _ in 0..max_frames {
+            if !self.is_running() {
+                return true;
+            }
+            self.step();
+        }
+        !self.is_running()
+    }
+
+    // Output ======================================================================
+
+    /// If any script is running
+    pub fn is_running(&self) -> bool {
+        self.threads.iter().any(|t| !t.done)
+    }
+
+    /// Frames that has run
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    /// Seconds since the interpreter started
+    pub fn time(&self) -> f64 {
+        self.frame as f64 / FRAME_RATE
+    }
+
+    /// Value of the `timer` reporter
+    pub fn timer(&self) -> f64 {
+        self.time() - self.timer_start
+    }
+
+    /// Question that's waiting for [`Interpreter::answer`]
+    pub fn pending_question(&self) -> Option<&str> {
+        self.questions.front().map(|(_, q)| q.as_str())
+    }
+
+    /// Value of a variable in the stage (`"Stage"`) or a sprite.
+    /// Clones are ignored.
+    pub fn variable(&self, target: &str, name: &str) -> Option<&Value> {
+        self.original(target)?
+            .variables
+            .values()
+            .find(|v| v.name == name)
+            .map(|v| &v.value)
+    }
+
+    /// Items of a list in the stage (`"Stage"`) or a sprite.
+    /// Clones are ignored.
+    pub fn list(&self, target: &str, name: &str) -> Option<&[Value]> {
+        self.original(target)?
+            .lists
+            .values()
+            .find(|l| l.name == name)
+            .map(|l| l.items.as_slice())
+    }
+
+    pub fn stage(&self) -> &TargetState {
+        &self.instances[0].state
+    }
+
+    /// State of the original sprite
+    pub fn sprite(&self, name: &str) -> Option<&TargetState> {
+        self.original(name).filter(|i| i.id != 0).map(|i| &i.state)
+    }
+
+    /// State of every clone of a sprite, oldest first
+    pub fn clones<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a TargetState> + 'a {
+        self.instances
+            .iter()
+            .filter(move |i| i.is_clone && self.code[i.target].name == name)
+            .map(|i| &i.state)
+    }
+
+    /// What the original sprite is saying, thinking doesn't count
+    pub fn say_bubble(&self, name: &str) -> Option<&str> {
+        match &self.sprite(name)?.bubble {
+            Some(Bubble {
+                kind: BubbleKind::Say,
+                text,
+            }) => Some(text),
+            _ => None,
+        }
+    }
+
+    /// Name of the current costume of the original sprite
+    pub fn costume_name(&self, name: &str) -> Option<&str> {
+        let instance = self.original(name)?;
+        self.code[instance.target]
+            .costumes
+            .get(instance.state.costume)
+            .map(|c| c.name.as_str())
+    }
+
+    /// Name of the current backdrop
+    pub fn backdrop_name(&self) -> Option<&str> {
+        self.code[0]
+            .costumes
+            .get(self.instances[0].state.costume)
+            .map(|c| c.name.as_str())
+    }
+
+    /// Opcodes that has run but are not supported by the interpreter
+    pub fn unsupported_opcodes(&self) -> &BTreeSet<String> {
+        &self.unsupported
+    }
+
+    // Internal ====================================================================
+
+    fn original(&self, name: &str) -> Option<&Instance> {
+        self.instances
+            .iter()
+            .find(|i| !i.is_clone && self.code[i.target].name == name)
+    }
+
+    fn instance(&self, id: u64) -> Option<&Instance> {
+        self.instances.iter().find(|i| i.id == id)
+    }
+
+    fn instance_mut(&mut self, id: u64) -> Option<&mut Instance> {
+        self.instances.iter_mut().find(|i| i.id == id)
+    }
+
+    /// Instances in the order Scratch runs hats, front sprite first and the stage last
+    fn execution_order(&self) -> Vec<u64> {
+        self.layers.iter().rev().copied().chain([0]).collect()
+    }
+
+    /// Start or restart scripts under hat with `opcode` that `filter` accepts.
+    /// Returns id of the threads.
+    fn start_hats(
+        &mut self,
+        opcode: &str,
+        filter: impl Fn(&Block) -> bool,
+        only: Option<u64>,
+    ) -> Vec<u64> {
+        let instances = match only {
+            Some(id) => vec![id],
+            None => self.execution_order(),
+        };
+        let mut started = vec![];
+        for instance_id in instances {
+            let Some(instance) = self.instance(instance_id) else {
+                continue;
+            };
+            let code = self.code[instance.target].clone();
+            for hat_id in &code.scripts {
+                let hat = &code.blocks[hat_id];
+                if hat.opcode == opcode && filter(hat) {
+                    started.push(self.start_thread(instance_id, hat_id, hat.next.clone()));
+                }
+            }
+        }
+        started
+    }
+
+    fn start_thread(&mut self, instance: u64, hat: &str, start: Option<String>) -> u64 {
+        let existing = self
+            .threads
+            .iter_mut()
+            .find(|t| !t.done && t.instance == instance && t.hat == hat);
+        if let Some(thread) = existing {
+            thread.restart(start);
+            return thread.id;
+        }
+        if let Some((current_id, current_instance, current_hat)) = &self.current_thread {
+            if *current_instance == instance && current_hat == hat {
+                self.restart_current = true;
+                return *current_id;
+            }
+        }
+        let id = self.next_thread_id;
+        self.next_thread_id += 1;
+        self.threads
+            .push(Thread::new(id, instance, hat.to_owned(), start));
+        id
+    }
+
+    fn start_broadcast(&mut self, name: &str) -> Vec<u64> {
+        let name = name.to_uppercase();
+        self.start_hats(
+            "event_whenbroadcastreceived",
+            |b| field(b, "BROADCAST_OPTION").to_uppercase() == name,
+            None,
+        )
+    }
+
+    fn start_backdrop_hats(&mut self) -> Vec<u64> {
+        let Some(backdrop) = self.backdrop_name().map(str::to_uppercase) else {
+            return vec![];
+        };
+        self.start_hats(
+            "event_whenbackdropswitchesto",
+            |b| field(b, "BACKDROP").to_uppercase() == backdrop,
+            None,
+        )
+    }
+
+    fn start_edge_triggered_hats(&mut self) {
+        for instance_id in self.execution_order() {
+            let Some(instance) = self.instance(instance_id) else {
+                continue;
+            };
+            let code = self.code[instance.target].clone();
+            for hat_id in &code.scripts {
+                let hat = &code.blocks[hat_id];
+                if hat.opcode != "event_whengreaterthan" {
+                    continue;
+                }
+                let ctx = exec::Ctx {
+                    code: &code,
+                    instance: instance_id,
+                    params: &HashMap::new(),
+                };
+                let value = self.input(&ctx, hat, "VALUE").to_number();
+                let current = match field(hat, "WHENGREATERTHANMENU").to_uppercase().as_str() {
+                    "TIMER" => self.timer(),
+                    "LOUDNESS" => self.loudness,
+                    _ => continue,
+                };
+                let triggered = current > value;
+                let was_triggered = self
+                    .edge_triggered
+                    .insert((instance_id, hat_id.clone()), triggered)
+                    .unwrap_or(false);
+                if triggered && !was_triggered {
+                    self.start_thread(instance_id, hat_id, hat.next.clone());
+                }
+            }
+        }
+    }
+
+    fn step_thread(&mut self, idx: usize) {
+        let placeholder = Thread {
+            done: true,
+            ..Default::default()
+        };
+        let mut thread = std::mem::replace(&mut self.threads[idx], placeholder);
+        self.current_thread = Some((thread.id, thread.instance, thread.hat.clone()));
+        self.run_thread(&mut thread);
+        self.current_thread = None;
+        if std::mem::take(&mut self.restart_current) {
+            let start = self
+                .instance(thread.instance)
+                .and_then(|i| self.code[i.target].blocks.get(&thread.hat))
+                .and_then(|hat| hat.next.clone());
+            thread.restart(start);
+        }
+        self.threads[idx] = thread;
+    }
+
+    /// Clone of `parent`, placed right behind it
+    fn create_clone(&mut self, parent: u64) {
+        if parent == 0 || self.instances.iter().filter(|i| i.is_clone).count() >= CLONE_LIMIT {
+            return;
+        }
+        let id = self.next_instance_id;
+        let Some(parent_instance) = self.instance(parent) else {
+            return;
+        };
+        let clone = Instance {
+            id,
+            target: parent_instance.target,
+            is_clone: true,
+            variables: parent_instance.variables.clone(),
+            lists: parent_instance.lists.clone(),
+            state: TargetState {
+                bubble: None,
+                ..parent_instance.state.clone()
+            },
+        };
+        self.instances.push(clone);
+        self.next_instance_id += 1;
+        let layer = self.layers.iter().position(|l| *l == parent).unwrap_or(0);
+        self.layers.insert(layer, id);
+        self.start_hats("control_start_as_clone", |_| true, Some(id));
+    }
+
+    fn delete_clone(&mut self, id: u64) {
+        self.instances.retain(|i| !(i.is_clone && i.id == id));
+        self.layers.retain(|l| *l != id);
+        for thread in &mut self.threads {
+            if thread.instance == id {
+                thread.done = true;
+            }
+        }
+        self.questions
+            .retain(|(thread, _)| self.threads.iter().any(|t| t.id == *thread && !t.done));
+    }
+
+    /// xorshift64*, returns in `[0, 1)`
+    fn random(&mut self) -> f64 {
+        self.rng ^= self.rng >> 12;
+        self.rng ^= self.rng << 25;
+        self.rng ^= self.rng >> 27;
+        let n = self.rng.wrapping_mul(0x2545_f491_4f6c_dd1d);
+        (n >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// Value of a field, empty if it doesn't exist
+fn field<'a>(block: &'a Block, name: &str) -> &'a str {
+    block
+        .fields
+        .get(name)
+        .map(|f| f.value.as_str())
+        .unwrap_or_default()
+}
+
+/// Interpreter of a project with `stage` and `sprite`, with the green flag clicked
+#[cfg(test)]
+pub(crate) fn start(
+    stage: crate::project::StageBuilder,
+    sprite: crate::project::SpriteBuilder,
+) -> Interpreter {
+    let project = crate::project::ProjectBuilder::new()
+        .set_stage(stage)
+        .add_sprite(sprite)
+        .build()
+        .unwrap();
+    let mut interpreter = Interpreter::new(&project).unwrap();
+    interpreter.green_flag();
+    interpreter
+}
@@ -0,0 +1,352 @@
+//! Running scripts and checking the results against scratch-vm
+
+use serde_json::json;
+
+use super::*;
+use crate::blocks::*;
+use crate::project::{SpriteBuilder, StageBuilder, TargetBuilder};
+use crate::scripting::{
+    arg::{JustReporter, Myself, Number, Reporter, Scope, Value as ArgValue, VarHandle},
+    custom_block::CustomBlockBuilder,
+    menu::StopOption,
+    stack::{HatBlock, StackBlock, TypedStackBuilder},
+};
+
+/// Interpreter of a project with one sprite `"Cat"` that has `scripts` and a variable `"v"`
+fn run_cat<S, E>(scripts: Vec<TypedStackBuilder<S, E>>) -> Interpreter {
+    let mut cat = SpriteBuilder::new(TargetBuilder::new("Cat"));
+    cat.declare_var::<Number>("v", 0.);
+    let cat = scripts.into_iter().fold(cat, SpriteBuilder::add_script);
+    start(StageBuilder::default(), cat)
+}
+
+fn v() -> VarHandle<Number> {
+    VarHandle::new("v".to_owned(), Scope::Sprite)
+}
+
+fn v_of(interpreter: &Interpreter) -> f64 {
+    interpreter.variable("Cat", "v").unwrap().to_number()
+}
+
+/// A project with one sprite `"Cat"` that has a single script made of `blocks`
+fn project_with_blocks(blocks: Json) -> Json {
+    json!({
+        "targets": [
+            { "isStage": true, "name": "Stage", "variables": {}, "lists": {}, "blocks": {} },
+            {
+                "isStage": false,
+                "name": "Cat",
+                "variables": { "v": ["v", 0] },
+                "lists": {},
+                "blocks": blocks,
+            },
+        ]
+    })
+}
+
+#[test]
+fn malformed_inputs_are_empty() {
+    let project = project_with_blocks(json!({
+        "hat": { "opcode": "event_whenflagclicked", "next": "set", "topLevel": true },
+        "set": {
+            "opcode": "data_setvariableto",
+            "inputs": { "VALUE": [1, [4]] },
+            "fields": { "VARIABLE": ["v", "v"] },
+            "next": "say",
+        },
+        "say": { "opcode": "looks_say", "inputs": { "MESSAGE": [1, [12]] } },
+    }));
+    let mut interpreter = Interpreter::from_json(&project).unwrap();
+    interpreter.green_flag();
+    assert!(interpreter.run_until_idle(1));
+    assert_eq!(
+        interpreter.variable("Cat", "v"),
+        Some(&Value::Text(String::new()))
+    );
+    assert_eq!(interpreter.say_bubble("Cat"), None);
+}
+
+/// `count down n`: changes `v` by 1 then calls itself with `n - 1` until `n` is 0
+fn count_down(warp: bool) -> (HatBlock, StackBlock) {
+    let block = CustomBlockBuilder::new()
+        .text("count down")
+        .arg::<ArgValue>("n")
+        .warp(warp)
+        .build();
+    let n = || -> JustReporter<Number> { Reporter::new(block.arg_string_number("n").0) };
+    let definition = block.define().next(if_(
+        greater_than(n(), 0),
+        change_var_by(&v(), 1).next(block.call((sub(n(), 1),))),
+    ));
+    (definition, block.call((100,)))
+}
+
+#[test]
+fn recursive_call_yields() {
+    // scratch-vm yields when a custom block calls itself without screen refresh
+    let (definition, call) = count_down(false);
+    let mut interpreter = run_cat(vec![definition, when_flag_clicked().next(call)]);
+    interpreter.step();
+    assert_eq!(v_of(&interpreter), 1.);
+    interpreter.step();
+    assert_eq!(v_of(&interpreter), 2.);
+    assert!(interpreter.run_until_idle(200));
+    assert_eq!(v_of(&interpreter), 100.);
+}
+
+#[test]
+fn recursive_warp_call_runs_in_one_frame() {
+    let (definition, call) = count_down(true);
+    let mut interpreter = run_cat(vec![definition, when_flag_clicked().next(call)]);
+    interpreter.step();
+    assert_eq!(v_of(&interpreter), 100.);
+    assert!(!interpreter.is_running());
+}
+
+#[test]
+fn endless_recursion_does_not_hang() {
+    for warp in [false, true] {
+        let block = CustomBlockBuilder::new().text("again").warp(warp).build();
+        let definition = block
+            .define()
+            .next(change_var_by(&v(), 1))
+            .next(block.call(()));
+        let mut interpreter = run_cat(vec![definition, when_flag_clicked().next(block.call(()))]);
+        interpreter.run_frames(3);
+        assert!(interpreter.is_running());
+        if warp {
+            // runs many calls each frame but still yields
+            assert!(v_of(&interpreter) > 3. && v_of(&interpreter) <= 3. * 100_000.);
+        } else {
+            assert_eq!(v_of(&interpreter), 3.);
+        }
+    }
+}
+
+#[test]
+fn text_to_number() {
+    // Cast.toNumber in scratch-vm
+    let cases = [
+        ("12", 12.),
+        (" 12 ", 12.),
+        ("", 0.),
+        ("abc", 0.),
+        ("1e3", 1000.),
+        ("-2.5", -2.5),
+        (".5", 0.5),
+        ("0x10", 16.),
+        ("0b101", 5.),
+        ("-0x10", 0.),
+        ("1.2.3", 0.),
+        ("Infinity", f64::INFINITY),
+        ("-Infinity", f64::NEG_INFINITY),
+        ("inf", 0.),
+        ("NaN", 0.),
+    ];
+    for (text, number) in cases {
+        assert_eq!(Value::from(text).to_number(), number, "{:?}", text);
+    }
+    assert_eq!(Value::from(true).to_number(), 1.);
+    assert_eq!(Value::from(f64::NAN).to_number(), 0.);
+}
+
+#[test]
+fn number_to_text() {
+    // String(number) in JavaScript
+    let cases = [
+        (0., "0"),
+        (-0., "0"),
+        (3., "3"),
+        (-1.5, "-1.5"),
+        (0.1 + 0.2, "0.30000000000000004"),
+        (1e20, "100000000000000000000"),
+        (1e21, "1e+21"),
+        (1.5e300, "1.5e+300"),
+        (0.000001, "0.000001"),
+        (1e-7, "1e-7"),
+        (f64::INFINITY, "Infinity"),
+        (f64::NEG_INFINITY, "-Infinity"),
+        (f64::NAN, "NaN"),
+    ];
+    for (number, text) in cases {
+        assert_eq!(Value::from(number).to_text(), text, "{:?}", number);
+    }
+    assert_eq!(Value::from(false).to_text(), "false");
+}
+
+#[test]
+fn to_bool() {
+    // Cast.toBoolean in scratch-vm
+    for falsy in [
+        Value::from(""),
+        "0".into(),
+        "false".into(),
+        "FALSE".into(),
+        0.0.into(),
+    ] {
+        assert!(!falsy.to_bool(), "{:?}", falsy);
+    }
+    for truthy in [
+        Value::from(" "),
+        "0.0".into(),
+        "true".into(),
+        "no".into(),
+        (-1.).into(),
+    ] {
+        assert!(truthy.to_bool(), "{:?}", truthy);
+    }
+}
+
+#[test]
+fn compare() {
+    // Cast.compare in scratch-vm
+    use std::cmp::Ordering::*;
+    let cases: [(Value, Value, std::cmp::Ordering); 11] = [
+        ("10".into(), "9".into(), Greater),
+        ("10".into(), 10.0.into(), Equal),
+        (" 10 ".into(), "10.0".into(), Equal),
+        ("a".into(), "B".into(), Less),
+        ("abc".into(), "ABC".into(), Equal),
+        ("10".into(), "9a".into(), Less),
+        ("".into(), 0.0.into(), Less),
+        (" ".into(), "".into(), Greater),
+        (true.into(), "true".into(), Equal),
+        (true.into(), 1.0.into(), Equal),
+        ("Infinity".into(), 1e308.into(), Greater),
+    ];
+    for (a, b, ordering) in cases {
+        assert_eq!(a.compare(&b), ordering, "{:?} {:?}", a, b);
+        assert_eq!(b.compare(&a), ordering.reverse(), "{:?} {:?}", b, a);
+    }
+}
+
+#[test]
+fn loops_yield_each_iteration() {
+    let mut interpreter = run_cat(vec![
+        when_flag_clicked().next(repeat(3u32, Some(change_var_by(&v(), 1))))
+    ]);
+    for expected in [1., 2., 3.] {
+        interpreter.step();
+        assert_eq!(v_of(&interpreter), expected);
+    }
+    assert!(interpreter.run_until_idle(1));
+}
+
+#[test]
+fn wait_takes_frames_of_its_duration() {
+    let mut interpreter = run_cat(vec![when_flag_clicked()
+        .next(wait(1.))
+        .next(set_var_to(&v(), 1))]);
+    // 30 frames per second, the first frame starts the timer
+    interpreter.run_frames(30);
+    assert_eq!(v_of(&interpreter), 0.);
+    interpreter.step();
+    assert_eq!(v_of(&interpreter), 1.);
+    assert!(!interpreter.is_running());
+}
+
+#[test]
+fn broadcast_and_wait_waits_for_receivers() {
+    let mut stage = StageBuilder::default();
+    let go = stage.declare_broadcast("go");
+    let mut cat = SpriteBuilder::new(TargetBuilder::new("Cat"));
+    cat.declare_var::<Number>("v", 0.);
+    let cat = cat
+        .add_script(
+            when_flag_clicked()
+                .next(broadcast_and_wait(&go))
+                .next(set_var_to(&v(), var(&v()) * 10)),
+        )
+        .add_script(when_broadcast_received(&go).next(repeat(2u32, Some(change_var_by(&v(), 1)))));
+    let mut interpreter = start(stage, cat);
+    // the receiver starts in the same frame as the broadcast
+    interpreter.step();
+    assert_eq!(v_of(&interpreter), 1.);
+    assert!(interpreter.run_until_idle(10));
+    assert_eq!(v_of(&interpreter), 20.);
+}
+
+#[test]
+fn ask_waits_for_answer() {
+    let mut interpreter = run_cat(vec![when_flag_clicked()
+        .next(ask_and_wait("name?"))
+        .next(say(join(answer(), "!")))]);
+    interpreter.run_frames(5);
+    assert_eq!(interpreter.pending_question(), Some("name?"));
+    // a visible sprite says the question like in Scratch
+    assert_eq!(interpreter.say_bubble("Cat"), Some("name?"));
+    interpreter.answer("Bob");
+    assert!(interpreter.run_until_idle(5));
+    assert_eq!(interpreter.pending_question(), None);
+    assert_eq!(interpreter.say_bubble("Cat"), Some("Bob!"));
+}
+
+#[test]
+fn clones_have_their_own_variables() {
+    let mut interpreter = run_cat(vec![
+        when_flag_clicked().next(repeat(3u32, Some(create_clone_of(Myself)))),
+        when_i_start_as_a_clone()
+            .next(change_var_by(&v(), 1))
+            .next(set_x(var(&v()))),
+    ]);
+    assert!(interpreter.run_until_idle(10));
+    // each clone copies the variable from the original when it's created
+    let xs: Vec<f64> = interpreter.clones("Cat").map(|clone| clone.x).collect();
+    assert_eq!(xs, [1., 1., 1.]);
+    assert_eq!(v_of(&interpreter), 0.);
+}
+
+#[test]
+fn clones_stop_at_limit_and_can_be_deleted() {
+    let mut interpreter = run_cat(vec![when_flag_clicked().next(repeat(
+        CLONE_LIMIT as u32 + 10,
+        Some(create_clone_of(Myself)),
+    ))]);
+    assert!(interpreter.run_until_idle(CLONE_LIMIT as u64 + 20));
+    assert_eq!(interpreter.clones("Cat").count(), CLONE_LIMIT);
+
+    // the original sprite isn't deleted
+    let mut interpreter = run_cat(vec![
+        when_flag_clicked()
+            .next(create_clone_of(Myself))
+            .next(delete_this_clone()),
+        when_i_start_as_a_clone().next(delete_this_clone()),
+    ]);
+    assert!(interpreter.run_until_idle(10));
+    assert_eq!(interpreter.clones("Cat").count(), 0);
+    assert!(interpreter.sprite("Cat").is_some());
+}
+
+#[test]
+fn custom_block_arguments_and_stop() {
+    let block = CustomBlockBuilder::new()
+        .text("set v to")
+        .arg::<ArgValue>("n")
+        .text("unless")
+        .arg::<ArgValue>("skip")
+        .build();
+    let arg =
+        |name: &str| -> JustReporter<Number> { Reporter::new(block.arg_string_number(name).0) };
+    // stop this script inside a custom block returns to the caller
+    let definition = block
+        .define()
+        .next(if_(
+            greater_than(arg("skip"), 0),
+            stop(StopOption::ThisScript),
+        ))
+        .next(set_var_to(&v(), arg("n")));
+    let mut interpreter = run_cat(vec![
+        definition,
+        when_flag_clicked()
+            .next(block.call((5, 0)))
+            .next(change_var_by(&v(), 1))
+            .next(block.call((100, 1)))
+            .next(change_var_by(&v(), 1)),
+    ]);
+    assert!(interpreter.run_until_idle(1));
+    assert_eq!(v_of(&interpreter), 7.);
+    // arguments are empty outside of the custom block they belong to
+    let mut interpreter = run_cat(vec![when_flag_clicked().next(set_var_to(&v(), arg("n")))]);
+    assert!(interpreter.run_until_idle(1));
+    assert_eq!(interpreter.variable("Cat", "v"), Some(&Value::Number(0.)));
+}
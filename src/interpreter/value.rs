@@ -0,0 +1,198 @@
+//! Values at runtime and how Scratch casts them
+
+use std::{cmp::Ordering, fmt};
+
+use serde_json::Value as Json;
+
+/// A value of a variable, list item or reporter
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Number(f64),
+    Text(String),
+    Bool(bool),
+}
+
+impl Value {
+    /// Cast to number the way Scratch does, anything that isn't a number is `0`
+    pub fn to_number(&self) -> f64 {
+        let n = self.to_number_or_nan();
+        if n.is_nan() {
+            0.
+        } else {
+            n
+        }
+    }
+
+    /// Cast to text the way Scratch does
+    pub fn to_text(&self) -> String {
+        match self {
+            Value::Number(n) => number_to_text(*n),
+            Value::Text(t) => t.clone(),
+            Value::Bool(b) => b.to_string(),
+        }
+    }
+
+    /// Cast to boolean the way Scratch does.
+    ///
+    /// `""`, `"0"`, `"false"`, `0` and `NaN` are false.
+    pub fn to_bool(&self) -> bool {
+        match self {
+            Value::Number(n) => *n != 0. && !n.is_nan(),
+            Value::Text(t) => !(t.is_empty() || t == "0" || t.eq_ignore_ascii_case("false")),
+            Value::Bool(b) => *b,
+        }
+    }
+
+    /// Compare 2 values like Scratch's `<`, `=` and `>` blocks.
+    ///
+    /// Numbers are compared as numbers, otherwise compare as case-insensitive text.
+    pub fn compare(&self, other: &Value) -> Ordering {
+        let mut n1 = self.to_number_or_nan();
+        let mut n2 = other.to_number_or_nan();
+        if n1 == 0. && self.is_whitespace() {
+            n1 = f64::NAN;
+        }
+        if n2 == 0. && other.is_whitespace() {
+            n2 = f64::NAN;
+        }
+        if n1.is_nan() || n2.is_nan() {
+            let s1 = self.to_text().to_lowercase();
+            let s2 = other.to_text().to_lowercase();
+            return s1.cmp(&s2);
+        }
+        n1.partial_cmp(&n2).unwrap_or(Ordering::Equal)
+    }
+
+    /// If this is a whole number, used by the random block to decide between integer and decimal
+    pub(crate) fn is_int(&self) -> bool {
+        match self {
+            Value::Number(n) => n.fract() == 0.,
+            Value::Text(t) => !t.contains('.'),
+            Value::Bool(_) => true,
+        }
+    }
+
+    fn is_whitespace(&self) -> bool {
+        match self {
+            Value::Text(t) => t.trim().is_empty(),
+            _ => false,
+        }
+    }
+
+    /// Javascript's `Number(value)`
+    fn to_number_or_nan(&self) -> f64 {
+        match self {
+            Value::Number(n) => *n,
+            Value::Bool(b) => *b as u8 as f64,
+            Value::Text(t) => text_to_number(t),
+        }
+    }
+
+    pub(crate) fn from_json(json: &Json) -> Value {
+        match json {
+            Json::Number(n) => Value::Number(n.as_f64().unwrap_or(0.)),
+            Json::String(s) => Value::Text(s.clone()),
+            Json::Bool(b) => Value::Bool(*b),
+            Json::Null => Value::Text(String::new()),
+            other => Value::Text(other.to_string()),
+        }
+    }
+}
+
+impl Default for Value {
+    fn default() -> Self {
+        Value::Number(0.)
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_text())
+    }
+}
+
+impl From<f64> for Value {
+    fn from(n: f64) -> Self {
+        Value::Number(n)
+    }
+}
+
+impl From<i64> for Value {
+    fn from(n: i64) -> Self {
+        Value::Number(n as f64)
+    }
+}
+
+impl From<String> for Value {
+    fn from(t: String) -> Self {
+        Value::Text(t)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(t: &str) -> Self {
+        Value::Text(t.to_owned())
+    }
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Self {
+        Value::Bool(b)
+    }
+}
+
+/// Javascript's `Number(text)`
+fn text_to_number(text: &str) -> f64 {
+    let t = text.trim();
+    if t.is_empty() {
+        return 0.;
+    }
+    let (sign, unsigned) = match t.as_bytes()[0] {
+        b'-' => (-1., &t[1..]),
+        b'+' => (1., &t[1..]),
+        _ => (1., t),
+    };
+    if unsigned == "Infinity" {
+        return sign * f64::INFINITY;
+    }
+    for (prefix, radix) in [
+        ("0x", 16),
+        ("0X", 16),
+        ("0b", 2),
+        ("0B", 2),
+        ("0o", 8),
+        ("0O", 8),
+    ] {
+        if let Some(digits) = t.strip_prefix(prefix) {
+            return u64::from_str_radix(digits, radix)
+                .map(|n| n as f64)
+                .unwrap_or(f64::NAN);
+        }
+    }
+    let is_decimal = unsigned
+        .bytes()
+        .all(|c| c.is_ascii_digit() || matches!(c, b'.' | b'e' | b'E' | b'+' | b'-'));
+    if !is_decimal {
+        return f64::NAN;
+    }
+    t.parse().unwrap_or(f64::NAN)
+}
+
+/// Javascript's `String(number)`
+fn number_to_text(n: f64) -> String {
+    if n.is_nan() {
+        "NaN".to_owned()
+    } else if n.is_infinite() {
+        if n > 0. { "Infinity" } else { "-Infinity" }.to_owned()
+    } else if n == 0. {
+        "0".to_owned()
+    } else if n.abs() >= 1e21 || n.abs() < 1e-6 {
+        let text = format!("{:e}", n);
+        match text.split_once('e') {
+            Some((mantissa, exp)) if !exp.starts_with('-') => format!("{}e+{}", mantissa, exp),
+            _ => text,
+        }
+    } else {
+        n.to_string()
+    }
+}
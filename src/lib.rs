@@ -1,7 +1,61 @@
 //! # Create Scratch project in Rust
 
 pub mod blocks;
+mod ids;
+pub mod interpreter;
+pub mod project;
+pub mod sb3;
 pub mod scripting;
+pub mod stdlib;
+
+/// Build a script with Rust-like syntax.
+///
+/// Each statement is a block (or a whole stack) and they're stacked in order with `;` between them.
+/// Blocks from [`crate::blocks`] has to be in scope.
+///
+/// | Syntax | Block |
+/// |---|---|
+/// | `when_flag_clicked` | Calling a block with no argument, same as `when_flag_clicked()` |
+/// | `loop { .. }` | [`blocks::forever`] |
+/// | `repeat <times> { .. }` | [`blocks::repeat`] |
+/// | `until <cond> { .. }` | [`blocks::repeat_until`] |
+/// | `if <cond> { .. } else if <cond> { .. } else { .. }` | [`scripting::if_else_chain`] |
+/// | Other expression, e.g. `(my_stack)` | Stack it as-is |
+///
+/// Putting hat block anywhere but the top of the script
+/// or putting anything below a cap block is a compile error.
+///
+/// # Examples
+///
+/// ```
+/// # use sb_scratchy::blocks::*;
+/// # use sb_scratchy::scratch;
+/// # use sb_scratchy::scripting::arg::Edge;
+/// let script = scratch! {
+///     when_flag_clicked;
+///     loop {
+///         move_steps(10);
+///         if touching(Edge) {
+///             turn_right(180)
+///         }
+///     }
+/// };
+///
+/// // Same as
+/// let script = when_flag_clicked().next(forever(Some(
+///     move_steps(10).next(if_(touching(Edge), turn_right(180))),
+/// )));
+/// ```
+///
+/// ```compile_fail
+/// # use sb_scratchy::blocks::*;
+/// # use sb_scratchy::scratch;
+/// let script = scratch! {
+///     move_steps(10);
+///     when_flag_clicked; // hat block can only be at the top of a script
+/// };
+/// ```
+pub use sb_scratchy_macros::scratch;
 
 macro_rules! all_derive {
     (
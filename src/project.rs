@@ -0,0 +1,1116 @@
+//! Building a whole project
+//!
+//! # Usage
+//!
+//! ```
+//! # use sb_scratchy::blocks::*;
+//! # use sb_scratchy::project::*;
+//! # use sb_scratchy::scripting::arg::Number;
+//! let mut stage = StageBuilder::default();
+//! let score = stage.declare_var::<Number>("score", "0");
+//!
+//! let mut cat = SpriteBuilder::new(
+//!     TargetBuilder::new("Cat")
+//!         .add_costume(CostumeBuilder::new("cat", "svg", b"<svg/>".to_vec())),
+//! )
+//! .set_position(10., -20.)
+//! .set_rotation_style(RotationStyle::LeftRight);
+//! let speed = cat.declare_var::<Number>("speed", "10");
+//! let cat = cat.add_script(
+//!     when_flag_clicked()
+//!         .next(set_var_to(&score, 0))
+//!         .next(move_steps(&speed))
+//!         .next(change_var_by(&score, 1)),
+//! );
+//!
+//! let project = ProjectBuilder::new()
+//!     .set_stage(stage)
+//!     .add_sprite(cat)
+//!     .build()
+//!     .unwrap();
+//!
+//! let project_json = project.to_json().unwrap();
+//! ```
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use sb_itchy::{
+    asset::{
+        AssetBuilder, CostumeBuilder as ItchyCostumeBuilder, SoundBuilder as ItchySoundBuilder,
+    },
+    data::{BroadcastBuilder, ListBuilder, VariableBuilder},
+    project::ProjectBuilder as ItchyProjectBuilder,
+    resource::Resource,
+    target::{
+        SpriteBuilder as ItchySpriteBuilder, StageBuilder as ItchyStageBuilder,
+        TargetBuilder as ItchyTargetBuilder,
+    },
+    uid::Uid,
+};
+use sb_sbity::{
+    project::Project as SbityProject,
+    target::{RotationStyle as SbityRotationStyle, VideoState as SbityVideoState},
+    value::Value as ScratchValue,
+};
+use serde_json::Value as Json;
+
+use crate::scripting::{
+    arg::{
+        BroadcastHandle, DataId, ListHandle, Scope, SpriteHandle, VarHandle, BROADCAST_PLACEHOLDER,
+        DATA_ID_PREFIX,
+    },
+    custom_block::{return_var_name, CustomBlockBuilder, Function},
+    local::CallStack,
+    stack::TypedStackBuilder,
+};
+
+pub use crate::scripting::menu::{RotationStyle, VideoState};
+
+impl From<RotationStyle> for SbityRotationStyle {
+    fn from(style: RotationStyle) -> Self {
+        match style {
+            RotationStyle::AllAround => SbityRotationStyle::AllAround,
+            RotationStyle::LeftRight => SbityRotationStyle::LeftRight,
+            RotationStyle::DontRotate => SbityRotationStyle::DontRotate,
+        }
+    }
+}
+
+impl From<VideoState> for SbityVideoState {
+    fn from(state: VideoState) -> Self {
+        match state {
+            VideoState::On => SbityVideoState::On,
+            VideoState::Off => SbityVideoState::Off,
+            VideoState::OnFlipped => SbityVideoState::OnFlipped,
+        }
+    }
+}
+
+// Asset =======================================================================
+
+/// Asset file of a costume or sound.
+///
+/// Scratch names asset files by their md5 and extension, e.g. `"83a9787d4cb6f3b7632b4ddfebf74367.wav"`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Asset {
+    md5ext: String,
+    data: Vec<u8>,
+}
+
+impl Asset {
+    pub fn new(extension: &str, data: Vec<u8>) -> Asset {
+        Asset {
+            md5ext: format!("{:x}.{}", md5::compute(&data), extension),
+            data,
+        }
+    }
+
+    /// File name of the asset
+    pub fn md5ext(&self) -> &str {
+        &self.md5ext
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+/// Costume of a sprite or backdrop of the stage
+#[derive(Debug, Clone, PartialEq)]
+pub struct CostumeBuilder {
+    name: String,
+    extension: String,
+    data: Vec<u8>,
+    rotation_center: (f64, f64),
+    bitmap_resolution: Option<u64>,
+}
+
+impl CostumeBuilder {
+    /// `extension` is the file extension without the dot, e.g. `"svg"` or `"png"`
+    pub fn new(
+        name: impl Into<String>,
+        extension: impl Into<String>,
+        data: Vec<u8>,
+    ) -> CostumeBuilder {
+        CostumeBuilder {
+            name: name.into(),
+            extension: extension.into(),
+            data,
+            rotation_center: (0., 0.),
+            bitmap_resolution: None,
+        }
+    }
+
+    pub fn set_rotation_center(mut self, x: f64, y: f64) -> CostumeBuilder {
+        self.rotation_center = (x, y);
+        self
+    }
+
+    /// Usually `1` for vector and `2` for bitmap costume
+    pub fn set_bitmap_resolution(mut self, bitmap_resolution: u64) -> CostumeBuilder {
+        self.bitmap_resolution = Some(bitmap_resolution);
+        self
+    }
+
+    pub fn asset(&self) -> Asset {
+        Asset::new(&self.extension, self.data.clone())
+    }
+
+    fn into_itchy(self) -> ItchyCostumeBuilder {
+        let mut c = ItchyCostumeBuilder::new(AssetBuilder::new(
+            self.name,
+            Resource::new(self.extension, self.data),
+        ));
+        c.set_rotation_center(self.rotation_center.0, self.rotation_center.1);
+        if let Some(bitmap_resolution) = self.bitmap_resolution {
+            c.set_bitmap_resolution(bitmap_resolution);
+        }
+        c
+    }
+}
+
+/// Sound of a sprite or the stage
+#[derive(Debug, Clone, PartialEq)]
+pub struct SoundBuilder {
+    name: String,
+    extension: String,
+    data: Vec<u8>,
+    rate: u64,
+    sample_count: u64,
+}
+
+impl SoundBuilder {
+    /// `extension` is the file extension without the dot, e.g. `"wav"` or `"mp3"`
+    pub fn new(
+        name: impl Into<String>,
+        extension: impl Into<String>,
+        data: Vec<u8>,
+    ) -> SoundBuilder {
+        SoundBuilder {
+            name: name.into(),
+            extension: extension.into(),
+            data,
+            rate: 48000,
+            sample_count: 0,
+        }
+    }
+
+    pub fn set_rate(mut self, rate: u64) -> SoundBuilder {
+        self.rate = rate;
+        self
+    }
+
+    pub fn set_sample_count(mut self, sample_count: u64) -> SoundBuilder {
+        self.sample_count = sample_count;
+        self
+    }
+
+    pub fn asset(&self) -> Asset {
+        Asset::new(&self.extension, self.data.clone())
+    }
+
+    fn into_itchy(self) -> ItchySoundBuilder {
+        let mut s = ItchySoundBuilder::new(AssetBuilder::new(
+            self.name,
+            Resource::new(self.extension, self.data),
+        ));
+        s.set_rate(self.rate);
+        s.set_sample_count(self.sample_count);
+        s
+    }
+}
+
+// Target ======================================================================
+
+/// Things both stage and sprite have.
+///
+/// Variables and lists added to the stage's target are global (for all sprites).
+#[derive(Debug, Clone, PartialEq)]
+pub struct TargetBuilder {
+    name: String,
+    scripts: Vec<sb_itchy::stack::StackBuilder>,
+    variables: Vec<(String, ScratchValue)>,
+    lists: Vec<(String, Vec<ScratchValue>)>,
+    handles: Handles,
+    /// Proccodes of the custom blocks added by [`crate::stdlib::Stdlib::install`]
+    helpers: Vec<String>,
+    costumes: Vec<CostumeBuilder>,
+    sounds: Vec<SoundBuilder>,
+    current_costume: u64,
+    volume: f64,
+}
+
+/// Variables and lists declared with a handle, by the id in their handle
+#[derive(Debug, Clone, PartialEq, Default)]
+struct Handles {
+    variables: Vec<(DataId, String)>,
+    lists: Vec<(DataId, String)>,
+}
+
+impl TargetBuilder {
+    pub fn new(name: impl Into<String>) -> TargetBuilder {
+        TargetBuilder {
+            name: name.into(),
+            scripts: vec![],
+            variables: vec![],
+            lists: vec![],
+            handles: Handles::default(),
+            helpers: vec![],
+            costumes: vec![],
+            sounds: vec![],
+            current_costume: 0,
+            volume: 100.,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Add a script. Usually starts with a [`crate::scripting::stack::HatBlock`].
+    pub fn add_script<S, E>(mut self, script: TypedStackBuilder<S, E>) -> TargetBuilder {
+        self.scripts.push(script.into_untyped());
+        self
+    }
+
+    pub(crate) fn add_untyped_script(
+        mut self,
+        script: sb_itchy::stack::StackBuilder,
+    ) -> TargetBuilder {
+        self.scripts.push(script);
+        self
+    }
+
+    pub fn add_variable(
+        mut self,
+        name: impl Into<String>,
+        value: impl Into<ScratchValue>,
+    ) -> TargetBuilder {
+        self.variables.push((name.into(), value.into()));
+        self
+    }
+
+    pub fn add_list<V: Into<ScratchValue>>(
+        mut self,
+        name: impl Into<String>,
+        values: impl IntoIterator<Item = V>,
+    ) -> TargetBuilder {
+        self.lists
+            .push((name.into(), values.into_iter().map(Into::into).collect()));
+        self
+    }
+
+    fn declare_var<T>(
+        &mut self,
+        scope: Scope,
+        name: String,
+        value: impl Into<ScratchValue>,
+    ) -> VarHandle<T> {
+        let id = DataId::generate();
+        self.variables.push((name.clone(), value.into()));
+        self.handles.variables.push((id, name.clone()));
+        VarHandle::declared(id, name, scope)
+    }
+
+    fn declare_list<T, V: Into<ScratchValue>>(
+        &mut self,
+        scope: Scope,
+        name: String,
+        values: impl IntoIterator<Item = V>,
+    ) -> ListHandle<T> {
+        let id = DataId::generate();
+        self.lists
+            .push((name.clone(), values.into_iter().map(Into::into).collect()));
+        self.handles.lists.push((id, name.clone()));
+        ListHandle::declared(id, name, scope)
+    }
+
+    /// Add the variable `name` if the target has none of that name yet
+    pub(crate) fn add_variable_once(&mut self, name: &str, value: impl Into<ScratchValue>) {
+        if !self.variables.iter().any(|(n, _)| n == name) {
+            self.variables.push((name.to_owned(), value.into()));
+        }
+    }
+
+    /// Add the definition of the helper `proccode` if it isn't added yet
+    pub(crate) fn add_helper<S, E>(&mut self, proccode: &str, definition: TypedStackBuilder<S, E>) {
+        if !self.helpers.iter().any(|p| p == proccode) {
+            self.helpers.push(proccode.to_owned());
+            self.scripts.push(definition.into_untyped());
+        }
+    }
+
+    pub fn add_costume(mut self, costume: CostumeBuilder) -> TargetBuilder {
+        self.costumes.push(costume);
+        self
+    }
+
+    pub fn add_sound(mut self, sound: SoundBuilder) -> TargetBuilder {
+        self.sounds.push(sound);
+        self
+    }
+
+    /// Index of the costume that is shown
+    pub fn set_current_costume(mut self, current_costume: u64) -> TargetBuilder {
+        self.current_costume = current_costume;
+        self
+    }
+
+    pub fn set_volume(mut self, volume: f64) -> TargetBuilder {
+        self.volume = volume;
+        self
+    }
+
+    fn assets(&self) -> impl Iterator<Item = Asset> + '_ {
+        let costumes = self.costumes.iter().map(CostumeBuilder::asset);
+        let sounds = self.sounds.iter().map(SoundBuilder::asset);
+        costumes.chain(sounds)
+    }
+
+    fn into_itchy(self, layer_order: u64) -> ItchyTargetBuilder {
+        let TargetBuilder {
+            name,
+            scripts,
+            variables,
+            lists,
+            handles: _,
+            helpers: _,
+            costumes,
+            sounds,
+            current_costume,
+            volume,
+        } = self;
+        let mut t = ItchyTargetBuilder::new(name);
+        for (name, value) in variables {
+            t.add_variable(name, VariableBuilder::new(value));
+        }
+        for (name, values) in lists {
+            t.add_list(name, ListBuilder::new(values));
+        }
+        for script in scripts {
+            t.add_block_stack(script);
+        }
+        for costume in costumes {
+            t.add_costume(costume.into_itchy());
+        }
+        for sound in sounds {
+            t.add_sound(sound.into_itchy());
+        }
+        t.set_current_costume(current_costume);
+        t.set_layer_order(layer_order);
+        t.set_volume(volume);
+        t
+    }
+}
+
+/// The stage. There's only one stage in a project and its name is always `"Stage"`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StageBuilder {
+    target: TargetBuilder,
+    broadcasts: Vec<String>,
+    tempo: f64,
+    video_transparency: f64,
+    video_state: VideoState,
+    text_to_speech_language: Option<String>,
+}
+
+impl StageBuilder {
+    /// Stage made of `target`, which is renamed to `"Stage"`
+    pub fn new(mut target: TargetBuilder) -> StageBuilder {
+        target.name = "Stage".to_owned();
+        StageBuilder {
+            target,
+            broadcasts: vec![],
+            tempo: 60.,
+            video_transparency: 50.,
+            video_state: VideoState::On,
+            text_to_speech_language: None,
+        }
+    }
+
+    pub fn target(&self) -> &TargetBuilder {
+        &self.target
+    }
+
+    /// Add a global variable and get the handle to use it in blocks
+    ///
+    /// The name must be unique, see [`BuildError::DuplicateName`].
+    pub fn declare_var<T>(
+        &mut self,
+        name: impl Into<String>,
+        value: impl Into<ScratchValue>,
+    ) -> VarHandle<T> {
+        self.target.declare_var(Scope::Global, name.into(), value)
+    }
+
+    /// Add a global list and get the handle to use it in blocks
+    ///
+    /// The name must be unique, see [`BuildError::DuplicateName`].
+    pub fn declare_list<T, V: Into<ScratchValue>>(
+        &mut self,
+        name: impl Into<String>,
+        values: impl IntoIterator<Item = V>,
+    ) -> ListHandle<T> {
+        self.target.declare_list(Scope::Global, name.into(), values)
+    }
+
+    /// Add a broadcast and get the handle to use it in blocks
+    pub fn declare_broadcast(&mut self, name: impl Into<String>) -> BroadcastHandle {
+        let name = name.into();
+        self.broadcasts.push(name.clone());
+        BroadcastHandle::new(name)
+    }
+
+    pub fn add_broadcast(mut self, name: impl Into<String>) -> StageBuilder {
+        self.broadcasts.push(name.into());
+        self
+    }
+
+    /// Add a script. Usually starts with a [`crate::scripting::stack::HatBlock`].
+    pub fn add_script<S, E>(mut self, script: TypedStackBuilder<S, E>) -> StageBuilder {
+        self.target = self.target.add_script(script);
+        self
+    }
+
+    pub fn set_tempo(mut self, tempo: f64) -> StageBuilder {
+        self.tempo = tempo;
+        self
+    }
+
+    pub fn set_video_transparency(mut self, video_transparency: f64) -> StageBuilder {
+        self.video_transparency = video_transparency;
+        self
+    }
+
+    pub fn set_video_state(mut self, video_state: VideoState) -> StageBuilder {
+        self.video_state = video_state;
+        self
+    }
+
+    pub fn set_text_to_speech_language(mut self, language: impl Into<String>) -> StageBuilder {
+        self.text_to_speech_language = Some(language.into());
+        self
+    }
+
+    fn into_itchy(self) -> ItchyStageBuilder {
+        let StageBuilder {
+            target,
+            broadcasts,
+            tempo,
+            video_transparency,
+            video_state,
+            text_to_speech_language,
+        } = self;
+        let mut t = target.into_itchy(0);
+        for name in broadcasts {
+            t.add_broadcast(name, BroadcastBuilder::new());
+        }
+        let mut s = ItchyStageBuilder::new(t);
+        s.set_tempo(tempo);
+        s.set_video_transparency(video_transparency);
+        s.set_video_state(video_state.into());
+        s.set_text_to_speech_language(text_to_speech_language);
+        s
+    }
+}
+
+impl Default for StageBuilder {
+    fn default() -> Self {
+        StageBuilder::new(TargetBuilder::new("Stage"))
+    }
+}
+
+/// A sprite
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpriteBuilder {
+    target: TargetBuilder,
+    x: f64,
+    y: f64,
+    size: f64,
+    direction: f64,
+    visible: bool,
+    draggable: bool,
+    rotation_style: RotationStyle,
+    layer_order: Option<u64>,
+}
+
+impl SpriteBuilder {
+    pub fn new(target: TargetBuilder) -> SpriteBuilder {
+        SpriteBuilder {
+            target,
+            x: 0.,
+            y: 0.,
+            size: 100.,
+            direction: 90.,
+            visible: true,
+            draggable: false,
+            rotation_style: RotationStyle::AllAround,
+            layer_order: None,
+        }
+    }
+
+    pub fn target(&self) -> &TargetBuilder {
+        &self.target
+    }
+
+    /// Handle to select this sprite in menus, like where to [`crate::blocks::go_to`]
+    pub fn handle(&self) -> SpriteHandle {
+        SpriteHandle::new(self.target.name.clone())
+    }
+
+    /// Add a variable for this sprite only and get the handle to use it in blocks
+    ///
+    /// The name must be unique, see [`BuildError::DuplicateName`].
+    pub fn declare_var<T>(
+        &mut self,
+        name: impl Into<String>,
+        value: impl Into<ScratchValue>,
+    ) -> VarHandle<T> {
+        self.target.declare_var(Scope::Sprite, name.into(), value)
+    }
+
+    /// Add a list for this sprite only and get the handle to use it in blocks
+    ///
+    /// The name must be unique, see [`BuildError::DuplicateName`].
+    pub fn declare_list<T, V: Into<ScratchValue>>(
+        &mut self,
+        name: impl Into<String>,
+        values: impl IntoIterator<Item = V>,
+    ) -> ListHandle<T> {
+        self.target.declare_list(Scope::Sprite, name.into(), values)
+    }
+
+    /// Add a custom block that returns `T` and its variable for the return value.
+    ///
+    /// The variable is named after the custom block, e.g. `"double %s (return)"`.
+    /// See [`Function`] for more detail.
+    pub fn declare_function<A, T>(
+        &mut self,
+        custom_block: CustomBlockBuilder<A>,
+    ) -> Function<A, T> {
+        let custom_block = custom_block.build();
+        let ret =
+            self.target
+                .declare_var(Scope::Sprite, return_var_name(custom_block.proccode()), "");
+        Function::new(custom_block, ret)
+    }
+
+    /// Add a list named `"(call stack)"` to keep locals of custom blocks in.
+    /// See [`crate::scripting::local`].
+    pub fn declare_call_stack(&mut self) -> CallStack {
+        let list = self.target.declare_list(
+            Scope::Sprite,
+            "(call stack)".to_owned(),
+            std::iter::empty::<ScratchValue>(),
+        );
+        CallStack::new(list)
+    }
+
+    /// Add a script. Usually starts with a [`crate::scripting::stack::HatBlock`].
+    pub fn add_script<S, E>(mut self, script: TypedStackBuilder<S, E>) -> SpriteBuilder {
+        self.target = self.target.add_script(script);
+        self
+    }
+
+    pub fn set_position(mut self, x: f64, y: f64) -> SpriteBuilder {
+        self.x = x;
+        self.y = y;
+        self
+    }
+
+    /// Size in percent
+    pub fn set_size(mut self, size: f64) -> SpriteBuilder {
+        self.size = size;
+        self
+    }
+
+    /// Direction in degrees. `90` is pointing right
+    pub fn set_direction(mut self, direction: f64) -> SpriteBuilder {
+        self.direction = direction;
+        self
+    }
+
+    pub fn set_visible(mut self, visible: bool) -> SpriteBuilder {
+        self.visible = visible;
+        self
+    }
+
+    pub fn set_draggable(mut self, draggable: bool) -> SpriteBuilder {
+        self.draggable = draggable;
+        self
+    }
+
+    pub fn set_rotation_style(mut self, rotation_style: RotationStyle) -> SpriteBuilder {
+        self.rotation_style = rotation_style;
+        self
+    }
+
+    /// Layer of the sprite. `1` is the bottom most layer as `0` is reserved for the stage.
+    ///
+    /// If not set, sprites are layered in the order they are added to the project.
+    pub fn set_layer_order(mut self, layer_order: u64) -> SpriteBuilder {
+        self.layer_order = Some(layer_order);
+        self
+    }
+
+    fn into_itchy(self, default_layer_order: u64) -> ItchySpriteBuilder {
+        let SpriteBuilder {
+            target,
+            x,
+            y,
+            size,
+            direction,
+            visible,
+            draggable,
+            rotation_style,
+            layer_order,
+        } = self;
+        let mut s =
+            ItchySpriteBuilder::new(target.into_itchy(layer_order.unwrap_or(default_layer_order)));
+        s.set_x(x);
+        s.set_y(y);
+        s.set_size(size);
+        s.set_direction(direction);
+        s.set_visible(visible);
+        s.set_draggable(draggable);
+        s.set_rotation_style(rotation_style.into());
+        s
+    }
+}
+
+/// The stage or a sprite, to add things that either can have
+pub trait Target: sealed::Sealed {
+    #[doc(hidden)]
+    fn target_mut(&mut self) -> &mut TargetBuilder;
+}
+
+impl Target for StageBuilder {
+    fn target_mut(&mut self) -> &mut TargetBuilder {
+        &mut self.target
+    }
+}
+
+impl Target for SpriteBuilder {
+    fn target_mut(&mut self) -> &mut TargetBuilder {
+        &mut self.target
+    }
+}
+
+mod sealed {
+    pub trait Sealed {}
+
+    impl Sealed for super::StageBuilder {}
+    impl Sealed for super::SpriteBuilder {}
+}
+
+// Project =====================================================================
+
+/// How ids of blocks, variables, lists and broadcasts are made when building the project
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IdStrategy {
+    /// Random ids, different on every build
+    #[default]
+    Random,
+    /// Hash of what a block is and where it is in its script.
+    /// Scripts that are unchanged keep their ids across builds.
+    Hash,
+    /// Count up from `seed` in a stable order.
+    /// Adding a script can change ids of other scripts.
+    Counter { seed: u64 },
+}
+
+/// Builder of the whole project
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ProjectBuilder {
+    stage: StageBuilder,
+    sprites: Vec<SpriteBuilder>,
+    id_strategy: IdStrategy,
+}
+
+impl ProjectBuilder {
+    pub fn new() -> ProjectBuilder {
+        ProjectBuilder::default()
+    }
+
+    pub fn set_stage(mut self, stage: StageBuilder) -> ProjectBuilder {
+        self.stage = stage;
+        self
+    }
+
+    pub fn add_sprite(mut self, sprite: SpriteBuilder) -> ProjectBuilder {
+        self.sprites.push(sprite);
+        self
+    }
+
+    /// Set how ids are made, [`IdStrategy::Random`] by default.
+    ///
+    /// ```
+    /// # use sb_scratchy::blocks::*;
+    /// # use sb_scratchy::project::*;
+    /// let build = || {
+    ///     let cat = SpriteBuilder::new(TargetBuilder::new("Cat"))
+    ///         .add_script(when_flag_clicked().next(move_steps(10)));
+    ///     ProjectBuilder::new()
+    ///         .add_sprite(cat)
+    ///         .set_id_strategy(IdStrategy::Hash)
+    ///         .build()
+    ///         .unwrap()
+    /// };
+    /// assert_eq!(build().to_json().unwrap(), build().to_json().unwrap());
+    /// ```
+    pub fn set_id_strategy(mut self, id_strategy: IdStrategy) -> ProjectBuilder {
+        self.id_strategy = id_strategy;
+        self
+    }
+
+    pub fn stage(&self) -> &StageBuilder {
+        &self.stage
+    }
+
+    pub fn sprites(&self) -> &[SpriteBuilder] {
+        &self.sprites
+    }
+
+    pub fn id_strategy(&self) -> IdStrategy {
+        self.id_strategy
+    }
+
+    /// Finish building the project
+    pub fn build(self) -> Result<Project, BuildError> {
+        let ProjectBuilder {
+            stage,
+            sprites,
+            id_strategy,
+        } = self;
+        let mut assets: Vec<Asset> = vec![];
+        let targets = std::iter::once(&stage.target).chain(sprites.iter().map(|s| &s.target));
+        for asset in targets.flat_map(TargetBuilder::assets) {
+            if !assets.iter().any(|a| a.md5ext == asset.md5ext) {
+                assets.push(asset);
+            }
+        }
+        check_names(&stage.target, &sprites)?;
+        let handles = std::iter::once(&stage.target)
+            .chain(sprites.iter().map(|s| &s.target))
+            .map(|t| t.handles.clone())
+            .collect::<Vec<_>>();
+        let first_broadcast = stage.broadcasts.first().cloned();
+        let mut p = ItchyProjectBuilder::new();
+        p.set_stage(stage.into_itchy());
+        for (i, sprite) in sprites.into_iter().enumerate() {
+            p.add_sprite(sprite.into_itchy(i as u64 + 1));
+        }
+        let mut resources = vec![];
+        let project = p.build(&mut resources);
+        let mut json = serde_json::to_value(&project)?;
+        resolve_handles(&mut json, &handles)?;
+        resolve_broadcast_placeholders(&mut json, first_broadcast.as_deref());
+        crate::ids::assign_ids(&mut json, id_strategy);
+        add_used_extensions(&mut json);
+        let project = serde_json::from_value(json)?;
+        Ok(Project { project, assets })
+    }
+}
+
+/// Error from building a project
+#[derive(Debug)]
+pub enum BuildError {
+    /// The built project couldn't be converted to and from json to set ids and extensions
+    Json(serde_json::Error),
+    /// `target` has two variables or two lists named `name`,
+    /// or it's a sprite with a variable or list named like a global one
+    DuplicateName { target: String, name: String },
+    /// Two sprites are named `name`, or a sprite is named `"Stage"` like the stage
+    DuplicateTarget { name: String },
+    /// A handle to the variable or list `name` is used in `target` but it's declared on another sprite
+    ForeignHandle { target: String, name: String },
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuildError::Json(e) => write!(f, "json error: {e}"),
+            BuildError::DuplicateName { target, name } => {
+                write!(f, "`{name}` is declared more than once in `{target}`")
+            }
+            BuildError::DuplicateTarget { name } => {
+                write!(f, "more than one target is named `{name}`")
+            }
+            BuildError::ForeignHandle { target, name } => {
+                write!(f, "`{name}` is used in `{target}` but isn't declared there")
+            }
+        }
+    }
+}
+
+impl std::error::Error for BuildError {}
+
+impl From<serde_json::Error> for BuildError {
+    fn from(e: serde_json::Error) -> Self {
+        BuildError::Json(e)
+    }
+}
+
+/// Error if two targets have the same name, if a target has two variables or lists of the same name,
+/// or a sprite has one of the same name as a global one
+fn check_names(stage: &TargetBuilder, sprites: &[SpriteBuilder]) -> Result<(), BuildError> {
+    let mut targets = HashSet::from([&stage.name]);
+    for sprite in sprites {
+        if !targets.insert(&sprite.target.name) {
+            return Err(BuildError::DuplicateTarget {
+                name: sprite.target.name.clone(),
+            });
+        }
+    }
+    fn names(t: &TargetBuilder) -> [Vec<&String>; 2] {
+        let variables = t.variables.iter().map(|(name, _)| name).collect();
+        let lists = t.lists.iter().map(|(name, _)| name).collect();
+        [variables, lists]
+    }
+    let global = names(stage);
+    for target in std::iter::once(stage).chain(sprites.iter().map(|s| &s.target)) {
+        let is_stage = std::ptr::eq(target, stage);
+        for (names, global) in names(target).into_iter().zip(&global) {
+            let mut seen = HashSet::new();
+            if !is_stage {
+                seen.extend(global.iter().copied());
+            }
+            for name in names {
+                if !seen.insert(name) {
+                    return Err(BuildError::DuplicateName {
+                        target: target.name.clone(),
+                        name: name.clone(),
+                    });
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Put the declared variable or list in place of each handle in blocks.
+/// `handles` are of the stage then each sprite, in the order of targets of `project.json`.
+fn resolve_handles(project: &mut Json, handles: &[Handles]) -> Result<(), BuildError> {
+    let Some(targets) = project["targets"].as_array_mut() else {
+        return Ok(());
+    };
+    // name and id in `project.json` of what each handle refers to, by the name of the handle in fields
+    let declared = targets
+        .iter()
+        .zip(handles)
+        .map(|(target, handles)| {
+            let mut declared = HashMap::new();
+            for (key, data) in [("variables", &handles.variables), ("lists", &handles.lists)] {
+                for (id, name) in data {
+                    let json_id = target[key]
+                        .as_object()
+                        .into_iter()
+                        .flatten()
+                        .find(|(_, data)| data[0].as_str() == Some(name.as_str()))
+                        .map(|(json_id, _)| json_id.clone());
+                    if let Some(json_id) = json_id {
+                        declared.insert(id.key(name), (name.clone(), json_id));
+                    }
+                }
+            }
+            declared
+        })
+        .collect::<Vec<_>>();
+
+    for (idx, target) in targets.iter_mut().enumerate() {
+        let target_name = target["name"].as_str().unwrap_or_default().to_owned();
+        let Some(blocks) = target["blocks"].as_object_mut() else {
+            continue;
+        };
+        for block in blocks.values_mut() {
+            for (reference, name_idx) in data_references(block) {
+                let Some(key) = reference[name_idx]
+                    .as_str()
+                    .filter(|k| k.starts_with(DATA_ID_PREFIX))
+                else {
+                    continue;
+                };
+                // the stage's are global and can be used in every target
+                let resolved = declared[idx].get(key).or_else(|| declared[0].get(key));
+                let Some((name, json_id)) = resolved.cloned() else {
+                    let name = key[DATA_ID_PREFIX.len()..]
+                        .split_once(':')
+                        .map_or("", |(_, name)| name);
+                    return Err(BuildError::ForeignHandle {
+                        target: target_name,
+                        name: name.to_owned(),
+                    });
+                };
+                reference.truncate(name_idx);
+                reference.push(Json::String(name));
+                reference.push(Json::String(json_id));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Broadcast added to the stage when a broadcast menu is left under a reporter and
+/// the stage has no broadcast, like Scratch does
+const FALLBACK_BROADCAST: &str = "message1";
+
+/// Put a broadcast of the stage in the broadcast menus left under reporters:
+/// the first one declared, or [`FALLBACK_BROADCAST`] which is added to the stage if there's none.
+fn resolve_broadcast_placeholders(project: &mut Json, first_declared: Option<&str>) {
+    fn placeholders(targets: &mut [Json]) -> Vec<&mut Json> {
+        targets
+            .iter_mut()
+            .filter_map(|target| target["blocks"].as_object_mut())
+            .flat_map(|blocks| blocks.values_mut())
+            .filter_map(|block| block.get_mut("fields")?.get_mut("BROADCAST_OPTION"))
+            .filter(|field| field[0].as_str() == Some(BROADCAST_PLACEHOLDER))
+            .collect()
+    }
+
+    let Some(targets) = project["targets"].as_array_mut() else {
+        return;
+    };
+    if placeholders(targets).is_empty() {
+        return;
+    }
+    let Some(broadcasts) = targets
+        .first_mut()
+        .and_then(|stage| stage["broadcasts"].as_object_mut())
+    else {
+        return;
+    };
+    let name = first_declared.unwrap_or(FALLBACK_BROADCAST);
+    let declared = broadcasts
+        .iter()
+        .find(|(_, declared)| declared.as_str() == Some(name))
+        .map(|(id, _)| id.clone());
+    let id = declared.unwrap_or_else(|| {
+        let id = Uid::generate().into_inner();
+        broadcasts.insert(id.clone(), Json::String(name.to_owned()));
+        id
+    });
+    for field in placeholders(targets) {
+        *field = serde_json::json!([name, id]);
+    }
+}
+
+/// Every variable and list a block refers to, with the index of the name in it.
+/// They are `[name, id]` in fields and `[12 or 13, name, id]` in inputs.
+fn data_references(block: &mut Json) -> Vec<(&mut Vec<Json>, usize)> {
+    let is_data = |primitive: &Vec<Json>| {
+        matches!(primitive.first().and_then(Json::as_u64), Some(12 | 13)) && primitive.len() > 1
+    };
+    let mut references = vec![];
+    match block {
+        // top level reporter, `[12 or 13, name, id, x, y]`
+        Json::Array(primitive) if is_data(primitive) => references.push((primitive, 1)),
+        Json::Object(object) => {
+            for (key, value) in object.iter_mut() {
+                match (key.as_str(), value) {
+                    ("inputs", Json::Object(inputs)) => {
+                        let primitives = inputs
+                            .values_mut()
+                            .filter_map(Json::as_array_mut)
+                            .flat_map(|input| input.iter_mut().skip(1))
+                            .filter_map(Json::as_array_mut)
+                            .filter(|primitive| is_data(primitive))
+                            .map(|primitive| (primitive, 1));
+                        references.extend(primitives);
+                    }
+                    ("fields", Json::Object(fields)) => {
+                        let fields = fields
+                            .values_mut()
+                            .filter_map(Json::as_array_mut)
+                            .filter(|field| !field.is_empty())
+                            .map(|field| (field, 0));
+                        references.extend(fields);
+                    }
+                    _ => {}
+                }
+            }
+        }
+        _ => {}
+    }
+    references
+}
+
+/// Opcode prefixes of blocks that are built into Scratch. Every other prefix is an id of an extension.
+const CORE_CATEGORIES: &[&str] = &[
+    "argument",
+    "colour",
+    "control",
+    "data",
+    "event",
+    "looks",
+    "math",
+    "motion",
+    "operator",
+    "procedures",
+    "sensing",
+    "sound",
+];
+
+/// Add extensions that blocks in the project are from to `extensions` of `project.json`
+fn add_used_extensions(project: &mut Json) {
+    let opcodes = project["targets"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|target| target["blocks"].as_object())
+        .flat_map(|blocks| blocks.values())
+        .filter_map(|block| block["opcode"].as_str());
+    let mut used = vec![];
+    for opcode in opcodes {
+        let Some((prefix, _)) = opcode.split_once('_') else {
+            continue;
+        };
+        if !CORE_CATEGORIES.contains(&prefix) && !used.contains(&prefix) {
+            used.push(prefix);
+        }
+    }
+    used.sort_unstable();
+    let used = used.into_iter().map(Json::from).collect::<Vec<_>>();
+
+    if !project["extensions"].is_array() {
+        project["extensions"] = Json::Array(vec![]);
+    }
+    let extensions = project["extensions"]
+        .as_array_mut()
+        .expect("set to array above");
+    for extension in used {
+        if !extensions.contains(&extension) {
+            extensions.push(extension);
+        }
+    }
+}
+
+/// Finished project
+#[derive(Debug, Clone, PartialEq)]
+pub struct Project {
+    project: SbityProject,
+    assets: Vec<Asset>,
+}
+
+impl Project {
+    pub fn project(&self) -> &SbityProject {
+        &self.project
+    }
+
+    /// Asset files of all costumes and sounds in the project
+    pub fn assets(&self) -> &[Asset] {
+        &self.assets
+    }
+
+    pub fn into_inner(self) -> SbityProject {
+        self.project
+    }
+
+    /// Serialize to `project.json`
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string(&self.project)
+    }
+}
+
+#[cfg(test)]
+mod tests;
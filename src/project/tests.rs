@@ -0,0 +1,136 @@
+use super::*;
+use crate::blocks::{add_to_list, answer, broadcast, set_var_to, when_flag_clicked};
+use crate::interpreter::{Interpreter, Value};
+use crate::scripting::arg::Number;
+
+fn build(stage: StageBuilder, sprites: Vec<SpriteBuilder>) -> Result<Project, BuildError> {
+    sprites
+        .into_iter()
+        .fold(
+            ProjectBuilder::new().set_stage(stage),
+            ProjectBuilder::add_sprite,
+        )
+        .build()
+}
+
+#[test]
+fn handles_refer_to_their_own_target() {
+    let mut stage = StageBuilder::default();
+    let total = stage.declare_list::<Number, f64>("total", []);
+    let mut cat = SpriteBuilder::new(TargetBuilder::new("Cat"));
+    let cat_score = cat.declare_var::<Number>("score", 0.);
+    let mut dog = SpriteBuilder::new(TargetBuilder::new("Dog"));
+    let dog_score = dog.declare_var::<Number>("score", 0.);
+    let cat = cat.add_script(
+        when_flag_clicked()
+            .next(set_var_to(&cat_score, 1))
+            .next(add_to_list(&total, 1)),
+    );
+    let dog = dog.add_script(
+        when_flag_clicked()
+            .next(set_var_to(&dog_score, 2))
+            .next(add_to_list(&total, 2)),
+    );
+
+    let project = build(stage, vec![cat, dog]).unwrap();
+    let mut interpreter = Interpreter::new(&project).unwrap();
+    interpreter.green_flag();
+    interpreter.run_until_idle(10);
+    let score = |target| interpreter.variable(target, "score").map(Value::to_number);
+    assert_eq!(score("Cat"), Some(1.));
+    assert_eq!(score("Dog"), Some(2.));
+    assert_eq!(interpreter.list("Stage", "total").map(<[_]>::len), Some(2));
+}
+
+#[test]
+fn handle_of_another_sprite_is_an_error() {
+    let mut cat = SpriteBuilder::new(TargetBuilder::new("Cat"));
+    let cat_score = cat.declare_var::<Number>("score", 0.);
+    let mut dog = SpriteBuilder::new(TargetBuilder::new("Dog"));
+    dog.declare_var::<Number>("score", 0.);
+    let dog = dog.add_script(when_flag_clicked().next(set_var_to(&cat_score, 1)));
+
+    let error = build(StageBuilder::default(), vec![cat, dog]).unwrap_err();
+    assert!(
+        matches!(&error, BuildError::ForeignHandle { target, name } if target == "Dog" && name == "score"),
+        "{error}"
+    );
+}
+
+#[test]
+fn duplicate_names_are_an_error() {
+    let is_duplicate = |error: BuildError, expected: &str| matches!(&error, BuildError::DuplicateName { name, .. } if name == expected);
+
+    let mut cat = SpriteBuilder::new(TargetBuilder::new("Cat"));
+    cat.declare_var::<Number>("score", 0.);
+    cat.declare_var::<Number>("score", 0.);
+    let error = build(StageBuilder::default(), vec![cat]).unwrap_err();
+    assert!(is_duplicate(error, "score"));
+
+    let mut stage = StageBuilder::default();
+    stage.declare_list::<Number, f64>("items", []);
+    let mut cat = SpriteBuilder::new(TargetBuilder::new("Cat"));
+    cat.declare_list::<Number, f64>("items", []);
+    let error = build(stage, vec![cat]).unwrap_err();
+    assert!(is_duplicate(error, "items"));
+
+    // variables and lists have their own names
+    let mut cat = SpriteBuilder::new(TargetBuilder::new("Cat"));
+    cat.declare_var::<Number>("score", 0.);
+    cat.declare_list::<Number, f64>("score", []);
+    assert!(build(StageBuilder::default(), vec![cat]).is_ok());
+}
+
+#[test]
+fn duplicate_target_names_are_an_error() {
+    let sprite = |name| SpriteBuilder::new(TargetBuilder::new(name));
+    let is_duplicate = |error: BuildError, expected: &str| matches!(&error, BuildError::DuplicateTarget { name } if name == expected);
+
+    let error = build(StageBuilder::default(), vec![sprite("Cat"), sprite("Cat")]).unwrap_err();
+    assert!(is_duplicate(error, "Cat"));
+    let error = build(StageBuilder::default(), vec![sprite("Stage")]).unwrap_err();
+    assert!(is_duplicate(error, "Stage"));
+    assert!(build(StageBuilder::default(), vec![sprite("Cat"), sprite("Dog")]).is_ok());
+}
+
+#[test]
+fn stage_is_always_named_stage() {
+    let stage = StageBuilder::new(TargetBuilder::new("Backdrops"));
+    assert_eq!(stage.target().name(), "Stage");
+    let project = build(stage, vec![]).unwrap();
+    let json = serde_json::to_value(project.project()).unwrap();
+    assert_eq!(json["targets"][0]["name"], "Stage");
+}
+
+#[test]
+fn broadcast_menu_under_reporter_is_a_stage_broadcast() {
+    let broadcasts = |stage: StageBuilder| {
+        let cat = SpriteBuilder::new(TargetBuilder::new("Cat"))
+            .add_script(when_flag_clicked().next(broadcast(answer())));
+        let json = serde_json::to_value(build(stage, vec![cat]).unwrap().project()).unwrap();
+        let menu = json["targets"][1]["blocks"]
+            .as_object()
+            .unwrap()
+            .values()
+            .find(|block| block["opcode"] == "event_broadcast_menu")
+            .unwrap()
+            .clone();
+        (
+            json["targets"][0]["broadcasts"].clone(),
+            menu["fields"]["BROADCAST_OPTION"].clone(),
+        )
+    };
+
+    let mut stage = StageBuilder::default();
+    stage.declare_broadcast("go");
+    stage.declare_broadcast("stop");
+    let (declared, option) = broadcasts(stage);
+    assert_eq!(declared[option[1].as_str().unwrap()], "go");
+    assert_eq!(option[0], "go");
+
+    // Scratch's default broadcast is added when the stage has none
+    let (declared, option) = broadcasts(StageBuilder::default());
+    assert_eq!(declared.as_object().unwrap().len(), 1);
+    assert_eq!(declared[option[1].as_str().unwrap()], "message1");
+    assert_eq!(option[0], "message1");
+}
@@ -0,0 +1,419 @@
+//! Reading and writing `.sb3` archive
+//!
+//! An `.sb3` file is a zip archive with `project.json` and every asset file at its root.
+//! Asset files are named by their md5 and extension.
+//!
+//! # Usage
+//!
+//! ```no_run
+//! # use sb_scratchy::project::*;
+//! let project = ProjectBuilder::new().build().unwrap();
+//! project.save_sb3("my_project.sb3").unwrap();
+//!
+//! let builder = ProjectBuilder::open_sb3("my_project.sb3").unwrap();
+//! ```
+
+use std::{
+    cell::RefCell,
+    collections::HashSet,
+    fmt,
+    fs::File,
+    io::{self, Read, Seek, Write},
+    path::Path,
+};
+
+use sb_itchy::{
+    block::{
+        BlockFieldBuilder, BlockInputBuilder, BlockInputValue, BlockNormalBuilder, FieldKind,
+        StackOrValue,
+    },
+    blocks,
+    stack::StackBuilder as ItchyStackBuilder,
+    uid::Uid,
+};
+use sb_sbity::{block::BlockMutation, value::Value as ScratchValue};
+use serde_json::{Map, Value as Json};
+use zip::{result::ZipError, write::FileOptions, ZipArchive, ZipWriter};
+
+use crate::project::*;
+
+/// Error from reading or writing `.sb3`
+#[derive(Debug)]
+pub enum Sb3Error {
+    Io(io::Error),
+    Zip(ZipError),
+    Json(serde_json::Error),
+    /// `project.json` is readable but isn't a project this crate understands
+    InvalidProject(String),
+}
+
+impl fmt::Display for Sb3Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Sb3Error::Io(e) => write!(f, "io error: {e}"),
+            Sb3Error::Zip(e) => write!(f, "zip error: {e}"),
+            Sb3Error::Json(e) => write!(f, "json error: {e}"),
+            Sb3Error::InvalidProject(reason) => write!(f, "invalid project: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for Sb3Error {}
+
+impl From<io::Error> for Sb3Error {
+    fn from(e: io::Error) -> Self {
+        Sb3Error::Io(e)
+    }
+}
+
+impl From<ZipError> for Sb3Error {
+    fn from(e: ZipError) -> Self {
+        Sb3Error::Zip(e)
+    }
+}
+
+impl From<serde_json::Error> for Sb3Error {
+    fn from(e: serde_json::Error) -> Self {
+        Sb3Error::Json(e)
+    }
+}
+
+fn invalid(reason: impl Into<String>) -> Sb3Error {
+    Sb3Error::InvalidProject(reason.into())
+}
+
+// Write =======================================================================
+
+impl Project {
+    /// Write the project as `.sb3` to `writer`
+    pub fn write_sb3<W: Write + Seek>(&self, writer: W) -> Result<W, Sb3Error> {
+        let mut zip = ZipWriter::new(writer);
+        let options = FileOptions::default();
+        zip.start_file("project.json", options)?;
+        zip.write_all(self.to_json()?.as_bytes())?;
+        for asset in self.assets() {
+            zip.start_file(asset.md5ext(), options)?;
+            zip.write_all(asset.data())?;
+        }
+        Ok(zip.finish()?)
+    }
+
+    /// Write the project as `.sb3` file at `path`
+    pub fn save_sb3(&self, path: impl AsRef<Path>) -> Result<(), Sb3Error> {
+        self.write_sb3(File::create(path)?)?;
+        Ok(())
+    }
+}
+
+// Read ========================================================================
+
+impl ProjectBuilder {
+    /// Read `.sb3` from `reader` back into builder
+    pub fn read_sb3<R: Read + Seek>(reader: R) -> Result<ProjectBuilder, Sb3Error> {
+        let mut zip = ZipArchive::new(reader)?;
+        let project: Json = {
+            let mut project_json = String::new();
+            zip.by_name("project.json")?
+                .read_to_string(&mut project_json)?;
+            serde_json::from_str(&project_json)?
+        };
+        let targets = project["targets"]
+            .as_array()
+            .ok_or_else(|| invalid("`targets` is not an array"))?;
+
+        let mut project_builder = ProjectBuilder::new();
+        for target in targets {
+            let target_builder = read_target(target, &mut zip)?;
+            if target["isStage"].as_bool().unwrap_or(false) {
+                project_builder = project_builder.set_stage(read_stage(target, target_builder)?);
+            } else {
+                project_builder = project_builder.add_sprite(read_sprite(target, target_builder));
+            }
+        }
+        Ok(project_builder)
+    }
+
+    /// Read `.sb3` file at `path` back into builder
+    pub fn open_sb3(path: impl AsRef<Path>) -> Result<ProjectBuilder, Sb3Error> {
+        ProjectBuilder::read_sb3(File::open(path)?)
+    }
+}
+
+fn object<'a>(json: &'a Json, key: &str) -> Result<&'a Map<String, Json>, Sb3Error> {
+    json[key]
+        .as_object()
+        .ok_or_else(|| invalid(format!("`{key}` is not an object")))
+}
+
+fn read_asset<R: Read + Seek>(
+    json: &Json,
+    zip: &mut ZipArchive<R>,
+) -> Result<(String, String, Vec<u8>), Sb3Error> {
+    let name = json["name"].as_str().unwrap_or_default().to_owned();
+    let md5ext = json["md5ext"]
+        .as_str()
+        .map(str::to_owned)
+        .or_else(|| {
+            let asset_id = json["assetId"].as_str()?;
+            let data_format = json["dataFormat"].as_str()?;
+            Some(format!("{asset_id}.{data_format}"))
+        })
+        .ok_or_else(|| invalid(format!("asset `{name}` has no file name")))?;
+    let extension = md5ext.rsplit('.').next().unwrap_or_default().to_owned();
+    let mut data = vec![];
+    zip.by_name(&md5ext)?.read_to_end(&mut data)?;
+    Ok((name, extension, data))
+}
+
+fn read_target<R: Read + Seek>(
+    json: &Json,
+    zip: &mut ZipArchive<R>,
+) -> Result<TargetBuilder, Sb3Error> {
+    let name = json["name"]
+        .as_str()
+        .ok_or_else(|| invalid("target has no name"))?;
+    let mut target = TargetBuilder::new(name);
+
+    let variables = object(json, "variables")?;
+    for variable in variables.values() {
+        let name = variable[0].as_str().unwrap_or_default();
+        target = target.add_variable(
+            name,
+            serde_json::from_value::<ScratchValue>(variable[1].clone())?,
+        );
+    }
+    let lists = object(json, "lists")?;
+    for list in lists.values() {
+        let name = list[0].as_str().unwrap_or_default();
+        let values: Vec<ScratchValue> = serde_json::from_value(list[1].clone())?;
+        target = target.add_list(name, values);
+    }
+
+    let reader = BlockReader {
+        blocks: object(json, "blocks")?,
+        local_variables: variables.keys().map(String::as_str).collect(),
+        local_lists: lists.keys().map(String::as_str).collect(),
+        read: RefCell::new(HashSet::new()),
+    };
+    for (id, block) in reader.blocks {
+        let is_top_level = match block {
+            Json::Array(_) => true,
+            _ => block["topLevel"].as_bool().unwrap_or(false),
+        };
+        if is_top_level {
+            target = target.add_untyped_script(reader.stack(id)?);
+        }
+    }
+
+    for costume in json["costumes"].as_array().into_iter().flatten() {
+        let (name, extension, data) = read_asset(costume, zip)?;
+        let mut costume_builder = CostumeBuilder::new(name, extension, data).set_rotation_center(
+            costume["rotationCenterX"].as_f64().unwrap_or_default(),
+            costume["rotationCenterY"].as_f64().unwrap_or_default(),
+        );
+        if let Some(bitmap_resolution) = costume["bitmapResolution"].as_u64() {
+            costume_builder = costume_builder.set_bitmap_resolution(bitmap_resolution);
+        }
+        target = target.add_costume(costume_builder);
+    }
+    for sound in json["sounds"].as_array().into_iter().flatten() {
+        let (name, extension, data) = read_asset(sound, zip)?;
+        let mut sound_builder = SoundBuilder::new(name, extension, data);
+        if let Some(rate) = sound["rate"].as_u64() {
+            sound_builder = sound_builder.set_rate(rate);
+        }
+        if let Some(sample_count) = sound["sampleCount"].as_u64() {
+            sound_builder = sound_builder.set_sample_count(sample_count);
+        }
+        target = target.add_sound(sound_builder);
+    }
+
+    if let Some(current_costume) = json["currentCostume"].as_u64() {
+        target = target.set_current_costume(current_costume);
+    }
+    if let Some(volume) = json["volume"].as_f64() {
+        target = target.set_volume(volume);
+    }
+    Ok(target)
+}
+
+fn read_stage(json: &Json, target: TargetBuilder) -> Result<StageBuilder, Sb3Error> {
+    let mut stage = StageBuilder::new(target);
+    for broadcast in object(json, "broadcasts")?.values() {
+        stage = stage.add_broadcast(broadcast.as_str().unwrap_or_default());
+    }
+    if let Some(tempo) = json["tempo"].as_f64() {
+        stage = stage.set_tempo(tempo);
+    }
+    if let Some(video_transparency) = json["videoTransparency"].as_f64() {
+        stage = stage.set_video_transparency(video_transparency);
+    }
+    match json["videoState"].as_str() {
+        Some("on") => stage = stage.set_video_state(VideoState::On),
+        Some("off") => stage = stage.set_video_state(VideoState::Off),
+        Some("on-flipped") => stage = stage.set_video_state(VideoState::OnFlipped),
+        _ => {}
+    }
+    if let Some(language) = json["textToSpeechLanguage"].as_str() {
+        stage = stage.set_text_to_speech_language(language);
+    }
+    Ok(stage)
+}
+
+fn read_sprite(json: &Json, target: TargetBuilder) -> SpriteBuilder {
+    let mut sprite = SpriteBuilder::new(target).set_position(
+        json["x"].as_f64().unwrap_or_default(),
+        json["y"].as_f64().unwrap_or_default(),
+    );
+    if let Some(size) = json["size"].as_f64() {
+        sprite = sprite.set_size(size);
+    }
+    if let Some(direction) = json["direction"].as_f64() {
+        sprite = sprite.set_direction(direction);
+    }
+    if let Some(visible) = json["visible"].as_bool() {
+        sprite = sprite.set_visible(visible);
+    }
+    if let Some(draggable) = json["draggable"].as_bool() {
+        sprite = sprite.set_draggable(draggable);
+    }
+    match json["rotationStyle"].as_str() {
+        Some("all around") => sprite = sprite.set_rotation_style(RotationStyle::AllAround),
+        Some("left-right") => sprite = sprite.set_rotation_style(RotationStyle::LeftRight),
+        Some("don't rotate") => sprite = sprite.set_rotation_style(RotationStyle::DontRotate),
+        _ => {}
+    }
+    if let Some(layer_order) = json["layerOrder"].as_u64() {
+        sprite = sprite.set_layer_order(layer_order);
+    }
+    sprite
+}
+
+/// Rebuild sb-itchy stacks from `blocks` of a target
+struct BlockReader<'a> {
+    blocks: &'a Map<String, Json>,
+    local_variables: HashSet<&'a str>,
+    local_lists: HashSet<&'a str>,
+    /// Blocks already read, each block is only in one place so reading one again means it loops
+    read: RefCell<HashSet<String>>,
+}
+
+impl<'a> BlockReader<'a> {
+    fn stack(&self, id: &str) -> Result<ItchyStackBuilder, Sb3Error> {
+        let mut stack: Option<ItchyStackBuilder> = None;
+        let mut next = Some(id);
+        while let Some(id) = next {
+            if !self.read.borrow_mut().insert(id.to_owned()) {
+                return Err(invalid(format!(
+                    "block `{id}` is in more than one place or loops"
+                )));
+            }
+            let block = self
+                .blocks
+                .get(id)
+                .ok_or_else(|| invalid(format!("block `{id}` does not exist")))?;
+            let b = self.block(block)?;
+            stack = Some(match stack {
+                Some(stack) => stack.next(b),
+                None => b,
+            });
+            next = block["next"].as_str();
+        }
+        Ok(stack.expect("stack has at least one block"))
+    }
+
+    fn block(&self, block: &Json) -> Result<ItchyStackBuilder, Sb3Error> {
+        if block.is_array() {
+            return self.var_list(block);
+        }
+        let opcode = block["opcode"]
+            .as_str()
+            .ok_or_else(|| invalid("block has no opcode"))?;
+        let mut b = BlockNormalBuilder::new(opcode);
+        for (name, input) in object(block, "inputs")? {
+            b.add_input(name.clone(), self.input(input)?);
+        }
+        for (name, field) in object(block, "fields")? {
+            b.add_field(name.clone(), self.field(name, field));
+        }
+        b.set_shadow(block["shadow"].as_bool().unwrap_or(false));
+        if block["mutation"].is_object() {
+            b.set_mutation(serde_json::from_value::<BlockMutation>(
+                block["mutation"].clone(),
+            )?);
+        }
+        Ok(ItchyStackBuilder::start(b))
+    }
+
+    /// Input is `[shadow type, input, obscured shadow (optional)]`
+    fn input(&self, input: &Json) -> Result<BlockInputBuilder, Sb3Error> {
+        let values = input
+            .as_array()
+            .ok_or_else(|| invalid("input is not an array"))?;
+        let mut b = BlockInputBuilder::new();
+        for value in values.iter().skip(1) {
+            b = match value {
+                Json::Null => b.input_none(),
+                Json::String(id) => b.input_some(StackOrValue::Stack(self.stack(id)?)),
+                Json::Array(_) => b.input_some(self.primitive(value)?),
+                _ => return Err(invalid("unknown input value")),
+            };
+        }
+        Ok(b)
+    }
+
+    /// Compressed primitive block `[type, value, ...]`
+    fn primitive(&self, primitive: &Json) -> Result<StackOrValue, Sb3Error> {
+        let value = || serde_json::from_value::<ScratchValue>(primitive[1].clone());
+        let input_value = match primitive[0].as_u64() {
+            Some(4) => BlockInputValue::Number { value: value()? },
+            Some(5) => BlockInputValue::PositiveNumber { value: value()? },
+            Some(6) => BlockInputValue::PositiveInteger { value: value()? },
+            Some(7) => BlockInputValue::Integer { value: value()? },
+            Some(8) => BlockInputValue::Angle { value: value()? },
+            Some(9) => BlockInputValue::Color { value: value()? },
+            Some(10) => BlockInputValue::String { value: value()? },
+            Some(11) => BlockInputValue::Broadcast {
+                name: value()?,
+                id: Uid::new(primitive[2].as_str().unwrap_or_default()),
+            },
+            Some(12 | 13) => return Ok(StackOrValue::Stack(self.var_list(primitive)?)),
+            _ => return Err(invalid("unknown primitive type")),
+        };
+        Ok(StackOrValue::Value(input_value))
+    }
+
+    /// Variable `[12, name, id, ...]` or list `[13, name, id, ...]` reporter
+    fn var_list(&self, primitive: &Json) -> Result<ItchyStackBuilder, Sb3Error> {
+        let name = primitive[1].as_str().unwrap_or_default();
+        let id = primitive[2].as_str().unwrap_or_default();
+        match primitive[0].as_u64() {
+            Some(12) if self.local_variables.contains(id) => Ok(blocks::sprite_var(name)),
+            Some(12) => Ok(blocks::global_var(name)),
+            Some(13) if self.local_lists.contains(id) => Ok(blocks::sprite_list(name)),
+            Some(13) => Ok(blocks::global_list(name)),
+            _ => Err(invalid("unknown variable or list reporter")),
+        }
+    }
+
+    /// Field is `[value, id (optional)]`
+    fn field(&self, name: &str, field: &Json) -> BlockFieldBuilder {
+        let value = match &field[0] {
+            Json::String(s) => s.clone(),
+            other => other.to_string(),
+        };
+        let id = field[1].as_str().unwrap_or_default();
+        let kind = match name {
+            "VARIABLE" if self.local_variables.contains(id) => FieldKind::SpriteVariable,
+            "VARIABLE" => FieldKind::GlobalVariable,
+            "LIST" if self.local_lists.contains(id) => FieldKind::SpriteList,
+            "LIST" => FieldKind::GlobalList,
+            "BROADCAST_OPTION" => FieldKind::Broadcast,
+            _ => return BlockFieldBuilder::new(value),
+        };
+        BlockFieldBuilder::new_with_kind(value, kind)
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,189 @@
+//! Writing projects and reading them back
+
+use std::io::Cursor;
+
+use serde_json::json;
+
+use super::*;
+use crate::blocks::*;
+use crate::scripting::{
+    arg::{Bool, Number, Value},
+    custom_block::CustomBlockBuilder,
+};
+
+fn write(project: &Project) -> Vec<u8> {
+    project.write_sb3(Cursor::new(vec![])).unwrap().into_inner()
+}
+
+/// Archive with only `project.json`
+fn sb3_of(project: &Json) -> Vec<u8> {
+    let mut zip = ZipWriter::new(Cursor::new(vec![]));
+    zip.start_file("project.json", FileOptions::default())
+        .unwrap();
+    zip.write_all(project.to_string().as_bytes()).unwrap();
+    zip.finish().unwrap().into_inner()
+}
+
+/// Project with a sprite `"Cat"` that has `blocks`
+fn project_with_blocks(blocks: Json) -> Json {
+    json!({
+        "targets": [
+            {
+                "isStage": true,
+                "name": "Stage",
+                "variables": {},
+                "lists": {},
+                "broadcasts": {},
+                "blocks": {},
+                "costumes": [],
+                "sounds": [],
+            },
+            {
+                "isStage": false,
+                "name": "Cat",
+                "variables": {},
+                "lists": {},
+                "blocks": blocks,
+                "costumes": [],
+                "sounds": [],
+            },
+        ]
+    })
+}
+
+#[test]
+fn round_trip() {
+    let mut stage = StageBuilder::default();
+    let score = stage.declare_var::<Number>("score", 0.);
+    let go = stage.declare_broadcast("go");
+    let mut cat = SpriteBuilder::new(TargetBuilder::new("Cat").add_costume(CostumeBuilder::new(
+        "cat",
+        "svg",
+        b"<svg/>".to_vec(),
+    )));
+    let items = cat.declare_list::<Value, &str>("items", ["a", "b"]);
+    let jump = CustomBlockBuilder::new()
+        .text("jump")
+        .arg::<Value>("height")
+        .arg::<Bool>("spin")
+        .warp(true)
+        .build();
+    let cat = cat
+        .add_script(
+            when_flag_clicked()
+                .next(say("Hello!"))
+                .next(jump.call((5, mouse_down())))
+                .next(add_to_list(&items, var(&score)))
+                .next(broadcast(&go)),
+        )
+        .add_script(
+            jump.define()
+                .next(if_(jump.arg_boolean("spin"), turn_right(15))),
+        )
+        .set_position(10., -20.);
+    let project = ProjectBuilder::new()
+        .set_stage(stage)
+        .add_sprite(cat)
+        .build()
+        .unwrap();
+
+    let read = ProjectBuilder::read_sb3(Cursor::new(write(&project)))
+        .unwrap()
+        .build()
+        .unwrap();
+    let reread = ProjectBuilder::read_sb3(Cursor::new(write(&read)))
+        .unwrap()
+        .build()
+        .unwrap();
+    assert_eq!(without_ids(&project), without_ids(&read));
+    assert_eq!(without_ids(&read), without_ids(&reread));
+}
+
+/// Targets of the project with blocks as trees and names instead of ids,
+/// ids are generated again each time the project is built
+fn without_ids(project: &Project) -> Json {
+    let json: Json = serde_json::from_str(&project.to_json().unwrap()).unwrap();
+    let targets = json["targets"].as_array().unwrap().iter().map(|target| {
+        let blocks = target["blocks"].as_object().unwrap();
+        let mut scripts: Vec<Json> = blocks
+            .values()
+            .filter(|block| block["topLevel"] == true)
+            .map(|block| block_tree(blocks, block))
+            .collect();
+        scripts.sort_by_key(Json::to_string);
+        let names = |key: &str| -> Vec<Json> {
+            let mut values: Vec<Json> =
+                target[key].as_object().unwrap().values().cloned().collect();
+            values.sort_by_key(Json::to_string);
+            values
+        };
+        json!({
+            "name": target["name"],
+            "variables": names("variables"),
+            "lists": names("lists"),
+            "broadcasts": names("broadcasts"),
+            "costumes": target["costumes"],
+            "x": target["x"],
+            "y": target["y"],
+            "scripts": scripts,
+        })
+    });
+    targets.collect()
+}
+
+fn block_tree(blocks: &Map<String, Json>, block: &Json) -> Json {
+    let resolve = |value: &Json| match value {
+        Json::String(id) => block_tree(blocks, &blocks[id]),
+        // variable, list and broadcast primitives have an id after the name
+        Json::Array(primitive) => primitive[..primitive.len().min(2)].into(),
+        other => other.clone(),
+    };
+    let inputs: Map<String, Json> = block["inputs"]
+        .as_object()
+        .unwrap()
+        .iter()
+        .map(|(name, input)| {
+            let input: Vec<Json> = input.as_array().unwrap().iter().map(resolve).collect();
+            (name.clone(), input.into())
+        })
+        .collect();
+    let fields: Map<String, Json> = block["fields"]
+        .as_object()
+        .unwrap()
+        .iter()
+        .map(|(name, field)| (name.clone(), field[0].clone()))
+        .collect();
+    json!({
+        "opcode": block["opcode"],
+        "inputs": inputs,
+        "fields": fields,
+        "mutation": block["mutation"],
+        "shadow": block["shadow"],
+        "next": resolve(&block["next"]),
+    })
+}
+
+#[test]
+fn looping_next_is_an_error() {
+    let project = project_with_blocks(json!({
+        "a": { "opcode": "motion_movesteps", "next": "b", "topLevel": true, "inputs": {}, "fields": {} },
+        "b": { "opcode": "motion_turnright", "next": "a", "inputs": {}, "fields": {} },
+    }));
+    let result = ProjectBuilder::read_sb3(Cursor::new(sb3_of(&project)));
+    assert!(matches!(result, Err(Sb3Error::InvalidProject(_))));
+}
+
+#[test]
+fn looping_input_is_an_error() {
+    let project = project_with_blocks(json!({
+        "a": {
+            "opcode": "control_forever",
+            "next": null,
+            "topLevel": true,
+            "inputs": { "SUBSTACK": [2, "a"] },
+            "fields": {},
+        },
+    }));
+    let result = ProjectBuilder::read_sb3(Cursor::new(sb3_of(&project)));
+    assert!(matches!(result, Err(Sb3Error::InvalidProject(_))));
+}
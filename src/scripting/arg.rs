@@ -1,10 +1,18 @@
 //! Argument/Input in Scratch block
 
 use std::marker::PhantomData;
+use std::sync::atomic::{AtomicU64, Ordering};
 
-use sb_itchy::prelude::{BlockFieldBuilder, BlockInputBuilder, BlockInputValue, FieldKind};
+use sb_itchy::{
+    prelude::{
+        BlockFieldBuilder, BlockInputBuilder, BlockInputValue, BlockNormalBuilder, FieldKind,
+        StackOrValue,
+    },
+    stack::StackBuilder as ItchyStackBuilder,
+};
 
 use crate::all_derive;
+use crate::scripting::menu::Key;
 use crate::scripting::stack::{StackableSide, TypedStackBuilder, UnstackableSide};
 
 /// Marker for [`IntoInput`] that this can be insert into input.
@@ -13,7 +21,8 @@ pub struct Reporter<T, S, E>(pub TypedStackBuilder<S, E>, pub PhantomData<T>);
 /// Just the classic Scratch repoter
 pub type JustReporter<T> = Reporter<T, UnstackableSide, UnstackableSide>;
 /// This is a kind of reporter that you can select item from a menu.
-pub type MenuReporter = JustReporter<Text>;
+/// `M` is the input it's made for, see [`MenuInput`].
+pub type MenuReporter<M> = JustReporter<M>;
 
 impl<T, S, E> Reporter<T, S, E> {
     pub fn new(typed_stack_builder: TypedStackBuilder<S, E>) -> Reporter<T, S, E> {
@@ -37,10 +46,35 @@ all_derive! {
     pub struct Float;
     pub struct Angle;
     pub struct Color;
+    /// MIDI note number, see [`crate::blocks::music::MidiNote`]
+    pub struct Note;
     pub struct Text;
     pub struct Bool;
     /// Could be text or number
     pub struct Value;
+    /// Items of a list joined together, reported by [`crate::blocks::list`]
+    pub struct ListContents;
+
+    /// Name of a costume, see [`crate::blocks::switch_costume_to`]
+    pub struct Costume;
+    /// Name of a backdrop, see [`crate::blocks::switch_backdrop_to`]
+    pub struct Backdrop;
+    /// Name of a sound, see [`crate::blocks::play_sound`]
+    pub struct Sound;
+    /// See [`crate::blocks::go_to`]
+    pub struct GoToTarget;
+    /// See [`crate::blocks::glide_to`]
+    pub struct GlideToTarget;
+    /// See [`crate::blocks::point_towards`]
+    pub struct PointTowardsTarget;
+    /// See [`crate::blocks::touching`]
+    pub struct TouchingTarget;
+    /// See [`crate::blocks::distance_to`]
+    pub struct DistanceToTarget;
+    /// See [`crate::blocks::create_clone_of`]
+    pub struct CloneTarget;
+    /// See [`crate::blocks::var_of`]
+    pub struct ObjectTarget;
 
     pub struct Stack;
 
@@ -58,12 +92,75 @@ all_derive! {
 // Input =========================================================================
 pub trait IntoInput<T> {
     fn into_input(self) -> BlockInputBuilder;
+
+    /// Like [`IntoInput::into_input`] but a reporter covers `shadow`,
+    /// which is what's left in the input when the reporter is dragged out in the editor.
+    ///
+    /// Anything else is a shadow itself so `shadow` is not used.
+    fn into_input_over(self, shadow: StackOrValue) -> BlockInputBuilder
+    where
+        Self: Sized,
+    {
+        let _ = shadow;
+        self.into_input()
+    }
+}
+
+/// Reporter over a shadow, `[3, reporter, shadow]` in `project.json`
+fn obscured(reporter: ItchyStackBuilder, shadow: StackOrValue) -> BlockInputBuilder {
+    BlockInputBuilder::new()
+        .input_some(StackOrValue::Stack(reporter))
+        .input_some(shadow)
 }
 
 impl<T, S, E> IntoInput<T> for Reporter<T, S, E> {
     fn into_input(self) -> BlockInputBuilder {
         BlockInputBuilder::stack(self.0.into_untyped())
     }
+
+    fn into_input_over(self, shadow: StackOrValue) -> BlockInputBuilder {
+        obscured(self.0.into_untyped(), shadow)
+    }
+}
+
+/// Input that Scratch keeps a shadow in, see [`IntoInput::into_input_over`]
+pub trait ShadowInput {
+    /// Shadow with `value` in it, like `10` in `move (10) steps`
+    fn shadow(value: impl ToString) -> StackOrValue;
+}
+
+macro_rules! shadow_input_impl {
+    ($($arg:ty => $variant:ident),*) => {
+        $(
+            impl ShadowInput for $arg {
+                fn shadow(value: impl ToString) -> StackOrValue {
+                    StackOrValue::Value(BlockInputValue::$variant { value: value.to_string().into() })
+                }
+            }
+        )*
+    }
+}
+
+shadow_input_impl! {
+    Number => Number,
+    PositiveNumber => PositiveNumber,
+    PositiveInteger => PositiveInteger,
+    Integer => Integer,
+    Float => Number,
+    Angle => Angle,
+    Color => Color,
+    Text => String,
+    Value => String
+}
+
+/// The shadow is a `note` block with a piano to pick the note
+impl ShadowInput for Note {
+    fn shadow(value: impl ToString) -> StackOrValue {
+        let mut b = BlockNormalBuilder::new("note");
+        b.add_field("NOTE", BlockFieldBuilder::new(value.to_string()));
+        b.set_shadow(true);
+        StackOrValue::Stack(ItchyStackBuilder::start(b))
+    }
 }
 
 macro_rules! into_arg_impl {
@@ -131,6 +228,9 @@ into_arg_impl! {
     Angle => Angle => f64,
     Angle => Angle => f32,
 
+    // color in hex, e.g. "#ff00ff"
+    Color => Color => String,
+
     Text => String => String,
 
     Value => Number => i64,
@@ -155,6 +255,15 @@ impl IntoInput<Text> for &str {
     }
 }
 
+/// Color in hex, e.g. `"#ff00ff"`
+impl IntoInput<Color> for &str {
+    fn into_input(self) -> BlockInputBuilder {
+        BlockInputBuilder::value(BlockInputValue::Color {
+            value: self.to_owned().into(),
+        })
+    }
+}
+
 impl IntoInput<Value> for &str {
     fn into_input(self) -> BlockInputBuilder {
         BlockInputBuilder::value(BlockInputValue::String {
@@ -163,6 +272,73 @@ impl IntoInput<Value> for &str {
     }
 }
 
+macro_rules! reporter_into_value_impl {
+    ($($from:ty),*) => {
+        $(
+            /// Any reporter can be put where text or number is expected
+            impl<S, E> IntoInput<Value> for Reporter<$from, S, E> {
+                fn into_input(self) -> BlockInputBuilder {
+                    BlockInputBuilder::stack(self.0.into_untyped())
+                }
+
+                fn into_input_over(self, shadow: StackOrValue) -> BlockInputBuilder {
+                    obscured(self.0.into_untyped(), shadow)
+                }
+            }
+        )*
+    };
+}
+
+reporter_into_value_impl!(
+    Number,
+    PositiveNumber,
+    PositiveInteger,
+    Integer,
+    Float,
+    Angle,
+    Color,
+    Text,
+    Bool,
+    ListContents
+);
+
+/// List contents can be used as text
+impl<S, E> IntoInput<Text> for Reporter<ListContents, S, E> {
+    fn into_input(self) -> BlockInputBuilder {
+        BlockInputBuilder::stack(self.0.into_untyped())
+    }
+
+    fn into_input_over(self, shadow: StackOrValue) -> BlockInputBuilder {
+        obscured(self.0.into_untyped(), shadow)
+    }
+}
+
+/// Broadcast name computed at runtime.
+/// The broadcast menu under it is left with the first broadcast of the stage.
+impl<S, E> IntoInput<Broadcast> for Reporter<Text, S, E> {
+    fn into_input(self) -> BlockInputBuilder {
+        let option = BlockFieldBuilder::new_with_kind(
+            BROADCAST_PLACEHOLDER.to_owned(),
+            FieldKind::Broadcast,
+        );
+        obscured(
+            self.0.into_untyped(),
+            StackOrValue::Stack(broadcast_menu(option)),
+        )
+    }
+}
+
+/// Note computed at runtime
+impl<S, E> IntoInput<Note> for Reporter<Number, S, E> {
+    fn into_input(self) -> BlockInputBuilder {
+        BlockInputBuilder::stack(self.0.into_untyped())
+    }
+
+    fn into_input_over(self, shadow: StackOrValue) -> BlockInputBuilder {
+        obscured(self.0.into_untyped(), shadow)
+    }
+}
+
 impl<E> IntoInput<Stack> for TypedStackBuilder<StackableSide, E> {
     fn into_input(self) -> BlockInputBuilder {
         BlockInputBuilder::stack(self.into_untyped())
@@ -180,41 +356,432 @@ impl<S: Into<String>> IntoField for S {
     }
 }
 
-impl<S: Into<String>> IntoField<Broadcast> for S {
-    fn into_field(self) -> BlockFieldBuilder {
-        BlockFieldBuilder::new_with_kind(self.into(), FieldKind::Broadcast)
-    }
+/// Escape hatch to put any string into a field that takes a typed option
+/// from [`crate::scripting::menu`].
+///
+/// Scratch won't complain but the block might not do anything if the option doesn't exist.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawField<S>(pub S);
+
+// Menu input ================================================================
+
+/// Input that Scratch shows as a dropdown menu.
+/// The menu is a shadow block with a single field that has the selected option.
+///
+/// Options of the menu go in as [`IntoField<M>`] and become the shadow block.
+/// Any reporter or variable covers the shadow block, which is left with [`MenuInput::DEFAULT`]
+/// like when the reporter is dropped in the editor.
+pub trait MenuInput {
+    /// Opcode of the shadow menu block
+    const OPCODE: &'static str;
+    /// Field of the shadow menu block
+    const FIELD: &'static str;
+    /// Option selected in the shadow menu block under a reporter
+    const DEFAULT: &'static str;
+}
+
+/// Shadow menu block of `M` with `option` selected
+fn menu_shadow<M: MenuInput>(option: BlockFieldBuilder) -> ItchyStackBuilder {
+    let mut b = BlockNormalBuilder::new(M::OPCODE);
+    b.add_field(M::FIELD, option);
+    b.set_shadow(true);
+    ItchyStackBuilder::start(b)
+}
+
+/// Shadow menu block of `M` left under a reporter
+fn menu_default<M: MenuInput>() -> StackOrValue {
+    StackOrValue::Stack(menu_shadow::<M>(BlockFieldBuilder::new(
+        M::DEFAULT.to_owned(),
+    )))
+}
+
+macro_rules! menu_input_impl {
+    ($($marker:ty => $opcode:literal $field:literal $default:literal),* $(,)?) => {
+        $(
+            impl MenuInput for $marker {
+                const OPCODE: &'static str = $opcode;
+                const FIELD: &'static str = $field;
+                const DEFAULT: &'static str = $default;
+            }
+
+            impl<O: IntoField<$marker>> IntoInput<$marker> for O {
+                fn into_input(self) -> BlockInputBuilder {
+                    BlockInputBuilder::stack(menu_shadow::<$marker>(self.into_field()))
+                }
+            }
+
+            impl<T, S, E> IntoInput<$marker> for Reporter<T, S, E>
+            where
+                Reporter<T, S, E>: IntoInput<Value>,
+            {
+                fn into_input(self) -> BlockInputBuilder {
+                    self.into_input_over(menu_default::<$marker>())
+                }
+            }
+
+            impl<T> IntoInput<$marker> for &VarHandle<T>
+            where
+                JustReporter<T>: IntoInput<Value>,
+            {
+                fn into_input(self) -> BlockInputBuilder {
+                    crate::blocks::var(self).into_input_over(menu_default::<$marker>())
+                }
+            }
+        )*
+    };
+}
+
+menu_input_impl! {
+    // marker => opcode field default
+    Costume => "looks_costume" "COSTUME" "costume1",
+    Backdrop => "looks_backdrops" "BACKDROP" "backdrop1",
+    Sound => "sound_sounds_menu" "SOUND_MENU" "pop",
+    GoToTarget => "motion_goto_menu" "TO" "_random_",
+    GlideToTarget => "motion_glideto_menu" "TO" "_random_",
+    PointTowardsTarget => "motion_pointtowards_menu" "TOWARDS" "_mouse_",
+    TouchingTarget => "sensing_touchingobjectmenu" "TOUCHINGOBJECTMENU" "_mouse_",
+    DistanceToTarget => "sensing_distancetomenu" "DISTANCETOMENU" "_mouse_",
+    CloneTarget => "control_create_clone_of_menu" "CLONE_OPTION" "_myself_",
+    ObjectTarget => "sensing_of_object_menu" "OBJECT" "_stage_",
+    Key => "sensing_keyoptions" "KEY_OPTION" "space",
+}
+
+macro_rules! menu_option_impl {
+    ($($option:ty => $value:literal in [$($marker:ty),*]),* $(,)?) => {
+        $($(
+            impl IntoField<$marker> for $option {
+                fn into_field(self) -> BlockFieldBuilder {
+                    BlockFieldBuilder::new($value.to_owned())
+                }
+            }
+        )*)*
+    };
 }
 
+/// The mouse pointer
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Mouse;
+/// A random position
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
-pub struct GlobalVar<S: Into<String>>(pub S);
+pub struct Random;
+/// Edge of the stage
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
-pub struct SpriteVar<S: Into<String>>(pub S);
+pub struct Edge;
+/// The stage
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
-pub struct GlobalList<S: Into<String>>(pub S);
+pub struct Stage;
+/// The sprite that runs the block
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
-pub struct SpriteList<S: Into<String>>(pub S);
+pub struct Myself;
+
+menu_option_impl! {
+    Mouse => "_mouse_" in [GoToTarget, GlideToTarget, PointTowardsTarget, TouchingTarget, DistanceToTarget],
+    Random => "_random_" in [GoToTarget, GlideToTarget],
+    Edge => "_edge_" in [TouchingTarget],
+    Stage => "_stage_" in [ObjectTarget],
+    Myself => "_myself_" in [CloneTarget],
+}
 
-impl<S: Into<String>> IntoField<Variable> for GlobalVar<S> {
-    fn into_field(self) -> BlockFieldBuilder {
-        BlockFieldBuilder::new_with_kind(self.0.into(), FieldKind::GlobalVariable)
+macro_rules! name_option_impl {
+    ($($option:ty => [$($marker:ty),*]),* $(,)?) => {
+        $($(
+            impl IntoField<$marker> for $option {
+                fn into_field(self) -> BlockFieldBuilder {
+                    BlockFieldBuilder::new(self.into())
+                }
+            }
+        )*)*
+    };
+}
+
+name_option_impl! {
+    &str => [Costume, Backdrop, Sound],
+    String => [Costume, Backdrop, Sound],
+}
+
+macro_rules! raw_field_impl {
+    ($($marker:ty),*) => {
+        $(
+            impl<S: Into<String>> IntoField<$marker> for RawField<S> {
+                fn into_field(self) -> BlockFieldBuilder {
+                    BlockFieldBuilder::new(self.0.into())
+                }
+            }
+        )*
+    };
+}
+
+raw_field_impl!(
+    Costume,
+    Backdrop,
+    Sound,
+    GoToTarget,
+    GlideToTarget,
+    PointTowardsTarget,
+    TouchingTarget,
+    DistanceToTarget,
+    CloneTarget,
+    ObjectTarget
+);
+
+// Variable/List handle ======================================================
+
+/// Where a variable or list lives
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Scope {
+    /// Belongs to the stage, for all sprites
+    Global,
+    /// Belongs to a single sprite, for this sprite only
+    Sprite,
+}
+
+/// Id given to each declared variable and list, unique in the program
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct DataId(u64);
+
+/// Start of the name a handle puts in its field, resolved to the declared variable or list on build
+pub(crate) const DATA_ID_PREFIX: &str = "\0data:";
+
+static NEXT_DATA_ID: AtomicU64 = AtomicU64::new(0);
+
+impl DataId {
+    pub(crate) fn generate() -> DataId {
+        DataId(NEXT_DATA_ID.fetch_add(1, Ordering::Relaxed))
+    }
+
+    /// Name used in fields until the project is built, `name` is kept to tell which it is
+    pub(crate) fn key(self, name: &str) -> String {
+        format!("{DATA_ID_PREFIX}{}:{name}", self.0)
+    }
+}
+
+/// Handle to a declared variable.
+///
+/// Created by [`crate::project::StageBuilder::declare_var`] (global)
+/// or [`crate::project::SpriteBuilder::declare_var`] (for this sprite only)
+/// which also add the variable to that target.
+/// `T` is the type of value this variable holds.
+///
+/// The handle refers to the variable it was declared as, not just to its name.
+/// Building a project that uses a sprite's handle in another sprite is an error,
+/// see [`crate::project::BuildError::ForeignHandle`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct VarHandle<T> {
+    id: Option<DataId>,
+    name: String,
+    scope: Scope,
+    marker: PhantomData<T>,
+}
+
+/// Handle to a declared list.
+///
+/// Created by [`crate::project::StageBuilder::declare_list`] (global)
+/// or [`crate::project::SpriteBuilder::declare_list`] (for this sprite only)
+/// which also add the list to that target.
+/// `T` is the type of item this list holds.
+///
+/// Like [`VarHandle`], it refers to the list it was declared as.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ListHandle<T> {
+    id: Option<DataId>,
+    name: String,
+    scope: Scope,
+    marker: PhantomData<T>,
+}
+
+impl<T> VarHandle<T> {
+    /// Handle to whatever variable is named `name` in the target it's used in
+    pub(crate) fn new(name: String, scope: Scope) -> VarHandle<T> {
+        VarHandle {
+            id: None,
+            name,
+            scope,
+            marker: PhantomData,
+        }
+    }
+
+    pub(crate) fn declared(id: DataId, name: String, scope: Scope) -> VarHandle<T> {
+        VarHandle {
+            id: Some(id),
+            name,
+            scope,
+            marker: PhantomData,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn scope(&self) -> Scope {
+        self.scope
+    }
+
+    /// Name in blocks that use it until the project is built
+    pub(crate) fn field_name(&self) -> String {
+        self.id
+            .map_or_else(|| self.name.clone(), |id| id.key(&self.name))
     }
 }
 
-impl<S: Into<String>> IntoField<Variable> for SpriteVar<S> {
+impl<T> ListHandle<T> {
+    /// Handle to whatever list is named `name` in the target it's used in
+    #[cfg(test)]
+    pub(crate) fn new(name: String, scope: Scope) -> ListHandle<T> {
+        ListHandle {
+            id: None,
+            name,
+            scope,
+            marker: PhantomData,
+        }
+    }
+
+    pub(crate) fn declared(id: DataId, name: String, scope: Scope) -> ListHandle<T> {
+        ListHandle {
+            id: Some(id),
+            name,
+            scope,
+            marker: PhantomData,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn scope(&self) -> Scope {
+        self.scope
+    }
+
+    /// Same list with items typed as `U`
+    pub(crate) fn retype<U>(&self) -> ListHandle<U> {
+        ListHandle {
+            id: self.id,
+            name: self.name.clone(),
+            scope: self.scope,
+            marker: PhantomData,
+        }
+    }
+
+    /// Name in blocks that use it until the project is built
+    pub(crate) fn field_name(&self) -> String {
+        self.id
+            .map_or_else(|| self.name.clone(), |id| id.key(&self.name))
+    }
+}
+
+impl<T> IntoField<Variable> for &VarHandle<T> {
     fn into_field(self) -> BlockFieldBuilder {
-        BlockFieldBuilder::new_with_kind(self.0.into(), FieldKind::SpriteVariable)
+        let kind = match self.scope {
+            Scope::Global => FieldKind::GlobalVariable,
+            Scope::Sprite => FieldKind::SpriteVariable,
+        };
+        BlockFieldBuilder::new_with_kind(self.field_name(), kind)
     }
 }
 
-impl<S: Into<String>> IntoField<List> for GlobalList<S> {
+impl<T> IntoField<List> for &ListHandle<T> {
     fn into_field(self) -> BlockFieldBuilder {
-        BlockFieldBuilder::new_with_kind(self.0.into(), FieldKind::GlobalList)
+        let kind = match self.scope {
+            Scope::Global => FieldKind::GlobalList,
+            Scope::Sprite => FieldKind::SpriteList,
+        };
+        BlockFieldBuilder::new_with_kind(self.field_name(), kind)
+    }
+}
+
+impl<T> IntoInput<T> for &VarHandle<T> {
+    fn into_input(self) -> BlockInputBuilder {
+        crate::blocks::var(self).into_input()
+    }
+
+    fn into_input_over(self, shadow: StackOrValue) -> BlockInputBuilder {
+        crate::blocks::var(self).into_input_over(shadow)
     }
 }
 
-impl<S: Into<String>> IntoField<List> for SpriteList<S> {
+// Broadcast handle ============================================================
+
+/// Handle to a declared broadcast.
+///
+/// Created by [`crate::project::StageBuilder::declare_broadcast`] which also add the broadcast to the stage.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BroadcastHandle {
+    name: String,
+}
+
+impl BroadcastHandle {
+    pub(crate) fn new(name: String) -> BroadcastHandle {
+        BroadcastHandle { name }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl IntoField<Broadcast> for &BroadcastHandle {
     fn into_field(self) -> BlockFieldBuilder {
-        BlockFieldBuilder::new_with_kind(self.0.into(), FieldKind::SpriteList)
+        BlockFieldBuilder::new_with_kind(self.name.clone(), FieldKind::Broadcast)
     }
 }
+
+impl IntoInput<Broadcast> for &BroadcastHandle {
+    fn into_input(self) -> BlockInputBuilder {
+        BlockInputBuilder::stack(broadcast_menu(self.into_field()))
+    }
+}
+
+/// Broadcast menu under a reporter until the project is built,
+/// then it's replaced by the first broadcast of the stage
+pub(crate) const BROADCAST_PLACEHOLDER: &str = "\0broadcast";
+
+/// Shadow broadcast menu block with `option` selected
+fn broadcast_menu(option: BlockFieldBuilder) -> ItchyStackBuilder {
+    let mut menu = BlockNormalBuilder::new("event_broadcast_menu");
+    menu.add_field("BROADCAST_OPTION", option);
+    menu.set_shadow(true);
+    ItchyStackBuilder::start(menu)
+}
+
+// Sprite handle ===============================================================
+
+/// Handle to a sprite, to select it in menus like the one of [`crate::blocks::go_to`].
+///
+/// Created by [`crate::project::SpriteBuilder::handle`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SpriteHandle {
+    name: String,
+}
+
+impl SpriteHandle {
+    pub(crate) fn new(name: String) -> SpriteHandle {
+        SpriteHandle { name }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+macro_rules! sprite_option_impl {
+    ($($marker:ty),*) => {
+        $(
+            impl IntoField<$marker> for &SpriteHandle {
+                fn into_field(self) -> BlockFieldBuilder {
+                    BlockFieldBuilder::new(self.name.clone())
+                }
+            }
+        )*
+    };
+}
+
+sprite_option_impl!(
+    GoToTarget,
+    GlideToTarget,
+    PointTowardsTarget,
+    TouchingTarget,
+    DistanceToTarget,
+    CloneTarget,
+    ObjectTarget
+);
@@ -0,0 +1,477 @@
+//! Create custom block (My Blocks / procedures)
+//!
+//! # Explanation
+//!
+//! A custom block in Scratch is made out of 3 parts:
+//!  - The definition hat `procedures_definition` that holds a `procedures_prototype`
+//!    describing the block's look and arguments.
+//!  - The call block `procedures_call` that runs the definition.
+//!  - Argument reporters `argument_reporter_string_number` and `argument_reporter_boolean`
+//!    that can be used inside the definition.
+//!
+//! This module keeps all 3 parts in sync from a single [`CustomBlock`] so that
+//! the arguments at the call site is type checked the same way built-in blocks are.
+//!
+//! # Usage
+//!
+//! ```
+//! # use sb_scratchy::blocks::{equals, mouse_down, say, turn_right, if_};
+//! # use sb_scratchy::scripting::arg::{Bool, Value};
+//! # use sb_scratchy::scripting::custom_block::*;
+//! let jump = CustomBlockBuilder::new()
+//!     .text("jump")
+//!     .arg::<Value>("height")
+//!     .text("and turn")
+//!     .arg::<Bool>("turn")
+//!     .warp(true)
+//!     .build();
+//!
+//! let definition = jump.define()
+//!     .next(if_(equals(jump.arg_string_number("height"), 0), say("too low")))
+//!     .next(if_(jump.arg_boolean("turn"), turn_right(180)));
+//!
+//! let call = jump.call((10, mouse_down()));
+//! ```
+
+use std::marker::PhantomData;
+
+use sb_itchy::{
+    block::{BlockFieldBuilder, BlockInputBuilder, BlockNormalBuilder},
+    stack::StackBuilder as ItchyStackBuilder,
+};
+use sb_sbity::block::{BlockMutation, BlockMutationEnum};
+
+use crate::blocks::{set_var_to, stop, var};
+use crate::scripting::{arg::*, menu::StopOption, stack::*};
+
+/// Argument type that can be used in custom block.
+///
+/// Only implemented for [`Value`] (`%s`) and [`Bool`] (`%b`) as that's all Scratch has.
+pub trait CustomBlockArgType {
+    /// Placeholder of the argument in the proccode
+    const PLACEHOLDER: &'static str;
+    /// Default value of the argument in the prototype
+    const DEFAULT: &'static str;
+    /// Opcode of the argument reporter
+    const REPORTER_OPCODE: &'static str;
+
+    /// Input of the argument in a call
+    fn call_input(input: impl IntoInput<Self>) -> BlockInputBuilder
+    where
+        Self: Sized;
+}
+
+impl CustomBlockArgType for Value {
+    const PLACEHOLDER: &'static str = "%s";
+    const DEFAULT: &'static str = "";
+    const REPORTER_OPCODE: &'static str = "argument_reporter_string_number";
+
+    fn call_input(input: impl IntoInput<Value>) -> BlockInputBuilder {
+        value_call_input(input)
+    }
+}
+
+impl CustomBlockArgType for Bool {
+    const PLACEHOLDER: &'static str = "%b";
+    const DEFAULT: &'static str = "false";
+    const REPORTER_OPCODE: &'static str = "argument_reporter_boolean";
+
+    /// Boolean inputs have no shadow
+    fn call_input(input: impl IntoInput<Bool>) -> BlockInputBuilder {
+        input.into_input()
+    }
+}
+
+/// Input of a `%s` argument in a call, over the empty text Scratch keeps under reporters
+pub(crate) fn value_call_input<T>(input: impl IntoInput<T>) -> BlockInputBuilder {
+    input.into_input_over(<Value as ShadowInput>::shadow(Value::DEFAULT))
+}
+
+/// Argument types of a custom block after adding another argument `T`.
+///
+/// Implemented for tuples up to 8 arguments.
+pub trait PushArg<T> {
+    type Output;
+}
+
+/// Arguments that can be passed to [`CustomBlock::call`] of custom block with argument types `A`.
+///
+/// Implemented for tuples up to 8 arguments where each element is [`IntoInput`] of its argument type.
+pub trait CallArgs<A> {
+    fn into_inputs(self) -> Vec<BlockInputBuilder>;
+}
+
+macro_rules! push_arg_impl {
+    ($($a:ident),*) => {
+        impl<$($a,)* T> PushArg<T> for ($($a,)*) {
+            type Output = ($($a,)* T,);
+        }
+    };
+}
+
+push_arg_impl!();
+push_arg_impl!(A0);
+push_arg_impl!(A0, A1);
+push_arg_impl!(A0, A1, A2);
+push_arg_impl!(A0, A1, A2, A3);
+push_arg_impl!(A0, A1, A2, A3, A4);
+push_arg_impl!(A0, A1, A2, A3, A4, A5);
+push_arg_impl!(A0, A1, A2, A3, A4, A5, A6);
+
+macro_rules! call_args_impl {
+    ($($a:ident => $i:ident),*) => {
+        impl<$($a: CustomBlockArgType,)* $($i: IntoInput<$a>),*> CallArgs<($($a,)*)> for ($($i,)*) {
+            #[allow(non_snake_case)]
+            fn into_inputs(self) -> Vec<BlockInputBuilder> {
+                let ($($i,)*) = self;
+                vec![$($a::call_input($i)),*]
+            }
+        }
+    };
+}
+
+call_args_impl!();
+call_args_impl!(A0 => I0);
+call_args_impl!(A0 => I0, A1 => I1);
+call_args_impl!(A0 => I0, A1 => I1, A2 => I2);
+call_args_impl!(A0 => I0, A1 => I1, A2 => I2, A3 => I3);
+call_args_impl!(A0 => I0, A1 => I1, A2 => I2, A3 => I3, A4 => I4);
+call_args_impl!(A0 => I0, A1 => I1, A2 => I2, A3 => I3, A4 => I4, A5 => I5);
+call_args_impl!(A0 => I0, A1 => I1, A2 => I2, A3 => I3, A4 => I4, A5 => I5, A6 => I6);
+call_args_impl!(A0 => I0, A1 => I1, A2 => I2, A3 => I3, A4 => I4, A5 => I5, A6 => I6, A7 => I7);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct CustomBlockArg {
+    id: String,
+    name: String,
+    default: &'static str,
+    reporter_opcode: &'static str,
+}
+
+/// Builder to create [`CustomBlock`].
+///
+/// `A` is a tuple of argument types added so far.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CustomBlockBuilder<A> {
+    proccode: Vec<String>,
+    args: Vec<CustomBlockArg>,
+    warp: bool,
+    marker: PhantomData<A>,
+}
+
+impl CustomBlockBuilder<()> {
+    /// Start building custom block with no text or argument
+    pub fn new() -> CustomBlockBuilder<()> {
+        CustomBlockBuilder {
+            proccode: vec![],
+            args: vec![],
+            warp: false,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl Default for CustomBlockBuilder<()> {
+    fn default() -> Self {
+        CustomBlockBuilder::new()
+    }
+}
+
+impl<A> CustomBlockBuilder<A> {
+    /// Add label text to the block.
+    ///
+    /// `%` is escaped in the proccode so it shows as is instead of being taken for an argument.
+    pub fn text(mut self, text: impl Into<String>) -> CustomBlockBuilder<A> {
+        self.proccode.push(text.into().replace('%', "\\%"));
+        self
+    }
+
+    /// Add argument to the block.
+    ///
+    /// `T` is either [`Value`] for text/number argument or [`Bool`] for boolean argument.
+    pub fn arg<T>(self, name: impl Into<String>) -> CustomBlockBuilder<<A as PushArg<T>>::Output>
+    where
+        T: CustomBlockArgType,
+        A: PushArg<T>,
+    {
+        let CustomBlockBuilder {
+            mut proccode,
+            mut args,
+            warp,
+            marker: _,
+        } = self;
+        proccode.push(T::PLACEHOLDER.to_owned());
+        args.push(CustomBlockArg {
+            id: format!("arg{}", args.len()),
+            name: name.into(),
+            default: T::DEFAULT,
+            reporter_opcode: T::REPORTER_OPCODE,
+        });
+        CustomBlockBuilder {
+            proccode,
+            args,
+            warp,
+            marker: PhantomData,
+        }
+    }
+
+    /// Set "Run without screen refresh"
+    pub fn warp(mut self, warp: bool) -> CustomBlockBuilder<A> {
+        self.warp = warp;
+        self
+    }
+
+    /// Finish building
+    pub fn build(self) -> CustomBlock<A> {
+        let CustomBlockBuilder {
+            proccode,
+            args,
+            warp,
+            marker: _,
+        } = self;
+        CustomBlock {
+            proccode: proccode.join(" "),
+            args,
+            warp,
+            marker: PhantomData,
+        }
+    }
+}
+
+/// A custom block that can be defined and called.
+///
+/// `A` is a tuple of argument types.
+/// See top module documentation for usage.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CustomBlock<A> {
+    proccode: String,
+    args: Vec<CustomBlockArg>,
+    warp: bool,
+    marker: PhantomData<A>,
+}
+
+impl<A> CustomBlock<A> {
+    /// Proccode of the custom block, e.g. `"jump %s and turn %b"`
+    pub fn proccode(&self) -> &str {
+        &self.proccode
+    }
+
+    /// If the custom block runs without screen refresh
+    pub fn is_warp(&self) -> bool {
+        self.warp
+    }
+
+    /// Text/number argument reporter of this custom block.
+    /// Only works inside the definition.
+    ///
+    /// # Panics
+    ///
+    /// If the custom block has no text/number argument named `name`.
+    pub fn arg_string_number(&self, name: &str) -> JustReporter<Value> {
+        self.arg_reporter(name)
+    }
+
+    /// Boolean argument reporter of this custom block.
+    /// Only works inside the definition.
+    ///
+    /// # Panics
+    ///
+    /// If the custom block has no boolean argument named `name`.
+    pub fn arg_boolean(&self, name: &str) -> JustReporter<Bool> {
+        self.arg_reporter(name)
+    }
+
+    fn arg_reporter<T: CustomBlockArgType>(&self, name: &str) -> JustReporter<T> {
+        let found = self
+            .args
+            .iter()
+            .any(|arg| arg.name == name && arg.reporter_opcode == T::REPORTER_OPCODE);
+        assert!(
+            found,
+            "custom block `{}` has no `{}` argument named `{name}`",
+            self.proccode,
+            T::PLACEHOLDER,
+        );
+        arg_reporter(name)
+    }
+
+    /// Create the `procedures_definition` hat of this custom block.
+    ///
+    /// Stack the body of the custom block below it.
+    pub fn define(&self) -> HatBlock {
+        let mut b = BlockNormalBuilder::new("procedures_definition");
+        b.add_input("custom_block", BlockInputBuilder::stack(self.prototype()));
+        BlockShape::from_block(b)
+    }
+
+    /// Create the `procedures_call` of this custom block
+    pub fn call(&self, args: impl CallArgs<A>) -> StackBlock {
+        self.call_with_inputs(args.into_inputs())
+    }
+
+    /// Call with inputs of the arguments in order
+    pub(crate) fn call_with_inputs(&self, inputs: Vec<BlockInputBuilder>) -> StackBlock {
+        let mut b = BlockNormalBuilder::new("procedures_call");
+        for (arg, input) in self.args.iter().zip(inputs) {
+            b.add_input(arg.id.clone(), input);
+        }
+        b.set_mutation(mutation(BlockMutationEnum::ProceduresCall {
+            proccode: self.proccode.clone(),
+            argumentids: self.json_of(|arg| arg.id.clone()),
+            warp: Some(self.warp),
+        }));
+        BlockShape::from_block(b)
+    }
+
+    fn prototype(&self) -> ItchyStackBuilder {
+        let mut b = BlockNormalBuilder::new("procedures_prototype");
+        b.set_shadow(true);
+        for arg in &self.args {
+            let mut reporter = BlockNormalBuilder::new(arg.reporter_opcode);
+            reporter.add_field("VALUE", BlockFieldBuilder::new(arg.name.clone()));
+            reporter.set_shadow(true);
+            b.add_input(
+                arg.id.clone(),
+                BlockInputBuilder::stack(ItchyStackBuilder::start(reporter)),
+            );
+        }
+        b.set_mutation(mutation(BlockMutationEnum::ProceduresPrototype {
+            proccode: self.proccode.clone(),
+            argumentids: self.json_of(|arg| arg.id.clone()),
+            argumentnames: self.json_of(|arg| arg.name.clone()),
+            argumentdefaults: self.json_of(|arg| arg.default.to_owned()),
+            warp: Some(self.warp),
+        }));
+        ItchyStackBuilder::start(b)
+    }
+
+    /// Scratch stores argument ids, names and defaults as json encoded string array
+    fn json_of(&self, f: impl FnMut(&CustomBlockArg) -> String) -> String {
+        serde_json::to_string(&self.args.iter().map(f).collect::<Vec<_>>())
+            .expect("string array is always serializable")
+    }
+}
+
+/// A custom block that returns a value of type `T`.
+///
+/// Scratch custom blocks can't report anything, so the return value is put in a sprite-only variable
+/// and read from it after calling.
+/// Created by [`crate::project::SpriteBuilder::declare_function`].
+///
+/// If the function ends without [`Function::return_`], the variable keeps what the last call returned.
+/// The variable is shared by every call in the sprite (clones have their own),
+/// read the return value right after calling before other scripts of the sprite get to call it.
+///
+/// # Examples
+///
+/// ```
+/// # use sb_scratchy::blocks::{equals, if_, less_than, say, when_flag_clicked};
+/// # use sb_scratchy::project::{SpriteBuilder, TargetBuilder};
+/// # use sb_scratchy::scripting::arg::Value;
+/// # use sb_scratchy::scripting::custom_block::CustomBlockBuilder;
+/// let mut cat = SpriteBuilder::new(TargetBuilder::new("Cat"));
+/// let at_least_ten = cat.declare_function::<_, Value>(
+///     CustomBlockBuilder::new()
+///         .text("at least ten")
+///         .arg::<Value>("n")
+///         .warp(true),
+/// );
+///
+/// let definition = at_least_ten
+///     .define()
+///     .next(if_(
+///         less_than(at_least_ten.arg_string_number("n"), 10),
+///         at_least_ten.return_(10),
+///     ))
+///     .next(at_least_ten.return_(at_least_ten.arg_string_number("n")));
+///
+/// let (call, ret) = at_least_ten.call((4,));
+/// let script = when_flag_clicked()
+///     .next(call)
+///     .next(if_(equals(ret, 10), say("it was less than ten")));
+///
+/// let cat = cat.add_script(definition).add_script(script);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Function<A, T> {
+    custom_block: CustomBlock<A>,
+    ret: VarHandle<T>,
+}
+
+impl<A, T> Function<A, T> {
+    pub(crate) fn new(custom_block: CustomBlock<A>, ret: VarHandle<T>) -> Function<A, T> {
+        Function { custom_block, ret }
+    }
+
+    /// Custom block of this function
+    pub fn custom_block(&self) -> &CustomBlock<A> {
+        &self.custom_block
+    }
+
+    /// Variable the return value is put in
+    pub fn return_var(&self) -> &VarHandle<T> {
+        &self.ret
+    }
+
+    /// Text/number argument reporter, see [`CustomBlock::arg_string_number`]
+    pub fn arg_string_number(&self, name: &str) -> JustReporter<Value> {
+        self.custom_block.arg_string_number(name)
+    }
+
+    /// Boolean argument reporter, see [`CustomBlock::arg_boolean`]
+    pub fn arg_boolean(&self, name: &str) -> JustReporter<Bool> {
+        self.custom_block.arg_boolean(name)
+    }
+
+    /// Create the `procedures_definition` hat of this function.
+    ///
+    /// Stack the body of the function below it.
+    pub fn define(&self) -> HatBlock {
+        self.custom_block.define()
+    }
+
+    /// Return `value` from the function.
+    ///
+    /// Only use this in the definition, it's `stop "this script"` which stops the whole script outside.
+    pub fn return_(&self, value: impl IntoInput<T>) -> CapBlock {
+        set_var_to(&self.ret, value).next(stop(StopOption::ThisScript))
+    }
+
+    /// Call the function.
+    ///
+    /// Gives the call block and a reporter of the return value that can be used after the call block.
+    pub fn call(&self, args: impl CallArgs<A>) -> (StackBlock, JustReporter<T>) {
+        self.call_with_inputs(args.into_inputs())
+    }
+
+    /// Call with inputs of the arguments in order
+    pub(crate) fn call_with_inputs(
+        &self,
+        inputs: Vec<BlockInputBuilder>,
+    ) -> (StackBlock, JustReporter<T>) {
+        (self.custom_block.call_with_inputs(inputs), var(&self.ret))
+    }
+}
+
+/// Name of the variable that keeps the return value of a function
+pub(crate) fn return_var_name(proccode: &str) -> String {
+    format!("{proccode} (return)")
+}
+
+fn mutation(mutation_enum: BlockMutationEnum) -> BlockMutation {
+    BlockMutation {
+        tag_name: "mutation".to_owned(),
+        children: vec![],
+        mutation_enum,
+    }
+}
+
+/// Argument reporter named `name`, not checked against any custom block
+pub(crate) fn arg_reporter<T: CustomBlockArgType>(name: impl Into<String>) -> JustReporter<T> {
+    let mut b = BlockNormalBuilder::new(T::REPORTER_OPCODE);
+    b.add_field("VALUE", BlockFieldBuilder::new(name.into()));
+    BlockShape::from_block(b)
+}
+
+#[cfg(test)]
+mod tests;
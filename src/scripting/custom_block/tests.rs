@@ -0,0 +1,87 @@
+use super::*;
+use crate::blocks::{change_var_by, if_, less_than, when_flag_clicked};
+use crate::interpreter::start;
+use crate::project::{SpriteBuilder, StageBuilder, TargetBuilder};
+
+fn jump() -> CustomBlock<(Value, Bool)> {
+    CustomBlockBuilder::new()
+        .text("jump")
+        .arg::<Value>("height")
+        .text("and turn")
+        .arg::<Bool>("turn")
+        .build()
+}
+
+#[test]
+fn percent_in_label_is_escaped() {
+    let block = CustomBlockBuilder::new()
+        .text("add 10%")
+        .arg::<Value>("n")
+        .text("%s")
+        .build();
+    assert_eq!(block.proccode(), "add 10\\% %s \\%s");
+}
+
+#[test]
+fn arg_reporters_of_the_block() {
+    let block = jump();
+    block.arg_string_number("height");
+    block.arg_boolean("turn");
+}
+
+#[test]
+#[should_panic(expected = "no `%s` argument named `hieght`")]
+fn misspelled_arg_panics() {
+    jump().arg_string_number("hieght");
+}
+
+#[test]
+#[should_panic(expected = "no `%b` argument named `height`")]
+fn arg_of_other_type_panics() {
+    jump().arg_boolean("height");
+}
+
+#[test]
+fn function_returns_early() {
+    let mut cat = SpriteBuilder::new(TargetBuilder::new("Cat"));
+    let small = cat.declare_var::<Value>("small", 0.);
+    let big = cat.declare_var::<Value>("big", 0.);
+    let late_returns = cat.declare_var::<Number>("late returns", 0.);
+    let at_least_ten = cat.declare_function::<_, Value>(
+        CustomBlockBuilder::new()
+            .text("at least ten")
+            .arg::<Value>("n")
+            .warp(true),
+    );
+    let definition = at_least_ten
+        .define()
+        .next(if_(
+            less_than(at_least_ten.arg_string_number("n"), 10),
+            at_least_ten.return_(10),
+        ))
+        .next(change_var_by(&late_returns, 1))
+        .next(at_least_ten.return_(at_least_ten.arg_string_number("n")));
+    let (call_small, ret_small) = at_least_ten.call((4,));
+    let (call_big, ret_big) = at_least_ten.call((20,));
+    let script = when_flag_clicked()
+        .next(call_small)
+        .next(set_var_to(&small, ret_small))
+        .next(call_big)
+        .next(set_var_to(&big, ret_big));
+    let mut interpreter = start(
+        StageBuilder::default(),
+        cat.add_script(definition).add_script(script),
+    );
+    assert!(interpreter.run_until_idle(10));
+    let number = |name| interpreter.variable("Cat", name).unwrap().to_number();
+    assert_eq!(number("small"), 10.);
+    assert_eq!(number("big"), 20.);
+    assert_eq!(number("late returns"), 1.);
+    // the return variable is the sprite's own
+    assert!(interpreter
+        .variable(
+            "Cat",
+            &return_var_name(at_least_ten.custom_block().proccode())
+        )
+        .is_some());
+}
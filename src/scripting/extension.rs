@@ -0,0 +1,134 @@
+//! Define blocks of extensions that aren't in [`crate::blocks`]
+//!
+//! # Explanation
+//!
+//! Opcode of an extension block is prefixed with id of the extension, e.g. `pen_clear` is block `clear` of extension `pen`.
+//! Extensions also make their menus as shadow blocks named `<id>_menu_<menu name>` that have a field with the menu name.
+//!
+//! [`extension_blocks!`](crate::extension_blocks) writes all of this for you and gives you
+//! typed functions the same as the ones in [`crate::blocks`].
+//! When the project is built, ids of the extensions that its blocks are from is added to the project.
+//!
+//! # Usage
+//!
+//! ```
+//! use sb_scratchy::blocks::say;
+//! use sb_scratchy::extension_blocks;
+//! use sb_scratchy::scripting::{arg::*, stack::*};
+//!
+//! extension_blocks! {
+//!     extension "fetch";
+//!
+//!     /// Fetch text from an url
+//!     #[opcode("fetch")]
+//!     pub fn fetch(
+//!         #[input("URL")] url: (IntoInput<Text>) = "https://extensions.turbowarp.org/hello.txt"
+//!     ) -> JustReporter<Text>;
+//! }
+//!
+//! extension_blocks! {
+//!     extension "lights";
+//!
+//!     #[opcode("whenButtonPressed")]
+//!     pub fn when_button_pressed(#[field("BUTTON")] button: (IntoField)) -> HatBlock;
+//!
+//!     #[opcode("setColor")]
+//!     pub fn set_light_color(
+//!         #[menu("LIGHT", "lights")] light: (IntoField),
+//!         #[input("COLOR")] color: (IntoInput<Color>) = "#ff0000"
+//!     ) -> StackBlock;
+//! }
+//!
+//! let script = when_button_pressed("A")
+//!     .next(set_light_color("left", "#00ff00"))
+//!     .next(say(fetch("https://example.com")));
+//! ```
+
+use sb_itchy::{
+    block::{BlockFieldBuilder, BlockInputBuilder, BlockNormalBuilder},
+    stack::StackBuilder as ItchyStackBuilder,
+};
+
+/// Input with a shadow menu block that has a single field, like menus of extension blocks
+pub fn menu_input(opcode: &str, field_name: &str, field: BlockFieldBuilder) -> BlockInputBuilder {
+    let mut b = BlockNormalBuilder::new(opcode);
+    b.add_field(field_name, field);
+    b.set_shadow(true);
+    BlockInputBuilder::stack(ItchyStackBuilder::start(b))
+}
+
+#[doc(hidden)]
+pub mod __private {
+    pub use sb_itchy::block::BlockNormalBuilder;
+}
+
+/// Define typed functions for blocks of an extension.
+///
+/// Each function is written like a function signature with an opcode attached:
+///  - `#[opcode("...")]` is the opcode without the extension id prefix.
+///  - Each argument says where it goes in the block and which type it accepts.
+///     - `#[input("NAME")] arg: (IntoInput<T>)` is an input.
+///       Add `= default` after it for the shadow that's kept when a reporter is put in.
+///     - `#[field("NAME")] arg: (IntoField<T>)` is a field.
+///     - `#[menu("NAME", "menu")] arg: (IntoField<T>)` is an input with the extension's menu `menu`.
+///  - Return type is the shape of the block,
+///    see [`crate::scripting::stack::BlockShape`].
+///
+/// Types are resolved where the macro is called so they need to be in scope.
+///
+/// See [`crate::scripting::extension`] for examples.
+#[macro_export]
+macro_rules! extension_blocks {
+    (
+        extension $extension:literal;
+        $(
+            $(#[doc = $doc:expr])*
+            #[opcode($opcode:literal)]
+            $(#[$attributes:meta])*
+            $vis:vis fn $fn_name:ident( $(
+                #[$kind:ident($($place:literal),+)]
+                $arg_name:ident: ($($arg_trait:tt)+) $(= $default:expr)?
+            ),* $(,)? ) -> $return_ty:ty;
+        )*
+    ) => {
+        $(
+            $(#[doc = $doc])*
+            $(#[$attributes])*
+            $vis fn $fn_name(
+                $($arg_name: impl $($arg_trait)+),*
+            ) -> $return_ty
+            {
+                #[allow(unused_mut)]
+                let mut b = $crate::scripting::extension::__private::BlockNormalBuilder::new(
+                    concat!($extension, "_", $opcode)
+                );
+                $(
+                    $crate::extension_blocks!(
+                        @arg $extension, b, $kind($($place),+) ($($arg_trait)+) $arg_name $(, $default)?
+                    );
+                )*
+                $crate::scripting::stack::BlockShape::from_block(b)
+            }
+        )*
+    };
+
+    (@arg $extension:literal, $b:ident, input($name:literal) (IntoInput<$t:ty>) $arg:ident, $default:expr) => {
+        $b.add_input($name, $crate::scripting::arg::IntoInput::<$t>::into_input_over(
+            $arg,
+            <$t as $crate::scripting::arg::ShadowInput>::shadow($default),
+        ))
+    };
+    (@arg $extension:literal, $b:ident, input($name:literal) (IntoInput<$t:ty>) $arg:ident) => {
+        $b.add_input($name, $crate::scripting::arg::IntoInput::<$t>::into_input($arg))
+    };
+    (@arg $extension:literal, $b:ident, field($name:literal) (IntoField $(<$t:ty>)?) $arg:ident) => {
+        $b.add_field($name, $crate::scripting::arg::IntoField::$(<$t>::)?into_field($arg))
+    };
+    (@arg $extension:literal, $b:ident, menu($name:literal, $menu:literal) (IntoField $(<$t:ty>)?) $arg:ident) => {
+        $b.add_input($name, $crate::scripting::extension::menu_input(
+            concat!($extension, "_menu_", $menu),
+            $menu,
+            $crate::scripting::arg::IntoField::$(<$t>::)?into_field($arg),
+        ))
+    };
+}
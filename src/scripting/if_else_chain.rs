@@ -1,6 +1,6 @@
 //! Create flat if-else chain
 //!
-//! # Explaination
+//! # Explanation
 //!
 //! In Scratch you'd sometimes normally create multiple if-else a little like this:
 //! ```txt
@@ -31,17 +31,17 @@
 //! Without macro:
 //! ```
 //! # use sb_scratchy::blocks::{move_steps, turn_left, say, equals};
-//! # use sb_scratchy::if_else_chain::if_;
+//! # use sb_scratchy::scripting::if_else_chain::if_;
 //! // This uses the shortcut
-//! if_(equals(1, 0), Some(
+//! if_(equals(1, 0),
 //!     move_steps(10)
-//! ))
-//! .else_if(equals(1, 1), Some(
+//! )
+//! .else_if(equals(1, 1),
 //!     turn_left(20)
-//! ))
-//! .else_(Some(
+//! )
+//! .else_(
 //!     say("wassup")
-//! ));
+//! );
 //! ```
 
 use std::marker::PhantomData;
@@ -178,7 +178,7 @@ impl<S> IfElseChainBuilder<S> {
                 }
             }
         };
-        unsafe { TypedStackBuilder::assume_typed(b) }
+        shaped(b)
     }
 }
 
@@ -187,3 +187,11 @@ impl<S> IntoInput<Stack> for IfElseChainBuilder<S> {
         BlockInputBuilder::stack(self.end().into_untyped())
     }
 }
+
+impl<S> IntoStack for IfElseChainBuilder<S> {
+    type End = StackableSide;
+
+    fn into_stack(self) -> StackBlock {
+        self.end()
+    }
+}
@@ -0,0 +1,326 @@
+//! Local variables of custom blocks
+//!
+//! # Explanation
+//!
+//! Variables in Scratch are shared by every call of a custom block,
+//! so a custom block that calls itself overwrites the variables of the call that's still running.
+//!
+//! Locals are kept in a list used as a call stack instead.
+//! Running the definition adds a frame with all of its locals to the end of the list
+//! and the frame is removed when it's done.
+//! Locals are found counting from the end of the list, so the frame of the body that's running
+//! has to be at the end of the list.
+//! That's why custom blocks with locals must run without screen refresh:
+//! no other script runs until it's done, so the only frames added after it
+//! are from custom blocks it calls which remove their frames before they're done.
+//! For the same reason the body shouldn't wait, e.g. with `wait` or `ask and wait`,
+//! and shouldn't run for so long that Scratch redraws the screen anyway (about half a second).
+//!
+//! # Usage
+//!
+//! ```
+//! # use sb_scratchy::blocks::{greater_than, if_else, mul, sub};
+//! # use sb_scratchy::project::{SpriteBuilder, TargetBuilder};
+//! # use sb_scratchy::scripting::arg::{Number, Reporter, Value};
+//! # use sb_scratchy::scripting::custom_block::CustomBlockBuilder;
+//! let mut cat = SpriteBuilder::new(TargetBuilder::new("Cat"));
+//! let call_stack = cat.declare_call_stack();
+//! let factorial = cat.declare_function::<_, Number>(
+//!     CustomBlockBuilder::new()
+//!         .text("factorial")
+//!         .arg::<Value>("n")
+//!         .warp(true),
+//! );
+//!
+//! let definition = call_stack.frame(|mut frame| {
+//!     // arguments are values, n is used as a number
+//!     let n = frame.declare_local::<Number>(Reporter::new(factorial.arg_string_number("n").0));
+//!     frame.define(factorial.custom_block(), |frame| {
+//!         let (call, ret) = factorial.call((sub(frame.get(&n), 1),));
+//!         if_else(
+//!             greater_than(frame.get(&n), 1),
+//!             // n is still n of this call after calling itself
+//!             call.next(frame.set(&n, mul(frame.get(&n), ret))),
+//!             frame.set(&n, 1),
+//!         )
+//!         .next(frame.return_(&factorial, frame.get(&n)))
+//!     })
+//! });
+//!
+//! let cat = cat.add_script(definition);
+//! ```
+//!
+//! Locals can only be used in the body of the frame that declared them:
+//! ```compile_fail
+//! # use sb_scratchy::project::{SpriteBuilder, TargetBuilder};
+//! # use sb_scratchy::scripting::arg::Number;
+//! # use sb_scratchy::scripting::custom_block::CustomBlockBuilder;
+//! let mut cat = SpriteBuilder::new(TargetBuilder::new("Cat"));
+//! let call_stack = cat.declare_call_stack();
+//! let jump = CustomBlockBuilder::new().text("jump").warp(true).build();
+//! let spin = CustomBlockBuilder::new().text("spin").warp(true).build();
+//!
+//! call_stack.frame(|mut jump_frame| {
+//!     let height = jump_frame.declare_local::<Number>(10);
+//!     call_stack.frame(|spin_frame| {
+//!         spin_frame.define(&spin, |frame| frame.set(&height, 0)) // height is a local of jump
+//!     });
+//!     jump_frame.define(&jump, |frame| frame.set(&height, 0))
+//! });
+//! ```
+
+use std::marker::PhantomData;
+
+use crate::blocks::{
+    add, add_to_list, delete_in_list, item_in_list, length_of_list, replace_in_list, set_var_to,
+    stop, sub,
+};
+use crate::scripting::{
+    arg::*,
+    custom_block::{CustomBlock, Function},
+    menu::StopOption,
+    stack::*,
+};
+
+/// List that keeps locals of custom blocks.
+///
+/// Created by [`crate::project::SpriteBuilder::declare_call_stack`].
+/// One is enough for every custom block of the sprite.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallStack {
+    list: ListHandle<Value>,
+}
+
+impl CallStack {
+    pub(crate) fn new(list: ListHandle<Value>) -> CallStack {
+        CallStack { list }
+    }
+
+    /// List the frames are kept in
+    pub fn list(&self) -> &ListHandle<Value> {
+        &self.list
+    }
+
+    /// Declare locals of a custom block then define it in `build`.
+    ///
+    /// Locals declared in `build` can't be used outside of it,
+    /// so they can't be used by the body of another custom block.
+    pub fn frame<R>(&self, build: impl for<'f> FnOnce(FrameBuilder<'f>) -> R) -> R {
+        build(FrameBuilder {
+            list: self.list.clone(),
+            pushes: vec![],
+            brand: PhantomData,
+        })
+    }
+}
+
+/// Invariant lifetime that is different for each frame
+type Brand<'f> = PhantomData<fn(&'f ()) -> &'f ()>;
+
+/// Handle to a local declared by [`FrameBuilder::declare_local`].
+///
+/// Use it with [`Frame`] of the same custom block.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Local<'f, T> {
+    slot: usize,
+    brand: Brand<'f>,
+    marker: PhantomData<T>,
+}
+
+/// Builder to declare locals of a custom block then define it.
+///
+/// Given by [`CallStack::frame`], see top module documentation for usage.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrameBuilder<'f> {
+    list: ListHandle<Value>,
+    /// Blocks that add each local to the frame
+    pushes: Vec<StackBlock>,
+    brand: Brand<'f>,
+}
+
+impl<'f> FrameBuilder<'f> {
+    /// Add a local and get the handle to use it in the body.
+    /// `initial` is reported each time the custom block runs.
+    pub fn declare_local<T>(&mut self, initial: impl IntoInput<T>) -> Local<'f, T> {
+        self.pushes
+            .push(add_to_list(&self.list.retype::<T>(), initial));
+        Local {
+            slot: self.pushes.len() - 1,
+            brand: PhantomData,
+            marker: PhantomData,
+        }
+    }
+
+    /// Define `custom_block` with the body, the definition is the whole script.
+    ///
+    /// The frame is added before the body and removed after it.
+    /// To leave the body early, use [`Frame::stop`] or [`Frame::return_`] which also remove it.
+    /// The body can only end with those if it can't go on after its last block.
+    ///
+    /// # Panics
+    /// If `custom_block` doesn't run without screen refresh, see top module documentation for why.
+    pub fn define<A, E: FrameEnd>(
+        self,
+        custom_block: &CustomBlock<A>,
+        body: impl FnOnce(&Frame<'f>) -> TypedStackBuilder<StackableSide, E>,
+    ) -> TypedStackBuilder<UnstackableSide, E::End> {
+        assert!(
+            custom_block.is_warp(),
+            "custom block with locals must run without screen refresh"
+        );
+        let FrameBuilder { list, pushes, .. } = self;
+        let frame = Frame {
+            list,
+            size: pushes.len(),
+            brand: PhantomData,
+        };
+        let body = E::end_frame(body(&frame), frame.pop());
+        let mut stack = custom_block.define();
+        for push in pushes {
+            stack = stack.next(push);
+        }
+        stack.next(body)
+    }
+}
+
+/// Locals of a custom block while defining its body.
+///
+/// Given by [`FrameBuilder::define`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame<'f> {
+    list: ListHandle<Value>,
+    size: usize,
+    brand: Brand<'f>,
+}
+
+impl<'f> Frame<'f> {
+    /// Report the local
+    pub fn get<T>(&self, local: &Local<'f, T>) -> JustReporter<T> {
+        item_in_list(&self.list_of(), self.index(local))
+    }
+
+    /// Set the local to `value`
+    pub fn set<T>(&self, local: &Local<'f, T>, value: impl IntoInput<T>) -> StackBlock {
+        replace_in_list(&self.list_of(), self.index(local), value)
+    }
+
+    /// Change the local by `by`
+    pub fn change(&self, local: &Local<'f, Number>, by: impl IntoInput<Number>) -> StackBlock {
+        self.set(local, add(self.get(local), by))
+    }
+
+    /// Remove the frame and stop the custom block.
+    ///
+    /// Only use this in the body, it's `stop "this script"` which stops the whole script outside.
+    pub fn stop(&self) -> TypedStackBuilder<StackableSide, FrameExit> {
+        let stop: CapBlock = match self.pop() {
+            Some(pop) => pop.next(stop(StopOption::ThisScript)),
+            None => stop(StopOption::ThisScript),
+        };
+        stop.retype_end()
+    }
+
+    /// Return `value` from `function`, removing the frame.
+    ///
+    /// The value is reported before the frame is removed so it can use the locals.
+    /// See [`Function::return_`].
+    pub fn return_<A, T>(
+        &self,
+        function: &Function<A, T>,
+        value: impl IntoInput<T>,
+    ) -> TypedStackBuilder<StackableSide, FrameExit> {
+        set_var_to(function.return_var(), value).next(self.stop())
+    }
+
+    /// Same list typed as items of the local
+    fn list_of<T>(&self) -> ListHandle<T> {
+        self.list.retype()
+    }
+
+    /// Index of the local in the list, counting from the end as the frame is always at the end
+    fn index<T>(&self, local: &Local<'f, T>) -> JustReporter<Integer> {
+        let from_end = self.size - 1 - local.slot;
+        if from_end == 0 {
+            length_of_list(&self.list)
+        } else {
+            // integers are numbers and subtracting them is still an integer
+            let length: JustReporter<Number> = Reporter::new(length_of_list(&self.list).0);
+            Reporter::new(sub(length, from_end).0)
+        }
+    }
+
+    /// Blocks that remove the frame
+    fn pop(&self) -> Option<StackBlock> {
+        (0..self.size)
+            .map(|_| delete_in_list(&self.list, length_of_list(&self.list)))
+            .reduce(StackBlock::next)
+    }
+}
+
+/// End of [`Frame::stop`] and [`Frame::return_`], nothing can be stacked below them.
+///
+/// Only these can end a body with locals that can't go on after its last block,
+/// a plain `stop "this script"` or [`Function::return_`] would leave the frame in the list.
+///
+/// ```compile_fail
+/// # use sb_scratchy::project::{SpriteBuilder, TargetBuilder};
+/// # use sb_scratchy::scripting::arg::Number;
+/// # use sb_scratchy::scripting::custom_block::CustomBlockBuilder;
+/// let mut cat = SpriteBuilder::new(TargetBuilder::new("Cat"));
+/// let call_stack = cat.declare_call_stack();
+/// let one = cat.declare_function::<_, Number>(CustomBlockBuilder::new().text("one").warp(true));
+///
+/// call_stack.frame(|frame| frame.define(one.custom_block(), |_| one.return_(1)));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameExit;
+
+impl UnstackableEnd for FrameExit {}
+
+mod sealed {
+    pub trait Sealed {}
+
+    impl Sealed for super::StackableSide {}
+    impl Sealed for super::FrameExit {}
+}
+
+/// Bottom of a body with locals.
+///
+/// If the body can go on after its last block ([`StackableSide`]), the frame is removed after it.
+/// Otherwise it ends with [`Frame::stop`] or [`Frame::return_`] ([`FrameExit`]) that already remove the frame.
+pub trait FrameEnd: sealed::Sealed + Sized {
+    /// Bottom of the definition
+    #[doc(hidden)]
+    type End;
+
+    #[doc(hidden)]
+    fn end_frame(
+        body: TypedStackBuilder<StackableSide, Self>,
+        pop: Option<StackBlock>,
+    ) -> TypedStackBuilder<StackableSide, Self::End>;
+}
+
+impl FrameEnd for StackableSide {
+    type End = StackableSide;
+
+    fn end_frame(body: StackBlock, pop: Option<StackBlock>) -> StackBlock {
+        match pop {
+            Some(pop) => body.next(pop),
+            None => body,
+        }
+    }
+}
+
+impl FrameEnd for FrameExit {
+    type End = UnstackableSide;
+
+    fn end_frame(
+        body: TypedStackBuilder<StackableSide, FrameExit>,
+        _pop: Option<StackBlock>,
+    ) -> CapBlock {
+        body.retype_end()
+    }
+}
+
+#[cfg(test)]
+mod tests;
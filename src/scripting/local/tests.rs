@@ -0,0 +1,134 @@
+//! Running custom blocks with locals in the interpreter
+
+use super::*;
+use crate::blocks::{
+    change_var_by, greater_than, if_, if_else, less_than, mul, repeat, when_flag_clicked,
+};
+use crate::interpreter::{start, Interpreter};
+use crate::project::{SpriteBuilder, StageBuilder, TargetBuilder};
+use crate::scripting::custom_block::CustomBlockBuilder;
+
+fn run(cat: SpriteBuilder) -> Interpreter {
+    let mut interpreter = start(StageBuilder::default(), cat);
+    assert!(interpreter.run_until_idle(100));
+    interpreter
+}
+
+fn number(interpreter: &Interpreter, name: &str) -> f64 {
+    interpreter.variable("Cat", name).unwrap().to_number()
+}
+
+fn call_stack_len(interpreter: &Interpreter) -> usize {
+    interpreter.list("Cat", "(call stack)").unwrap().len()
+}
+
+#[test]
+fn factorial() {
+    // same as the example in the module documentation
+    let mut cat = SpriteBuilder::new(TargetBuilder::new("Cat"));
+    let out = cat.declare_var::<Number>("out", 0.);
+    let call_stack = cat.declare_call_stack();
+    let factorial = cat.declare_function::<_, Number>(
+        CustomBlockBuilder::new()
+            .text("factorial")
+            .arg::<Value>("n")
+            .warp(true),
+    );
+    let definition = call_stack.frame(|mut frame| {
+        let n = frame.declare_local::<Number>(Reporter::new(factorial.arg_string_number("n").0));
+        frame.define(factorial.custom_block(), |frame| {
+            let (call, ret) = factorial.call((sub(frame.get(&n), 1),));
+            if_else(
+                greater_than(frame.get(&n), 1),
+                call.next(frame.set(&n, mul(frame.get(&n), ret))),
+                frame.set(&n, 1),
+            )
+            .next(frame.return_(&factorial, frame.get(&n)))
+        })
+    });
+    let (call, ret) = factorial.call((10,));
+    let cat = cat
+        .add_script(definition)
+        .add_script(when_flag_clicked().next(call).next(set_var_to(&out, ret)));
+    let interpreter = run(cat);
+    assert_eq!(number(&interpreter, "out"), 3628800.);
+    assert_eq!(call_stack_len(&interpreter), 0);
+}
+
+#[test]
+fn fibonacci_with_early_return() {
+    let mut cat = SpriteBuilder::new(TargetBuilder::new("Cat"));
+    let out = cat.declare_var::<Number>("out", 0.);
+    let call_stack = cat.declare_call_stack();
+    let fib = cat.declare_function::<_, Number>(
+        CustomBlockBuilder::new()
+            .text("fib")
+            .arg::<Value>("n")
+            .warp(true),
+    );
+    let definition = call_stack.frame(|mut frame| {
+        let sum = frame.declare_local::<Number>(0);
+        let n = frame.declare_local::<Number>(Reporter::new(fib.arg_string_number("n").0));
+        frame.define(fib.custom_block(), |frame| {
+            let (call_1, ret_1) = fib.call((sub(frame.get(&n), 1),));
+            let (call_2, ret_2) = fib.call((sub(frame.get(&n), 2),));
+            if_(
+                less_than(frame.get(&n), 2),
+                frame.return_(&fib, frame.get(&n)),
+            )
+            .next(call_1)
+            .next(frame.set(&sum, ret_1))
+            .next(call_2)
+            .next(frame.change(&sum, ret_2))
+            .next(frame.return_(&fib, frame.get(&sum)))
+        })
+    });
+    let (call, ret) = fib.call((15,));
+    let cat = cat
+        .add_script(definition)
+        .add_script(when_flag_clicked().next(call).next(set_var_to(&out, ret)));
+    let interpreter = run(cat);
+    assert_eq!(number(&interpreter, "out"), 610.);
+    assert_eq!(call_stack_len(&interpreter), 0);
+}
+
+#[test]
+fn frame_is_removed_after_body() {
+    let mut cat = SpriteBuilder::new(TargetBuilder::new("Cat"));
+    let out = cat.declare_var::<Number>("out", 0.);
+    let call_stack = cat.declare_call_stack();
+    let count = CustomBlockBuilder::new()
+        .text("count to")
+        .arg::<Value>("n")
+        .warp(true)
+        .build();
+    let definition = call_stack.frame(|mut frame| {
+        let i = frame.declare_local::<Number>(0);
+        let unused = frame.declare_local::<Value>("unused");
+        frame.define(&count, |frame| {
+            repeat(
+                Reporter::new(count.arg_string_number("n").0),
+                Some(frame.change(&i, 1)),
+            )
+            .next(frame.set(&unused, 1))
+            .next(change_var_by(&out, frame.get(&i)))
+        })
+    });
+    let cat = cat.add_script(definition).add_script(
+        when_flag_clicked()
+            .next(count.call((3,)))
+            .next(count.call((4,))),
+    );
+    let interpreter = run(cat);
+    assert_eq!(number(&interpreter, "out"), 7.);
+    assert_eq!(call_stack_len(&interpreter), 0);
+}
+
+#[test]
+#[should_panic(expected = "without screen refresh")]
+fn locals_need_warp() {
+    let mut cat = SpriteBuilder::new(TargetBuilder::new("Cat"));
+    let call_stack = cat.declare_call_stack();
+    let jump = CustomBlockBuilder::new().text("jump").build();
+    call_stack.frame(|frame| frame.define(&jump, |frame| frame.stop()));
+}
@@ -0,0 +1,280 @@
+//! Loops that Scratch doesn't have a block for
+//!
+//! # Usage
+//!
+//! ```
+//! # use sb_scratchy::blocks::{mouse_down, move_steps, turn_right, var};
+//! # use sb_scratchy::project::{SpriteBuilder, TargetBuilder};
+//! # use sb_scratchy::scripting::arg::Number;
+//! # use sb_scratchy::scripting::loops::{for_range, while_};
+//! # let mut sprite = SpriteBuilder::new(TargetBuilder::new("Cat"));
+//! let i = sprite.declare_var::<Number>("i", 0.);
+//!
+//! while_(mouse_down(), Some(turn_right(15)));
+//!
+//! // Count 10, 8, 6, 4, 2
+//! for_range(&i, 10, 0, -2, Some(move_steps(var(&i))));
+//! ```
+//!
+//! # Break and continue
+//!
+//! Loops that start with `breakable_` give their body as [`LoopBody`] which can break or continue the loop.
+//! They need a variable to keep track of it, the guard.
+//! One guard can be used by every breakable loop of a script even when they're inside each other,
+//! but each script that can run at the same time as another needs its own guard:
+//! loops wait for the next frame at the end of each time they repeat,
+//! so another script could reset the guard before the loop sees that it should break.
+//! A guard that is a variable of a sprite is fine for scripts that only differ by which clone runs them
+//! as each clone has its own copy.
+//!
+//! ```
+//! # use sb_scratchy::blocks::{key_pressed, mouse_down, move_steps, say, turn_right};
+//! # use sb_scratchy::project::{SpriteBuilder, TargetBuilder};
+//! # use sb_scratchy::scripting::arg::Value;
+//! # use sb_scratchy::scripting::loops::breakable_forever;
+//! # use sb_scratchy::scripting::menu::Key;
+//! # let mut sprite = SpriteBuilder::new(TargetBuilder::new("Cat"));
+//! let guard = sprite.declare_var::<Value>("loop guard", "");
+//!
+//! breakable_forever(&guard, |body| {
+//!     body.if_(key_pressed(Key::Space), |body| body.break_())
+//!         .if_(mouse_down(), |body| body.then(turn_right(15)).continue_())
+//!         .then(move_steps(10))
+//! })
+//! .next(say("done!"));
+//! ```
+//!
+//! Markers can only be used inside a loop and always break/continue the loop that gives the body:
+//! ```compile_fail
+//! # use sb_scratchy::project::{SpriteBuilder, TargetBuilder};
+//! # use sb_scratchy::scripting::arg::Value;
+//! # use sb_scratchy::scripting::loops::breakable_forever;
+//! # let mut sprite = SpriteBuilder::new(TargetBuilder::new("Cat"));
+//! let guard = sprite.declare_var::<Value>("loop guard", "");
+//!
+//! breakable_forever(&guard, |outer| {
+//!     let inner = breakable_forever(&guard, |_inner| outer.break_()); // outer body of the inner loop
+//!     unreachable!()
+//! });
+//! ```
+
+use std::marker::PhantomData;
+
+use crate::blocks::{
+    add, change_var_by, equals, greater_than, if_, less_than, mul, not, or, repeat_until,
+    set_var_to, sub, var,
+};
+use crate::scripting::{arg::*, stack::*};
+use sb_itchy::blocks::{self, if_ as if_block, if_else as if_else_block};
+
+/// Value of the guard variable after [`LoopBody::break_`]
+const BREAK: &str = "break";
+/// Value of the guard variable after [`LoopBody::continue_`]
+const CONTINUE: &str = "continue";
+
+/// Repeat while `cond` is true.
+///
+/// This is `repeat until <not <cond>>` in Scratch.
+pub fn while_(cond: impl IntoInput<Bool>, body: Option<impl IntoStack>) -> StackBlock {
+    repeat_until(not(cond), body)
+}
+
+/// Repeat with `counter` counting from `start` by `step` for as long as it hasn't reached `end`.
+///
+/// Like `start..end` in Rust, `end` is excluded. `step` can be negative to count down.
+/// `end` and `step` are reported every time it loops, use a variable for them
+/// if they can change inside the loop and you don't want that.
+///
+/// `counter` is set to `start - step` and changed by `step` before the body,
+/// so the body can end with a block that nothing can be put below, like `stop`.
+/// After the loop it's the last value the body had.
+pub fn for_range(
+    counter: &VarHandle<Number>,
+    start: impl IntoInput<Number>,
+    end: impl IntoInput<Number>,
+    step: impl IntoInput<Number> + Clone,
+    body: Option<impl IntoStack>,
+) -> StackBlock {
+    // (end - next) * step > 0 works for both direction of step
+    let next = add(var(counter), step.clone());
+    let cond = greater_than(mul(sub(end, next), step.clone()), 0);
+    let change = change_var_by(counter, step.clone());
+    let body = body.map(|body| change.clone().next(body.into_stack()));
+    let start = set_var_to(counter, sub(start, step));
+    match body {
+        Some(body) => start.next(while_(cond, Some(body))),
+        None => start.next(while_(cond, Some(change))),
+    }
+}
+
+/// Body of a breakable loop.
+///
+/// This is only given inside breakable loops like [`breakable_repeat`]
+/// so breaking or continuing outside of a loop doesn't compile.
+/// `'a` is different for each loop so a body can't be used in a loop inside it.
+///
+/// Blocks after something that can break or continue are put in `if <(guard) = ()>`
+/// so they're skipped when it does.
+#[derive(Debug, PartialEq)]
+pub struct LoopBody<'a> {
+    guard: VarHandle<Value>,
+    /// Each block and whether it can break or continue
+    stmts: Vec<(StackBlock, bool)>,
+    /// Invariant so `'a` can't be changed to the lifetime of another loop
+    loop_: PhantomData<fn(&'a ()) -> &'a ()>,
+}
+
+impl<'a> LoopBody<'a> {
+    fn new(guard: &VarHandle<Value>) -> LoopBody<'a> {
+        LoopBody {
+            guard: guard.clone(),
+            stmts: vec![],
+            loop_: PhantomData,
+        }
+    }
+
+    /// Add blocks to the body
+    pub fn then(mut self, stack: StackBlock) -> LoopBody<'a> {
+        self.stmts.push((stack, false));
+        self
+    }
+
+    /// Stop the loop. Nothing after this in the body runs.
+    pub fn break_(self) -> LoopBody<'a> {
+        self.jump(BREAK)
+    }
+
+    /// Skip to the next time the loop repeats. Nothing after this in the body runs.
+    pub fn continue_(self) -> LoopBody<'a> {
+        self.jump(CONTINUE)
+    }
+
+    /// If that can break or continue inside
+    pub fn if_(
+        mut self,
+        cond: impl IntoInput<Bool>,
+        then: impl FnOnce(LoopBody<'a>) -> LoopBody<'a>,
+    ) -> LoopBody<'a> {
+        let (then, jumps) = then(LoopBody::new(&self.guard)).build();
+        let b = shaped(if_block(cond.into_input(), then.map(IntoInput::into_input)));
+        self.stmts.push((b, jumps));
+        self
+    }
+
+    /// If else that can break or continue inside
+    pub fn if_else(
+        mut self,
+        cond: impl IntoInput<Bool>,
+        then: impl FnOnce(LoopBody<'a>) -> LoopBody<'a>,
+        else_: impl FnOnce(LoopBody<'a>) -> LoopBody<'a>,
+    ) -> LoopBody<'a> {
+        let (then, then_jumps) = then(LoopBody::new(&self.guard)).build();
+        let (else_, else_jumps) = else_(LoopBody::new(&self.guard)).build();
+        let b = shaped(if_else_block(
+            cond.into_input(),
+            then.map(IntoInput::into_input),
+            else_.map(IntoInput::into_input),
+        ));
+        self.stmts.push((b, then_jumps || else_jumps));
+        self
+    }
+
+    fn jump(mut self, to: &str) -> LoopBody<'a> {
+        let b = set_var_to(&self.guard, to);
+        self.stmts.push((b, true));
+        self
+    }
+
+    /// Blocks of the body and whether it can break or continue
+    fn build(self) -> (Option<StackBlock>, bool) {
+        let LoopBody { guard, stmts, .. } = self;
+        let jumps = stmts.iter().any(|(_, jumps)| *jumps);
+        // building from the end so that everything after a jump can be put in a guard
+        let mut rest: Option<StackBlock> = None;
+        for (stmt, jumps) in stmts.into_iter().rev() {
+            if jumps {
+                rest = rest.map(|rest| if_(equals(var(&guard), ""), rest));
+            }
+            rest = Some(match rest {
+                Some(rest) => stmt.next(rest),
+                None => stmt,
+            });
+        }
+        (rest, jumps)
+    }
+}
+
+/// Reset guard so the body runs
+fn reset(guard: &VarHandle<Value>) -> StackBlock {
+    set_var_to(guard, "")
+}
+
+/// Blocks to run every time a breakable loop repeats
+fn iteration(
+    guard: &VarHandle<Value>,
+    body: impl for<'a> FnOnce(LoopBody<'a>) -> LoopBody<'a>,
+) -> StackBlock {
+    match body(LoopBody::new(guard)).build() {
+        (Some(body), _) => reset(guard).next(body),
+        (None, _) => reset(guard),
+    }
+}
+
+/// [`crate::blocks::repeat`] that can break or continue. See top module documentation for usage.
+///
+/// `counter` keeps how many times are left so it stops right after breaking.
+/// Each breakable repeat needs its own counter, even the ones inside each other.
+pub fn breakable_repeat(
+    guard: &VarHandle<Value>,
+    counter: &VarHandle<Number>,
+    times: impl IntoInput<PositiveInteger>,
+    body: impl for<'a> FnOnce(LoopBody<'a>) -> LoopBody<'a>,
+) -> StackBlock {
+    // times is rounded once before it starts like repeat does
+    let times: JustReporter<Number> = shaped(blocks::round(
+        times.into_input_over(<Number as ShadowInput>::shadow("")),
+    ));
+    let cond = or(equals(var(guard), BREAK), less_than(var(counter), 1));
+    let iteration = change_var_by(counter, -1).next(iteration(guard, body));
+    reset(guard)
+        .next(set_var_to(counter, times))
+        .next(repeat_until(cond, Some(iteration)))
+        .next(reset(guard))
+}
+
+/// [`crate::blocks::repeat_until`] that can break or continue. See top module documentation for usage.
+pub fn breakable_repeat_until(
+    guard: &VarHandle<Value>,
+    cond: impl IntoInput<Bool>,
+    body: impl for<'a> FnOnce(LoopBody<'a>) -> LoopBody<'a>,
+) -> StackBlock {
+    let cond = or(cond, equals(var(guard), BREAK));
+    reset(guard)
+        .next(repeat_until(cond, Some(iteration(guard, body))))
+        .next(reset(guard))
+}
+
+/// [`while_`] that can break or continue. See top module documentation for usage.
+pub fn breakable_while(
+    guard: &VarHandle<Value>,
+    cond: impl IntoInput<Bool>,
+    body: impl for<'a> FnOnce(LoopBody<'a>) -> LoopBody<'a>,
+) -> StackBlock {
+    breakable_repeat_until(guard, not(cond), body)
+}
+
+/// [`crate::blocks::forever`] that can break or continue. See top module documentation for usage.
+///
+/// Unlike forever, this can have blocks below it as it stops when it breaks.
+pub fn breakable_forever(
+    guard: &VarHandle<Value>,
+    body: impl for<'a> FnOnce(LoopBody<'a>) -> LoopBody<'a>,
+) -> StackBlock {
+    let cond = equals(var(guard), BREAK);
+    reset(guard)
+        .next(repeat_until(cond, Some(iteration(guard, body))))
+        .next(reset(guard))
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,183 @@
+//! Running breakable loops in the interpreter
+
+use super::*;
+use crate::blocks::{add, modulo, when_flag_clicked};
+use crate::interpreter::{start, Interpreter};
+use crate::project::{SpriteBuilder, StageBuilder, TargetBuilder};
+
+/// Variables of a sprite `"Cat"` used by the loops
+struct Vars {
+    guard: VarHandle<Value>,
+    counter: VarHandle<Number>,
+    inner_counter: VarHandle<Number>,
+    i: VarHandle<Number>,
+    j: VarHandle<Number>,
+    sum: VarHandle<Number>,
+}
+
+/// Run `script` until it's done and get the interpreter to read variables of `"Cat"` from
+fn run(script: impl FnOnce(&Vars) -> StackBlock) -> Interpreter {
+    let mut cat = SpriteBuilder::new(TargetBuilder::new("Cat"));
+    let vars = Vars {
+        guard: cat.declare_var("guard", ""),
+        counter: cat.declare_var("counter", 0.),
+        inner_counter: cat.declare_var("inner counter", 0.),
+        i: cat.declare_var("i", 0.),
+        j: cat.declare_var("j", 0.),
+        sum: cat.declare_var("sum", 0.),
+    };
+    let cat = cat.add_script(when_flag_clicked().next(script(&vars)));
+    let mut interpreter = start(StageBuilder::default(), cat);
+    assert!(interpreter.run_until_idle(1000), "loop didn't stop");
+    interpreter
+}
+
+fn number(interpreter: &Interpreter, name: &str) -> f64 {
+    interpreter.variable("Cat", name).unwrap().to_number()
+}
+
+#[test]
+fn repeat_stops_right_after_break() {
+    let interpreter = run(|v| {
+        breakable_repeat(&v.guard, &v.counter, 1_000_000u32, |body| {
+            body.then(change_var_by(&v.i, 1))
+                .if_(equals(var(&v.i), 3), |body| body.break_())
+                .then(change_var_by(&v.sum, 1))
+        })
+    });
+    assert_eq!(number(&interpreter, "i"), 3.);
+    assert_eq!(number(&interpreter, "sum"), 2.);
+    // a frame for each time it repeated and one more to see that it broke
+    assert_eq!(interpreter.frame(), 4);
+    assert_eq!(interpreter.variable("Cat", "guard").unwrap().to_text(), "");
+}
+
+#[test]
+fn repeat_rounds_times() {
+    for (times, expected) in [(0., 0.), (2.4, 2.), (2.5, 3.), (3., 3.)] {
+        let interpreter = run(|v| {
+            let times: JustReporter<PositiveInteger> = Reporter::new(add(var(&v.sum), times).0);
+            breakable_repeat(&v.guard, &v.counter, times, |body| {
+                body.then(change_var_by(&v.i, 1))
+            })
+        });
+        assert_eq!(number(&interpreter, "i"), expected, "repeat {}", times);
+    }
+}
+
+#[test]
+fn continue_skips_rest_of_body() {
+    let interpreter = run(|v| {
+        breakable_repeat(&v.guard, &v.counter, 10u32, |body| {
+            body.then(change_var_by(&v.i, 1))
+                .if_(equals(modulo(var(&v.i), 2), 0), |body| body.continue_())
+                .then(change_var_by(&v.sum, var(&v.i)))
+        })
+    });
+    assert_eq!(number(&interpreter, "i"), 10.);
+    assert_eq!(number(&interpreter, "sum"), 25.);
+}
+
+#[test]
+fn nested_loops_share_guard() {
+    let interpreter = run(|v| {
+        breakable_forever(&v.guard, |outer| {
+            outer
+                .then(change_var_by(&v.i, 1))
+                .if_else(
+                    greater_than(var(&v.i), 4),
+                    |body| body.break_(),
+                    |body| body.then(set_var_to(&v.j, 0)),
+                )
+                .then(breakable_repeat(
+                    &v.guard,
+                    &v.inner_counter,
+                    10u32,
+                    |inner| {
+                        inner
+                            .then(change_var_by(&v.j, 1))
+                            .if_(greater_than(var(&v.j), 2), |body| body.break_())
+                            .then(change_var_by(&v.sum, 1))
+                    },
+                ))
+                // the inner break only stops the inner loop
+                .then(change_var_by(&v.sum, 100))
+        })
+        .next(breakable_while(
+            &v.guard,
+            less_than(var(&v.j), 10),
+            |body| body.then(change_var_by(&v.j, 1)),
+        ))
+    });
+    assert_eq!(number(&interpreter, "i"), 5.);
+    assert_eq!(number(&interpreter, "sum"), 4. * 102.);
+    assert_eq!(number(&interpreter, "j"), 10.);
+}
+
+#[test]
+fn while_repeats_while_true() {
+    let interpreter = run(|v| while_(less_than(var(&v.i), 5), Some(change_var_by(&v.i, 2))));
+    assert_eq!(number(&interpreter, "i"), 6.);
+}
+
+#[test]
+fn for_range_excludes_end() {
+    for (start, end, step, count, last) in [
+        (0., 5., 1., 5., 4.),
+        (1., 10., 3., 3., 7.),
+        (5., 0., -2., 3., 1.),
+        (3., 3., 1., 0., 0.),
+    ] {
+        let interpreter = run(|v| {
+            for_range(
+                &v.counter,
+                start,
+                end,
+                step,
+                Some(change_var_by(&v.sum, 1).next(set_var_to(&v.i, var(&v.counter)))),
+            )
+        });
+        let case = format!("for {start}..{end} by {step}");
+        assert_eq!(number(&interpreter, "sum"), count, "{case}");
+        assert_eq!(number(&interpreter, "i"), last, "{case}");
+    }
+}
+
+#[test]
+fn for_range_takes_any_body() {
+    let interpreter = run(|v| {
+        for_range(
+            &v.counter,
+            0,
+            5,
+            1,
+            Some(
+                crate::scripting::switch::switch_(var(&v.counter))
+                    .case(3, change_var_by(&v.sum, 10))
+                    .default(change_var_by(&v.sum, 1)),
+            ),
+        )
+    });
+    assert_eq!(number(&interpreter, "sum"), 14.);
+    assert_eq!(number(&interpreter, "counter"), 4.);
+}
+
+#[test]
+fn blocks_after_jump_are_guarded() {
+    let guard = VarHandle::<Value>::new("guard".to_owned(), Scope::Sprite);
+    let i = VarHandle::<Number>::new("i".to_owned(), Scope::Sprite);
+    let (body, jumps) = LoopBody::new(&guard)
+        .then(change_var_by(&i, 1))
+        .break_()
+        .then(change_var_by(&i, 2))
+        .build();
+    assert!(jumps);
+    let expected = change_var_by(&i, 1)
+        .next(set_var_to(&guard, BREAK))
+        .next(if_(equals(var(&guard), ""), change_var_by(&i, 2)));
+    assert_eq!(body, Some(expected));
+
+    let (body, jumps) = LoopBody::new(&guard).then(change_var_by(&i, 1)).build();
+    assert!(!jumps);
+    assert_eq!(body, Some(change_var_by(&i, 1)));
+}
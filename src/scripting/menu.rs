@@ -0,0 +1,378 @@
+//! Typed options for block menus and fields
+//!
+//! Each enum here is accepted by the block that has that menu, e.g. [`LooksEffect`] for
+//! [`crate::blocks::change_looks_effect_by`].
+//!
+//! If you really need to put something else in, wrap the string in [`RawField`]:
+//! ```
+//! # use sb_scratchy::blocks::change_looks_effect_by;
+//! # use sb_scratchy::scripting::{arg::RawField, menu::LooksEffect};
+//! change_looks_effect_by(LooksEffect::Ghost, 10);
+//! change_looks_effect_by(RawField("GHOST"), 10);
+//! ```
+
+use sb_itchy::block::BlockFieldBuilder;
+
+use crate::scripting::arg::{IntoField, RawField};
+
+macro_rules! menu_option {
+    ( $(
+        $(#[$attributes:meta])*
+        $name:ident {
+            $(
+                $(#[$variant_attributes:meta])*
+                $variant:ident => $value:literal
+            ),* $(,)?
+        }
+    )* ) => {
+        $(
+            $(#[$attributes])*
+            #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+            pub enum $name {
+                $(
+                    $(#[$variant_attributes])*
+                    $variant
+                ),*
+            }
+
+            impl $name {
+                /// Value of this option as Scratch stores it
+                pub fn as_str(&self) -> &'static str {
+                    match self {
+                        $($name::$variant => $value),*
+                    }
+                }
+
+                /// Parse the value Scratch stores
+                pub fn parse(s: &str) -> Option<Self> {
+                    match s {
+                        $($value => Some($name::$variant),)*
+                        _ => None,
+                    }
+                }
+            }
+
+            impl IntoField<$name> for $name {
+                fn into_field(self) -> BlockFieldBuilder {
+                    BlockFieldBuilder::new(self.as_str().to_owned())
+                }
+            }
+
+            impl<S: Into<String>> IntoField<$name> for RawField<S> {
+                fn into_field(self) -> BlockFieldBuilder {
+                    BlockFieldBuilder::new(self.0.into())
+                }
+            }
+        )*
+    };
+}
+
+menu_option! {
+    /// Option for [`crate::blocks::stop`].
+    ///
+    /// Stopping other scripts is [`crate::blocks::stop_other_scripts`]
+    /// as it can be stacked below.
+    StopOption {
+        All => "all",
+        ThisScript => "this script",
+    }
+
+    /// Option for [`crate::blocks::when_key_pressed`]
+    Key {
+        Any => "any",
+        Space => "space",
+        LeftArrow => "left arrow",
+        RightArrow => "right arrow",
+        UpArrow => "up arrow",
+        DownArrow => "down arrow",
+        A => "a",
+        B => "b",
+        C => "c",
+        D => "d",
+        E => "e",
+        F => "f",
+        G => "g",
+        H => "h",
+        I => "i",
+        J => "j",
+        K => "k",
+        L => "l",
+        M => "m",
+        N => "n",
+        O => "o",
+        P => "p",
+        Q => "q",
+        R => "r",
+        S => "s",
+        T => "t",
+        U => "u",
+        V => "v",
+        W => "w",
+        X => "x",
+        Y => "y",
+        Z => "z",
+        Num0 => "0",
+        Num1 => "1",
+        Num2 => "2",
+        Num3 => "3",
+        Num4 => "4",
+        Num5 => "5",
+        Num6 => "6",
+        Num7 => "7",
+        Num8 => "8",
+        Num9 => "9",
+    }
+
+    /// Option for [`crate::blocks::when_greater_than`]
+    GreaterThanOption {
+        Loudness => "LOUDNESS",
+        Timer => "TIMER",
+    }
+
+    /// Option for [`crate::blocks::change_looks_effect_by`] and [`crate::blocks::set_looks_effect_to`]
+    LooksEffect {
+        Color => "COLOR",
+        Fisheye => "FISHEYE",
+        Whirl => "WHIRL",
+        Pixelate => "PIXELATE",
+        Mosaic => "MOSAIC",
+        Brightness => "BRIGHTNESS",
+        Ghost => "GHOST",
+    }
+
+    /// Option for [`crate::blocks::go_to_layer`]
+    LayerPosition {
+        Front => "front",
+        Back => "back",
+    }
+
+    /// Option for [`crate::blocks::change_layer`]
+    LayerDirection {
+        Forward => "forward",
+        Backward => "backward",
+    }
+
+    /// Option for [`crate::blocks::costume`] and [`crate::blocks::backdrop`]
+    NumberName {
+        Number => "number",
+        Name => "name",
+    }
+
+    /// Option for [`crate::blocks::set_rotation_style`] and rotation style of a sprite
+    #[derive(Default)]
+    RotationStyle {
+        #[default]
+        AllAround => "all around",
+        LeftRight => "left-right",
+        DontRotate => "don't rotate",
+    }
+
+    /// Option for [`crate::blocks::math_op`]
+    MathOp {
+        Abs => "abs",
+        Floor => "floor",
+        Ceiling => "ceiling",
+        Sqrt => "sqrt",
+        Sin => "sin",
+        Cos => "cos",
+        Tan => "tan",
+        Asin => "asin",
+        Acos => "acos",
+        Atan => "atan",
+        /// Natural logarithm
+        Ln => "ln",
+        /// Base 10 logarithm
+        Log => "log",
+        /// e to the power of
+        EPow => "e ^",
+        /// 10 to the power of
+        TenPow => "10 ^",
+    }
+
+    /// Option for [`crate::blocks::set_drag_mode`]
+    DragMode {
+        Draggable => "draggable",
+        NotDraggable => "not draggable",
+    }
+
+    /// Option for [`crate::blocks::current_datetime`]
+    DateTimeUnit {
+        Year => "YEAR",
+        Month => "MONTH",
+        Date => "DATE",
+        DayOfWeek => "DAYOFWEEK",
+        Hour => "HOUR",
+        Minute => "MINUTE",
+        Second => "SECOND",
+    }
+
+    /// Option for [`crate::blocks::change_sound_effect_by`] and [`crate::blocks::set_sound_effect_to`]
+    SoundEffect {
+        Pitch => "PITCH",
+        Pan => "PAN",
+    }
+
+    /// Option for [`crate::blocks::pen::change_pen_param_by`] and [`crate::blocks::pen::set_pen_param_to`]
+    PenColorParam {
+        Color => "color",
+        Saturation => "saturation",
+        Brightness => "brightness",
+        Transparency => "transparency",
+    }
+
+    /// Option for [`crate::blocks::music::play_drum_for_beats`]
+    Drum {
+        SnareDrum => "1",
+        BassDrum => "2",
+        SideStick => "3",
+        CrashCymbal => "4",
+        OpenHiHat => "5",
+        ClosedHiHat => "6",
+        Tambourine => "7",
+        HandClap => "8",
+        Claves => "9",
+        WoodBlock => "10",
+        Cowbell => "11",
+        Triangle => "12",
+        Bongo => "13",
+        Conga => "14",
+        Cabasa => "15",
+        Guiro => "16",
+        Vibraslap => "17",
+        Cuica => "18",
+    }
+
+    /// Option for [`crate::blocks::music::set_instrument`]
+    Instrument {
+        Piano => "1",
+        ElectricPiano => "2",
+        Organ => "3",
+        Guitar => "4",
+        ElectricGuitar => "5",
+        Bass => "6",
+        Pizzicato => "7",
+        Cello => "8",
+        Trombone => "9",
+        Clarinet => "10",
+        Saxophone => "11",
+        Flute => "12",
+        WoodenFlute => "13",
+        Bassoon => "14",
+        Choir => "15",
+        Vibraphone => "16",
+        MusicBox => "17",
+        SteelDrum => "18",
+        Marimba => "19",
+        SynthLead => "20",
+        SynthPad => "21",
+    }
+
+    /// Option for [`crate::blocks::text_to_speech::set_voice`]
+    Voice {
+        Alto => "ALTO",
+        Tenor => "TENOR",
+        Squeak => "SQUEAK",
+        Giant => "GIANT",
+        Kitten => "KITTEN",
+    }
+
+    /// Option for [`crate::blocks::text_to_speech::set_language`]
+    SpeechLanguage {
+        Arabic => "ar",
+        ChineseMandarin => "zh-cn",
+        Danish => "da",
+        Dutch => "nl",
+        English => "en",
+        French => "fr",
+        German => "de",
+        Hindi => "hi",
+        Icelandic => "is",
+        Italian => "it",
+        Japanese => "ja",
+        Korean => "ko",
+        Norwegian => "nb",
+        Polish => "pl",
+        PortugueseBrazilian => "pt-br",
+        Portuguese => "pt",
+        Romanian => "ro",
+        Russian => "ru",
+        Spanish => "es",
+        SpanishLatinAmerican => "es-419",
+        Swedish => "sv",
+        Turkish => "tr",
+        Welsh => "cy",
+    }
+
+    /// Option for [`crate::blocks::translate::translate`]
+    TranslateLanguage {
+        Amharic => "am",
+        Arabic => "ar",
+        Azerbaijani => "az",
+        Basque => "eu",
+        Bulgarian => "bg",
+        Catalan => "ca",
+        ChineseSimplified => "zh-cn",
+        ChineseTraditional => "zh-tw",
+        Croatian => "hr",
+        Czech => "cs",
+        Danish => "da",
+        Dutch => "nl",
+        English => "en",
+        Estonian => "et",
+        Finnish => "fi",
+        French => "fr",
+        Galician => "gl",
+        German => "de",
+        Greek => "el",
+        Hebrew => "he",
+        Hungarian => "hu",
+        Icelandic => "is",
+        Indonesian => "id",
+        Irish => "ga",
+        Italian => "it",
+        Japanese => "ja",
+        Korean => "ko",
+        Latvian => "lv",
+        Lithuanian => "lt",
+        Maori => "mi",
+        Norwegian => "nb",
+        Persian => "fa",
+        Polish => "pl",
+        Portuguese => "pt",
+        Romanian => "ro",
+        Russian => "ru",
+        ScotsGaelic => "gd",
+        Serbian => "sr",
+        Slovak => "sk",
+        Slovenian => "sl",
+        Spanish => "es",
+        Swedish => "sv",
+        Thai => "th",
+        Turkish => "tr",
+        Ukrainian => "uk",
+        Vietnamese => "vi",
+        Welsh => "cy",
+        Zulu => "zu",
+    }
+
+    /// Option for [`crate::blocks::video_sensing::video_on`]
+    VideoAttribute {
+        Motion => "motion",
+        Direction => "direction",
+    }
+
+    /// Option for [`crate::blocks::video_sensing::video_on`]
+    VideoSubject {
+        Sprite => "sprite",
+        Stage => "stage",
+    }
+
+    /// Option for [`crate::blocks::video_sensing::turn_video`] and state of the video sensing camera on the stage
+    #[derive(Default)]
+    VideoState {
+        #[default]
+        On => "on",
+        Off => "off",
+        OnFlipped => "on-flipped",
+    }
+}
@@ -0,0 +1,12 @@
+//! Scripting utilities built on top of sb-itchy
+
+pub mod arg;
+pub mod custom_block;
+pub mod extension;
+pub mod if_else_chain;
+pub mod local;
+pub mod loops;
+pub mod menu;
+pub mod ops;
+pub mod stack;
+pub mod switch;
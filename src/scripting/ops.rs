@@ -0,0 +1,130 @@
+//! Operator overloading for reporters
+//!
+//! # Usage
+//!
+//! Number reporters work with `+`, `-`, `*`, `/`, `%` and unary `-`,
+//! boolean reporters work with `!`, `&` and `|`.
+//! Note that `%` is Scratch's modulo which takes the sign of the divisor, unlike Rust's.
+//! Comparison are methods since Rust's comparison operators must return `bool`.
+//!
+//! ```
+//! # use sb_scratchy::blocks::{mouse_down, x_position, y_position, if_, say};
+//! // add(mul(x_position(), 2), 10)
+//! let x = x_position() * 2 + 10;
+//! // 100 - y_position()
+//! let y = 100 - y_position();
+//! // and(not(mouse_down()), less_than(x, y))
+//! let cond = !mouse_down() & x.lt(y);
+//!
+//! let script = if_(cond, say("hello"));
+//! ```
+
+use std::ops::{Add, BitAnd, BitOr, Div, Mul, Neg, Not, Rem, Sub};
+
+use crate::blocks;
+use crate::scripting::arg::*;
+
+macro_rules! number_op_impl {
+    ($($op:ident::$op_fn:ident => $block:ident),*) => {
+        $(
+            impl<R: IntoInput<Number>> $op<R> for JustReporter<Number> {
+                type Output = JustReporter<Number>;
+
+                fn $op_fn(self, rhs: R) -> JustReporter<Number> {
+                    blocks::$block(self, rhs)
+                }
+            }
+
+            number_op_impl!(@lhs $op::$op_fn => $block; i64, i32, i16, isize, u64, u32, u16, u8, usize, f64, f32);
+        )*
+    };
+
+    (@lhs $op:ident::$op_fn:ident => $block:ident; $($lhs:ty),*) => {
+        $(
+            impl $op<JustReporter<Number>> for $lhs {
+                type Output = JustReporter<Number>;
+
+                fn $op_fn(self, rhs: JustReporter<Number>) -> JustReporter<Number> {
+                    blocks::$block(self, rhs)
+                }
+            }
+        )*
+    };
+}
+
+number_op_impl! {
+    Add::add => add,
+    Sub::sub => sub,
+    Mul::mul => mul,
+    Div::div => div,
+    Rem::rem => modulo
+}
+
+/// `0 - self`
+impl Neg for JustReporter<Number> {
+    type Output = JustReporter<Number>;
+
+    fn neg(self) -> JustReporter<Number> {
+        blocks::sub(0, self)
+    }
+}
+
+impl Not for JustReporter<Bool> {
+    type Output = JustReporter<Bool>;
+
+    fn not(self) -> JustReporter<Bool> {
+        blocks::not(self)
+    }
+}
+
+impl<R: IntoInput<Bool>> BitAnd<R> for JustReporter<Bool> {
+    type Output = JustReporter<Bool>;
+
+    fn bitand(self, rhs: R) -> JustReporter<Bool> {
+        blocks::and(self, rhs)
+    }
+}
+
+impl<R: IntoInput<Bool>> BitOr<R> for JustReporter<Bool> {
+    type Output = JustReporter<Bool>;
+
+    fn bitor(self, rhs: R) -> JustReporter<Bool> {
+        blocks::or(self, rhs)
+    }
+}
+
+/// Comparison between reporters or a reporter and a value
+impl<T> JustReporter<T>
+where
+    JustReporter<T>: IntoInput<Value>,
+{
+    /// `self < rhs`
+    pub fn lt(self, rhs: impl IntoInput<Value>) -> JustReporter<Bool> {
+        blocks::less_than(self, rhs)
+    }
+
+    /// `self > rhs`
+    pub fn gt(self, rhs: impl IntoInput<Value>) -> JustReporter<Bool> {
+        blocks::greater_than(self, rhs)
+    }
+
+    /// `self = rhs`
+    pub fn eq_(self, rhs: impl IntoInput<Value>) -> JustReporter<Bool> {
+        blocks::equals(self, rhs)
+    }
+
+    /// `not (rhs < self)`
+    pub fn le(self, rhs: impl IntoInput<Value>) -> JustReporter<Bool> {
+        blocks::not(blocks::less_than(rhs, self))
+    }
+
+    /// `not (self < rhs)`
+    pub fn ge(self, rhs: impl IntoInput<Value>) -> JustReporter<Bool> {
+        blocks::not(blocks::less_than(self, rhs))
+    }
+
+    /// `not (self = rhs)`
+    pub fn ne_(self, rhs: impl IntoInput<Value>) -> JustReporter<Bool> {
+        blocks::not(blocks::equals(self, rhs))
+    }
+}
@@ -7,6 +7,8 @@ use sb_itchy::{
     stack::StackBuilder as ItchyStackBuilder,
 };
 
+use crate::scripting::arg::{IntoInput, JustReporter, Reporter, Stack};
+
 /// State/Marker for [`TypedStackBuilder`] that this side can be stacked.
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub struct StackableSide;
@@ -46,6 +48,7 @@ mod test {
 impl<S, E> TypedStackBuilder<S, E> {
     /// Start building stack
     ///
+    /// Only for the shapes a block can have, see [`BlockShape`].
     /// Though, you probably want to use predefined blocks in [`crate::blocks`].
     ///
     /// # Examples
@@ -80,7 +83,19 @@ impl<S, E> TypedStackBuilder<S, E> {
     ///     TypedStackBuilder::start(b)
     /// }
     /// ```
-    pub fn start(block_builder: BlockNormalBuilder) -> TypedStackBuilder<S, E> {
+    ///
+    /// A block that nothing can be stacked on or below can't be started:
+    ///
+    /// ```compile_fail
+    /// # use sb_itchy::block::BlockNormalBuilder;
+    /// # use sb_scratchy::scripting::stack::{TypedStackBuilder, UnstackableSide};
+    /// let lonely: TypedStackBuilder<UnstackableSide, UnstackableSide> =
+    ///     TypedStackBuilder::start(BlockNormalBuilder::new("anOpCodeOfYourBlock"));
+    /// ```
+    pub fn start(block_builder: BlockNormalBuilder) -> TypedStackBuilder<S, E>
+    where
+        TypedStackBuilder<S, E>: BlockShape,
+    {
         TypedStackBuilder {
             stack_builder: ItchyStackBuilder::start(block_builder),
             start: PhantomData,
@@ -95,7 +110,10 @@ impl<S, E> TypedStackBuilder<S, E> {
     pub fn start_with_capacity(
         capacity: usize,
         block_builder: BlockBuilder,
-    ) -> TypedStackBuilder<S, E> {
+    ) -> TypedStackBuilder<S, E>
+    where
+        TypedStackBuilder<S, E>: BlockShape,
+    {
         TypedStackBuilder {
             stack_builder: ItchyStackBuilder::start_with_capacity(capacity, block_builder),
             start: PhantomData,
@@ -103,8 +121,16 @@ impl<S, E> TypedStackBuilder<S, E> {
         }
     }
 
+    /// Type an already built stack without checking anything.
+    ///
+    /// This is a low-level escape hatch. To define a block, build a [`BlockNormalBuilder`]
+    /// and give it a shape with [`BlockShape::from_block`] instead.
+    ///
     /// # Safety
-    /// I mark this unsafe first because I'm unsure what kind of dark power incorrectly typed boys are packing.
+    /// The stack must actually have the shape `S` and `E` describe:
+    /// the top block must accept a block above it only if `S` is [`StackableSide`],
+    /// and the bottom block must accept a block below it only if `E` is [`StackableSide`].
+    /// Getting this wrong makes a project that Scratch can't load properly.
     pub unsafe fn assume_typed(stack_builder: ItchyStackBuilder) -> TypedStackBuilder<S, E> {
         TypedStackBuilder {
             stack_builder,
@@ -134,3 +160,169 @@ impl<S> TypedStackBuilder<S, StackableSide> {
         }
     }
 }
+
+/// Becomes a stack that can be put below another block, e.g. in the body of a loop.
+///
+/// Implemented for everything in this crate that goes into a [`Stack`] input:
+/// stacks that can be put below another block and the if-else chain and switch builders.
+pub trait IntoStack: IntoInput<Stack> {
+    /// Bottom of the stack
+    type End;
+
+    fn into_stack(self) -> TypedStackBuilder<StackableSide, Self::End>;
+}
+
+impl<E> IntoStack for TypedStackBuilder<StackableSide, E> {
+    type End = E;
+
+    fn into_stack(self) -> TypedStackBuilder<StackableSide, E> {
+        self
+    }
+}
+
+/// End of a stack that can't have blocks below it.
+///
+/// Other than [`UnstackableSide`], it can be an end that tells how the stack ends,
+/// like [`crate::scripting::local::FrameExit`].
+pub(crate) trait UnstackableEnd {}
+
+impl UnstackableEnd for UnstackableSide {}
+
+impl<S, E> TypedStackBuilder<S, E> {
+    /// Same stack with another end that also can't have blocks below it
+    pub(crate) fn retype_end<NE>(self) -> TypedStackBuilder<S, NE>
+    where
+        E: UnstackableEnd,
+        NE: UnstackableEnd,
+    {
+        TypedStackBuilder {
+            stack_builder: self.stack_builder,
+            start: PhantomData,
+            end: PhantomData,
+        }
+    }
+}
+
+mod sealed {
+    use super::*;
+
+    pub trait Sealed: Sized {
+        fn from_stack(stack_builder: ItchyStackBuilder) -> Self;
+    }
+
+    impl<S, E> Sealed for TypedStackBuilder<S, E> {
+        fn from_stack(stack_builder: ItchyStackBuilder) -> Self {
+            TypedStackBuilder {
+                stack_builder,
+                start: PhantomData,
+                end: PhantomData,
+            }
+        }
+    }
+
+    impl<T, S, E> Sealed for Reporter<T, S, E> {
+        fn from_stack(stack_builder: ItchyStackBuilder) -> Self {
+            Reporter::new(TypedStackBuilder::from_stack(stack_builder))
+        }
+    }
+}
+
+/// Shape of a block: whether it can be stacked on top/below and whether it reports something.
+///
+/// Implemented for [`HatBlock`], [`CapBlock`], [`StackBlock`] and [`JustReporter`].
+/// This is sealed, the shapes are all the shapes Scratch has.
+///
+/// # Examples
+///
+/// ```
+/// use sb_itchy::block::BlockNormalBuilder;
+/// use sb_scratchy::scripting::{
+///     arg::{IntoInput, JustReporter, Number},
+///     stack::{BlockShape, HatBlock, StackBlock},
+/// };
+///
+/// fn some_hat() -> HatBlock {
+///     BlockShape::from_block(BlockNormalBuilder::new("anOpCodeOfYourHat"))
+/// }
+///
+/// fn some_reporter(x: impl IntoInput<Number>) -> JustReporter<Number> {
+///     let mut b = BlockNormalBuilder::new("anOpCodeOfYourReporter");
+///     b.add_input("X", x.into_input());
+///     BlockShape::from_block(b)
+/// }
+///
+/// let stack: StackBlock = BlockShape::from_block(BlockNormalBuilder::new("anOpCodeOfYourBlock"));
+/// ```
+///
+/// A single block can't be anything else, like a block that nothing can be stacked on or below:
+///
+/// ```compile_fail
+/// # use sb_itchy::block::BlockNormalBuilder;
+/// # use sb_scratchy::scripting::stack::{BlockShape, TypedStackBuilder, UnstackableSide};
+/// let lonely: TypedStackBuilder<UnstackableSide, UnstackableSide> =
+///     BlockShape::from_block(BlockNormalBuilder::new("anOpCodeOfYourBlock"));
+/// ```
+pub trait BlockShape: sealed::Sealed {
+    /// Give a single block this shape
+    fn from_block(block_builder: BlockNormalBuilder) -> Self {
+        Self::from_stack(ItchyStackBuilder::start(block_builder))
+    }
+}
+
+impl BlockShape for HatBlock {}
+impl BlockShape for CapBlock {}
+impl BlockShape for StackBlock {}
+impl<T> BlockShape for JustReporter<T> {}
+
+/// Give a single block made by [`sb_itchy::blocks`] its shape.
+pub(crate) fn shaped<B: BlockShape>(block: ItchyStackBuilder) -> B {
+    B::from_stack(block)
+}
+
+/// Checks that [`crate::scratch!`] puts around blocks so misplaced ones are reported where they are
+#[doc(hidden)]
+pub mod checks {
+    use super::*;
+
+    #[diagnostic::on_unimplemented(
+        message = "`{Self}` can't be put below another block",
+        label = "hat blocks can only be at the top of a script"
+    )]
+    pub trait CanGoBelow {
+        type End;
+        fn into_stack(self) -> TypedStackBuilder<StackableSide, Self::End>;
+    }
+
+    impl<E> CanGoBelow for TypedStackBuilder<StackableSide, E> {
+        type End = E;
+        fn into_stack(self) -> TypedStackBuilder<StackableSide, E> {
+            self
+        }
+    }
+
+    #[diagnostic::on_unimplemented(
+        message = "nothing can be put below `{Self}`",
+        label = "cap blocks end the script"
+    )]
+    pub trait CanGoAbove {
+        type Start;
+        fn into_stack(self) -> TypedStackBuilder<Self::Start, StackableSide>;
+    }
+
+    impl<S> CanGoAbove for TypedStackBuilder<S, StackableSide> {
+        type Start = S;
+        fn into_stack(self) -> TypedStackBuilder<S, StackableSide> {
+            self
+        }
+    }
+
+    /// `block` if something can be put above it
+    pub fn below<B: CanGoBelow>(block: B) -> TypedStackBuilder<StackableSide, B::End> {
+        block.into_stack()
+    }
+
+    /// `block` if something can be put below it
+    pub fn above<B: CanGoAbove>(block: B) -> TypedStackBuilder<B::Start, StackableSide> {
+        block.into_stack()
+    }
+}
@@ -0,0 +1,127 @@
+//! Create switch that compares a value to each case
+//!
+//! # Explanation
+//!
+//! Scratch doesn't have switch. This builder create flat if-else chain like
+//! [`crate::scripting::if_else_chain`] with `<(value) = (case)>` as the conditions:
+//! ```txt
+//! if <(value) = (case 1)> {
+//!     
+//! } else if <(value) = (case 2)> {
+//!     
+//! } else {
+//!     
+//! }
+//! ```
+//!
+//! The value is reported in every condition until a case matches.
+//! If reporting it has a side effect or is different every time, like `pick random`,
+//! put it in a variable first.
+//!
+//! # Usage
+//!
+//! ```
+//! # use sb_scratchy::blocks::{answer, say, turn_left, turn_right};
+//! # use sb_scratchy::scripting::switch::switch_;
+//! switch_(answer())
+//!     .case("left", turn_left(90))
+//!     .case("right", turn_right(90))
+//!     .default(say("which way?"))
+//!     .end();
+//! ```
+
+use crate::blocks::equals;
+use crate::scripting::{
+    arg::*,
+    if_else_chain::{if_, Building, End, IfElseChainBuilder},
+    stack::*,
+};
+use sb_itchy::prelude::BlockInputBuilder;
+
+/// Start switch over `value`. See top module documentation for usage.
+pub fn switch_<V>(value: V) -> Switch<V>
+where
+    V: IntoInput<Value> + Clone,
+{
+    Switch { value }
+}
+
+/// Switch without any case yet. Add one with [`Switch::case`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Switch<V> {
+    value: V,
+}
+
+impl<V> Switch<V>
+where
+    V: IntoInput<Value> + Clone,
+{
+    /// Add first case
+    pub fn case(
+        self,
+        case: impl IntoInput<Value>,
+        then: impl IntoInput<Stack>,
+    ) -> SwitchBuilder<V, Building> {
+        let chain = if_(equals(self.value.clone(), case), then);
+        SwitchBuilder {
+            value: self.value,
+            chain,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// Builder to create switch.
+///
+/// See top module documentation for usage.
+pub struct SwitchBuilder<V, S> {
+    value: V,
+    chain: IfElseChainBuilder<S>,
+}
+
+impl<V> SwitchBuilder<V, Building>
+where
+    V: IntoInput<Value> + Clone,
+{
+    /// Add case
+    pub fn case(
+        mut self,
+        case: impl IntoInput<Value>,
+        then: impl IntoInput<Stack>,
+    ) -> SwitchBuilder<V, Building> {
+        self.chain = self.chain.else_if(equals(self.value.clone(), case), then);
+        self
+    }
+
+    /// Add default for when no case matches. After this you cannot add anymore case
+    pub fn default(self, then: impl IntoInput<Stack>) -> SwitchBuilder<V, End> {
+        SwitchBuilder {
+            value: self.value,
+            chain: self.chain.else_(then),
+        }
+    }
+}
+
+impl<V, S> SwitchBuilder<V, S> {
+    /// End switch
+    pub fn end(self) -> StackBlock {
+        self.chain.end()
+    }
+}
+
+impl<V, S> IntoInput<Stack> for SwitchBuilder<V, S> {
+    fn into_input(self) -> BlockInputBuilder {
+        BlockInputBuilder::stack(self.end().into_untyped())
+    }
+}
+
+impl<V, S> IntoStack for SwitchBuilder<V, S> {
+    type End = StackableSide;
+
+    fn into_stack(self) -> StackBlock {
+        self.end()
+    }
+}
+
+#[cfg(test)]
+mod tests;
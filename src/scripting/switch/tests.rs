@@ -0,0 +1,44 @@
+//! Running switch in the interpreter
+
+use super::*;
+use crate::blocks::{set_var_to, var, when_flag_clicked};
+use crate::interpreter::start;
+use crate::project::{SpriteBuilder, StageBuilder, TargetBuilder};
+
+/// What `out` is after switching over `value` with and without default
+fn run(value: &str) -> (String, String) {
+    let mut cat = SpriteBuilder::new(TargetBuilder::new("Cat"));
+    let x = cat.declare_var::<Value>("x", value);
+    let out = cat.declare_var::<Text>("out", "");
+    let without_default = cat.declare_var::<Text>("without default", "");
+    let script = when_flag_clicked()
+        .next(
+            switch_(var(&x))
+                .case(1, set_var_to(&out, "one"))
+                .case("b", set_var_to(&out, "bee"))
+                .case(1, set_var_to(&out, "one again"))
+                .default(set_var_to(&out, "other"))
+                .end(),
+        )
+        .next(
+            switch_(var(&x))
+                .case("b", set_var_to(&without_default, "bee"))
+                .end(),
+        );
+    let mut interpreter = start(StageBuilder::default(), cat.add_script(script));
+    assert!(interpreter.run_until_idle(10));
+    let text = |name| interpreter.variable("Cat", name).unwrap().to_text();
+    (text("out"), text("without default"))
+}
+
+#[test]
+fn first_matching_case_runs() {
+    assert_eq!(run("1"), ("one".to_owned(), String::new()));
+    // Scratch compares text without case
+    assert_eq!(run("B"), ("bee".to_owned(), "bee".to_owned()));
+}
+
+#[test]
+fn default_runs_when_nothing_matches() {
+    assert_eq!(run("c"), ("other".to_owned(), String::new()));
+}
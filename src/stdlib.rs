@@ -0,0 +1,789 @@
+//! Helpers that Scratch doesn't have a block for, made as custom blocks
+//!
+//! # Explanation
+//!
+//! Each helper is a custom block of the sprite (or stage) that returns like [`crate::scripting::custom_block::Function`].
+//! Calling one gives the call block and a reporter of what it returns that can be used after the call block.
+//!
+//! [`Stdlib`] keeps track of which helpers are called and only those are added to the sprite or stage
+//! with [`Stdlib::install`].
+//! Some helpers also add variables named `"(stdlib i)"`, `"(stdlib j)"` and `"(stdlib text)"` that they count with.
+//!
+//! Helpers run without screen refresh. Like Scratch, text is compared ignoring case.
+//! Integer and bitwise math is in [`bits`].
+//!
+//! # Usage
+//!
+//! ```
+//! # use sb_scratchy::blocks::*;
+//! # use sb_scratchy::project::{SpriteBuilder, TargetBuilder};
+//! # use sb_scratchy::scripting::arg::Text;
+//! # use sb_scratchy::stdlib::Stdlib;
+//! let mut cat = SpriteBuilder::new(TargetBuilder::new("Cat"));
+//! let words = cat.declare_list::<Text, &str>("words", []);
+//!
+//! let mut std = Stdlib::new();
+//! let (clamp, x) = std.clamp(mouse_x(), -100, 100);
+//! let (upper, shout) = std.to_upper(answer());
+//! let script = when_flag_clicked()
+//!     .next(clamp)
+//!     .next(set_x(x))
+//!     .next(ask_and_wait("What's your name?"))
+//!     .next(upper)
+//!     .next(say(shout))
+//!     .next(std.split(answer(), " ", &words))
+//!     .next(std.sort(&words));
+//!
+//! // Only clamp, to upper, split and sort are added
+//! let cat = std.install(cat.add_script(script));
+//! ```
+
+pub mod bits;
+
+use crate::blocks::*;
+use crate::project::{Target, TargetBuilder};
+use crate::scripting::{
+    arg::*,
+    custom_block::{
+        arg_reporter, return_var_name, value_call_input, CustomBlock, CustomBlockBuilder, Function,
+    },
+    menu::MathOp,
+    stack::*,
+};
+
+const ALPHABET_LOWER: &str = "abcdefghijklmnopqrstuvwxyz";
+const ALPHABET_UPPER: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+/// Helpers used by a sprite or the stage.
+///
+/// See top module documentation for usage.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Stdlib {
+    used: Vec<Helper>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Helper {
+    Min,
+    Max,
+    Clamp,
+    Pow,
+    Atan2,
+    Lerp,
+    Sign,
+    Substring,
+    IndexOf,
+    ToUpper,
+    ToLower,
+    RepeatString,
+    Reverse,
+    Split(ListHandle<Value>),
+    Sort(ListHandle<Value>),
+    Shuffle(ListHandle<Value>),
+    Bitwise(bits::BitOp),
+}
+
+impl Stdlib {
+    pub fn new() -> Stdlib {
+        Stdlib { used: vec![] }
+    }
+
+    /// Smaller of `a` and `b`
+    pub fn min(
+        &mut self,
+        a: impl IntoInput<Number>,
+        b: impl IntoInput<Number>,
+    ) -> (StackBlock, JustReporter<Number>) {
+        self.use_(Helper::Min);
+        min_fn().call_with_inputs(vec![value_call_input(a), value_call_input(b)])
+    }
+
+    /// Bigger of `a` and `b`
+    pub fn max(
+        &mut self,
+        a: impl IntoInput<Number>,
+        b: impl IntoInput<Number>,
+    ) -> (StackBlock, JustReporter<Number>) {
+        self.use_(Helper::Max);
+        max_fn().call_with_inputs(vec![value_call_input(a), value_call_input(b)])
+    }
+
+    /// `x` but no smaller than `min` and no bigger than `max`
+    pub fn clamp(
+        &mut self,
+        x: impl IntoInput<Number>,
+        min: impl IntoInput<Number>,
+        max: impl IntoInput<Number>,
+    ) -> (StackBlock, JustReporter<Number>) {
+        self.use_(Helper::Clamp);
+        clamp_fn().call_with_inputs(vec![
+            value_call_input(x),
+            value_call_input(min),
+            value_call_input(max),
+        ])
+    }
+
+    /// `base` to the power of `exponent`
+    pub fn pow(
+        &mut self,
+        base: impl IntoInput<Number>,
+        exponent: impl IntoInput<Number>,
+    ) -> (StackBlock, JustReporter<Number>) {
+        self.use_(Helper::Pow);
+        pow_fn().call_with_inputs(vec![value_call_input(base), value_call_input(exponent)])
+    }
+
+    /// Angle of the point (`x`, `y`) from the x axis in degrees, from `-180` to `180`.
+    ///
+    /// Note that this is in degrees like other Scratch blocks.
+    pub fn atan2(
+        &mut self,
+        y: impl IntoInput<Number>,
+        x: impl IntoInput<Number>,
+    ) -> (StackBlock, JustReporter<Number>) {
+        self.use_(Helper::Atan2);
+        atan2_fn().call_with_inputs(vec![value_call_input(y), value_call_input(x)])
+    }
+
+    /// Number `t` of the way from `a` to `b`, `a + (b - a) * t`
+    pub fn lerp(
+        &mut self,
+        a: impl IntoInput<Number>,
+        b: impl IntoInput<Number>,
+        t: impl IntoInput<Number>,
+    ) -> (StackBlock, JustReporter<Number>) {
+        self.use_(Helper::Lerp);
+        lerp_fn().call_with_inputs(vec![
+            value_call_input(a),
+            value_call_input(b),
+            value_call_input(t),
+        ])
+    }
+
+    /// `1` if `x` is positive, `-1` if it's negative and `0` if it's `0`
+    pub fn sign(&mut self, x: impl IntoInput<Number>) -> (StackBlock, JustReporter<Number>) {
+        self.use_(Helper::Sign);
+        sign_fn().call_with_inputs(vec![value_call_input(x)])
+    }
+
+    /// Letters of `text` from letter `from` to letter `to`, including both
+    pub fn substring(
+        &mut self,
+        text: impl IntoInput<Text>,
+        from: impl IntoInput<Integer>,
+        to: impl IntoInput<Integer>,
+    ) -> (StackBlock, JustReporter<Text>) {
+        self.use_(Helper::Substring);
+        substring_fn().call_with_inputs(vec![
+            value_call_input(text),
+            value_call_input(from),
+            value_call_input(to),
+        ])
+    }
+
+    /// Which letter of `text` the first `part` starts at, `0` if there's none
+    pub fn index_of(
+        &mut self,
+        text: impl IntoInput<Text>,
+        part: impl IntoInput<Text>,
+    ) -> (StackBlock, JustReporter<Integer>) {
+        self.use_(Helper::IndexOf);
+        index_of_fn().call_with_inputs(vec![value_call_input(part), value_call_input(text)])
+    }
+
+    /// `text` with english letters in uppercase
+    pub fn to_upper(&mut self, text: impl IntoInput<Text>) -> (StackBlock, JustReporter<Text>) {
+        self.use_(Helper::ToUpper);
+        to_upper_fn().call_with_inputs(vec![value_call_input(text)])
+    }
+
+    /// `text` with english letters in lowercase
+    pub fn to_lower(&mut self, text: impl IntoInput<Text>) -> (StackBlock, JustReporter<Text>) {
+        self.use_(Helper::ToLower);
+        to_lower_fn().call_with_inputs(vec![value_call_input(text)])
+    }
+
+    /// `text` joined `times` times
+    pub fn repeat_string(
+        &mut self,
+        text: impl IntoInput<Text>,
+        times: impl IntoInput<PositiveInteger>,
+    ) -> (StackBlock, JustReporter<Text>) {
+        self.use_(Helper::RepeatString);
+        repeat_string_fn().call_with_inputs(vec![value_call_input(text), value_call_input(times)])
+    }
+
+    /// Letters of `text` backward
+    pub fn reverse(&mut self, text: impl IntoInput<Text>) -> (StackBlock, JustReporter<Text>) {
+        self.use_(Helper::Reverse);
+        reverse_fn().call_with_inputs(vec![value_call_input(text)])
+    }
+
+    /// Replace items of `list` with parts of `text` between each `separator`.
+    ///
+    /// `separator` is a single letter.
+    pub fn split<T>(
+        &mut self,
+        text: impl IntoInput<Text>,
+        separator: impl IntoInput<Text>,
+        list: &ListHandle<T>,
+    ) -> StackBlock {
+        let list = any_list(list);
+        let call = split_block(&list)
+            .call_with_inputs(vec![value_call_input(text), value_call_input(separator)]);
+        self.use_(Helper::Split(list));
+        call
+    }
+
+    /// Sort items of `list` from smallest to biggest,
+    /// comparing them the same way [`crate::blocks::less_than`] does
+    pub fn sort<T>(&mut self, list: &ListHandle<T>) -> StackBlock {
+        let list = any_list(list);
+        let call = sort_block(&list).call_with_inputs(vec![]);
+        self.use_(Helper::Sort(list));
+        call
+    }
+
+    /// Put items of `list` in random order
+    pub fn shuffle<T>(&mut self, list: &ListHandle<T>) -> StackBlock {
+        let list = any_list(list);
+        let call = shuffle_block(&list).call_with_inputs(vec![]);
+        self.use_(Helper::Shuffle(list));
+        call
+    }
+
+    /// Add the used helpers to the stage or a sprite
+    ///
+    /// Helpers and variables that `target` already has from another [`Stdlib`] aren't added again.
+    /// The stage's variables are global, so sprites can't have a helper or temp variable the stage also has,
+    /// see [`crate::project::BuildError::DuplicateName`].
+    pub fn install<T: Target>(self, mut target: T) -> T {
+        let t = target.target_mut();
+        if self.used.iter().any(Helper::needs_temps) {
+            t.add_variable_once(I, 0.);
+            t.add_variable_once(J, 0.);
+            t.add_variable_once(TEXT, "");
+        }
+        for helper in self.used {
+            helper.install(t);
+        }
+        target
+    }
+
+    fn use_(&mut self, helper: Helper) {
+        if !self.used.contains(&helper) {
+            self.used.push(helper);
+        }
+    }
+}
+
+impl Helper {
+    fn needs_temps(&self) -> bool {
+        !matches!(
+            self,
+            Helper::Min
+                | Helper::Max
+                | Helper::Clamp
+                | Helper::Atan2
+                | Helper::Lerp
+                | Helper::Sign
+                | Helper::RepeatString
+        )
+    }
+
+    fn install(self, target: &mut TargetBuilder) {
+        match self {
+            Helper::Min => {
+                let f = min_fn();
+                let def = f.define().next(if_else(
+                    less_than(arg::<Value>("a"), arg::<Value>("b")),
+                    f.return_(arg::<Number>("a")),
+                    f.return_(arg::<Number>("b")),
+                ));
+                add_function(target, &f, def)
+            }
+            Helper::Max => {
+                let f = max_fn();
+                let def = f.define().next(if_else(
+                    greater_than(arg::<Value>("a"), arg::<Value>("b")),
+                    f.return_(arg::<Number>("a")),
+                    f.return_(arg::<Number>("b")),
+                ));
+                add_function(target, &f, def)
+            }
+            Helper::Clamp => {
+                let f = clamp_fn();
+                let def = f
+                    .define()
+                    .next(if_(
+                        less_than(arg::<Value>("x"), arg::<Value>("min")),
+                        f.return_(arg::<Number>("min")),
+                    ))
+                    .next(if_(
+                        greater_than(arg::<Value>("x"), arg::<Value>("max")),
+                        f.return_(arg::<Number>("max")),
+                    ))
+                    .next(f.return_(arg::<Number>("x")));
+                add_function(target, &f, def)
+            }
+            Helper::Pow => {
+                let f = pow_fn();
+                let ret = f.return_var();
+                let exponent = || arg::<Number>("exponent");
+                // squaring for integer exponent so negative base works too,
+                // it repeats once for each bit of the exponent so big ones are still quick
+                let integer = set_var_to(ret, 1)
+                    .next(set_var_to(&i(), math_op(MathOp::Abs, exponent())))
+                    .next(set_var_to(&j(), arg::<Number>("base")))
+                    .next(repeat_until(
+                        var(&i()).lt(1),
+                        Some(
+                            if_(
+                                equals(var(&i()) % 2, 1),
+                                set_var_to(ret, var(ret) * var(&j())),
+                            )
+                            .next(set_var_to(&j(), var(&j()) * var(&j())))
+                            .next(set_var_to(&i(), math_op(MathOp::Floor, var(&i()) / 2))),
+                        ),
+                    ))
+                    .next(if_(exponent().lt(0), set_var_to(ret, 1 / var(ret))));
+                let other = set_var_to(
+                    ret,
+                    math_op(
+                        MathOp::EPow,
+                        exponent() * math_op(MathOp::Ln, arg::<Number>("base")),
+                    ),
+                );
+                let is_integer = and(
+                    equals(round(exponent()), exponent()),
+                    less_than(math_op(MathOp::Abs, exponent()), "Infinity"),
+                );
+                let def = f.define().next(if_else(is_integer, integer, other));
+                add_function(target, &f, def)
+            }
+            Helper::Atan2 => {
+                let f = atan2_fn();
+                let ret = f.return_var();
+                let (x, y) = (|| arg::<Number>("x"), || arg::<Number>("y"));
+                let def = f
+                    .define()
+                    .next(if_(
+                        equals(x(), 0),
+                        if_(y().gt(0), f.return_(90))
+                            .next(if_(y().lt(0), f.return_(-90)))
+                            .next(f.return_(0)),
+                    ))
+                    .next(set_var_to(ret, math_op(MathOp::Atan, y() / x())))
+                    .next(if_(
+                        x().lt(0),
+                        if_else(y().lt(0), change_var_by(ret, -180), change_var_by(ret, 180)),
+                    ));
+                add_function(target, &f, def)
+            }
+            Helper::Lerp => {
+                let f = lerp_fn();
+                let (a, b, t) = (
+                    || arg::<Number>("a"),
+                    || arg::<Number>("b"),
+                    || arg::<Number>("t"),
+                );
+                let def = f.define().next(f.return_(a() + (b() - a()) * t()));
+                add_function(target, &f, def)
+            }
+            Helper::Sign => {
+                let f = sign_fn();
+                let x = || arg::<Number>("x");
+                let def = f
+                    .define()
+                    .next(if_(x().gt(0), f.return_(1)))
+                    .next(if_(x().lt(0), f.return_(-1)))
+                    .next(f.return_(0));
+                add_function(target, &f, def)
+            }
+            Helper::Substring => {
+                let f = substring_fn();
+                let ret = f.return_var();
+                let count = arg::<Number>("to") - arg::<Number>("from") + 1;
+                let def = f
+                    .define()
+                    .next(set_var_to(ret, ""))
+                    .next(set_var_to(&i(), arg::<Number>("from")))
+                    .next(repeat(
+                        retype::<_, PositiveInteger>(count),
+                        Some(
+                            set_var_to(ret, join(ret, letter_of(&i_as(), arg::<Text>("text"))))
+                                .next(change_var_by(&i(), 1)),
+                        ),
+                    ));
+                add_function(target, &f, def)
+            }
+            Helper::IndexOf => {
+                let f = index_of_fn();
+                let (text, part) = (|| arg::<Text>("text"), || arg::<Text>("part"));
+                let tries = retype::<_, Number>(length_of(text()))
+                    - retype::<_, Number>(length_of(part()))
+                    + 1;
+                let letter = letter_of(retype::<_, PositiveInteger>(var(&i()) + var(&j())), text());
+                let try_at_i = set_var_to(&text_temp(), "")
+                    .next(set_var_to(&j(), 0))
+                    .next(repeat(
+                        length_of(part()),
+                        Some(
+                            set_var_to(&text_temp(), join(&text_temp(), letter))
+                                .next(change_var_by(&j(), 1)),
+                        ),
+                    ))
+                    .next(if_(equals(var(&text_temp()), part()), f.return_(&i_as())))
+                    .next(change_var_by(&i(), 1));
+                let def = f
+                    .define()
+                    .next(if_(not(contains(text(), part())), f.return_(0)))
+                    .next(set_var_to(&i(), 1))
+                    .next(repeat(retype::<_, PositiveInteger>(tries), Some(try_at_i)))
+                    .next(f.return_(0));
+                add_function(target, &f, def)
+            }
+            Helper::ToUpper => {
+                let f = to_upper_fn();
+                let def = change_case(&f, ALPHABET_UPPER);
+                add_function(target, &f, def)
+            }
+            Helper::ToLower => {
+                let f = to_lower_fn();
+                let def = change_case(&f, ALPHABET_LOWER);
+                add_function(target, &f, def)
+            }
+            Helper::RepeatString => {
+                let f = repeat_string_fn();
+                let ret = f.return_var();
+                let def = f.define().next(set_var_to(ret, "")).next(repeat(
+                    arg::<PositiveInteger>("times"),
+                    Some(set_var_to(ret, join(ret, arg::<Text>("text")))),
+                ));
+                add_function(target, &f, def)
+            }
+            Helper::Reverse => {
+                let f = reverse_fn();
+                let ret = f.return_var();
+                let text = || arg::<Text>("text");
+                let def = f
+                    .define()
+                    .next(set_var_to(ret, ""))
+                    .next(set_var_to(&i(), 1))
+                    .next(repeat(
+                        length_of(text()),
+                        Some(
+                            set_var_to(ret, join(letter_of(&i_as(), text()), ret))
+                                .next(change_var_by(&i(), 1)),
+                        ),
+                    ));
+                add_function(target, &f, def)
+            }
+            Helper::Split(list) => {
+                let block = split_block(&list);
+                let text = || arg::<Text>("text");
+                let letter = || letter_of(&i_as(), text());
+                let def = block
+                    .define()
+                    .next(delete_all_in_list(&list))
+                    .next(set_var_to(&text_temp(), ""))
+                    .next(set_var_to(&i(), 1))
+                    .next(repeat(
+                        length_of(text()),
+                        Some(
+                            if_else(
+                                equals(letter(), arg::<Value>("separator")),
+                                add_to_list(&list, &text_as()).next(set_var_to(&text_temp(), "")),
+                                set_var_to(&text_temp(), join(&text_temp(), letter())),
+                            )
+                            .next(change_var_by(&i(), 1)),
+                        ),
+                    ))
+                    .next(add_to_list(&list, &text_as()));
+                target.add_helper(block.proccode(), def)
+            }
+            Helper::Sort(list) => {
+                // insertion sort
+                let block = sort_block(&list);
+                let length = || retype::<_, Number>(length_of_list(&list));
+                let j_item = || item_in_list(&list, &j_as());
+                let after_j = || retype::<_, Integer>(var(&j()) + 1);
+                let shift = repeat_until(
+                    or(var(&j()).lt(1), not(greater_than(j_item(), &text_as()))),
+                    Some(replace_in_list(&list, after_j(), j_item()).next(change_var_by(&j(), -1))),
+                );
+                let def = block.define().next(set_var_to(&i(), 2)).next(repeat(
+                    retype::<_, PositiveInteger>(length() - 1),
+                    Some(
+                        set_var_to(&text_as::<Value>(), item_in_list(&list, &i_as()))
+                            .next(set_var_to(&j(), var(&i()) - 1))
+                            .next(shift)
+                            .next(replace_in_list(&list, after_j(), &text_as()))
+                            .next(change_var_by(&i(), 1)),
+                    ),
+                ));
+                target.add_helper(block.proccode(), def)
+            }
+            Helper::Shuffle(list) => {
+                // Fisher-Yates shuffle
+                let block = shuffle_block(&list);
+                let length = || retype::<_, Number>(length_of_list(&list));
+                let def = block.define().next(set_var_to(&i(), length())).next(repeat(
+                    retype::<_, PositiveInteger>(length() - 1),
+                    Some(
+                        set_var_to(&j(), random(1, &i()))
+                            .next(set_var_to(
+                                &text_as::<Value>(),
+                                item_in_list(&list, &i_as()),
+                            ))
+                            .next(replace_in_list(
+                                &list,
+                                &i_as(),
+                                item_in_list(&list, &j_as()),
+                            ))
+                            .next(replace_in_list(&list, &j_as(), &text_as()))
+                            .next(change_var_by(&i(), -1)),
+                    ),
+                ));
+                target.add_helper(block.proccode(), def)
+            }
+            Helper::Bitwise(op) => bits::install(op, target),
+        }
+    }
+}
+
+/// Definition of [`Stdlib::to_upper`] and [`Stdlib::to_lower`]
+fn change_case<A>(f: &Function<A, Text>, to: &str) -> HatBlock {
+    let ret = f.return_var();
+    let text = || arg::<Text>("text");
+    // equals ignores case so this finds the letter in either case
+    let find_letter = set_var_to(&j(), 1).next(repeat_until(
+        equals(letter_of(&j_as(), ALPHABET_LOWER), &text_as()),
+        Some(change_var_by(&j(), 1)),
+    ));
+    f.define()
+        .next(set_var_to(ret, ""))
+        .next(set_var_to(&i(), 1))
+        .next(repeat(
+            length_of(text()),
+            Some(
+                set_var_to(&text_temp(), letter_of(&i_as(), text()))
+                    .next(if_(
+                        contains(ALPHABET_LOWER, &text_temp()),
+                        find_letter.next(set_var_to(&text_temp(), letter_of(&j_as(), to))),
+                    ))
+                    .next(set_var_to(ret, join(ret, &text_temp())))
+                    .next(change_var_by(&i(), 1)),
+            ),
+        ))
+}
+
+fn min_fn() -> Function<(Value, Value), Number> {
+    function(
+        CustomBlockBuilder::new()
+            .text("min of")
+            .arg::<Value>("a")
+            .text("and")
+            .arg::<Value>("b"),
+    )
+}
+
+fn max_fn() -> Function<(Value, Value), Number> {
+    function(
+        CustomBlockBuilder::new()
+            .text("max of")
+            .arg::<Value>("a")
+            .text("and")
+            .arg::<Value>("b"),
+    )
+}
+
+fn clamp_fn() -> Function<(Value, Value, Value), Number> {
+    function(
+        CustomBlockBuilder::new()
+            .text("clamp")
+            .arg::<Value>("x")
+            .text("between")
+            .arg::<Value>("min")
+            .text("and")
+            .arg::<Value>("max"),
+    )
+}
+
+fn pow_fn() -> Function<(Value, Value), Number> {
+    function(
+        CustomBlockBuilder::new()
+            .arg::<Value>("base")
+            .text("to the power of")
+            .arg::<Value>("exponent"),
+    )
+}
+
+fn atan2_fn() -> Function<(Value, Value), Number> {
+    function(
+        CustomBlockBuilder::new()
+            .text("atan2 of y")
+            .arg::<Value>("y")
+            .text("x")
+            .arg::<Value>("x"),
+    )
+}
+
+fn lerp_fn() -> Function<(Value, Value, Value), Number> {
+    function(
+        CustomBlockBuilder::new()
+            .text("lerp from")
+            .arg::<Value>("a")
+            .text("to")
+            .arg::<Value>("b")
+            .text("by")
+            .arg::<Value>("t"),
+    )
+}
+
+fn sign_fn() -> Function<(Value,), Number> {
+    function(CustomBlockBuilder::new().text("sign of").arg::<Value>("x"))
+}
+
+fn substring_fn() -> Function<(Value, Value, Value), Text> {
+    function(
+        CustomBlockBuilder::new()
+            .text("letters of")
+            .arg::<Value>("text")
+            .text("from")
+            .arg::<Value>("from")
+            .text("to")
+            .arg::<Value>("to"),
+    )
+}
+
+fn index_of_fn() -> Function<(Value, Value), Integer> {
+    function(
+        CustomBlockBuilder::new()
+            .text("index of")
+            .arg::<Value>("part")
+            .text("in")
+            .arg::<Value>("text"),
+    )
+}
+
+fn to_upper_fn() -> Function<(Value,), Text> {
+    function(
+        CustomBlockBuilder::new()
+            .text("uppercase of")
+            .arg::<Value>("text"),
+    )
+}
+
+fn to_lower_fn() -> Function<(Value,), Text> {
+    function(
+        CustomBlockBuilder::new()
+            .text("lowercase of")
+            .arg::<Value>("text"),
+    )
+}
+
+fn repeat_string_fn() -> Function<(Value, Value), Text> {
+    function(
+        CustomBlockBuilder::new()
+            .text("repeat text")
+            .arg::<Value>("text")
+            .arg::<Value>("times")
+            .text("times"),
+    )
+}
+
+fn reverse_fn() -> Function<(Value,), Text> {
+    function(
+        CustomBlockBuilder::new()
+            .text("reverse of")
+            .arg::<Value>("text"),
+    )
+}
+
+fn split_block(list: &ListHandle<Value>) -> CustomBlock<(Value, Value)> {
+    CustomBlockBuilder::new()
+        .text("split")
+        .arg::<Value>("text")
+        .text("by")
+        .arg::<Value>("separator")
+        .text(format!("into {}", list.name()))
+        .warp(true)
+        .build()
+}
+
+fn sort_block(list: &ListHandle<Value>) -> CustomBlock<()> {
+    CustomBlockBuilder::new()
+        .text(format!("sort {}", list.name()))
+        .warp(true)
+        .build()
+}
+
+fn shuffle_block(list: &ListHandle<Value>) -> CustomBlock<()> {
+    CustomBlockBuilder::new()
+        .text(format!("shuffle {}", list.name()))
+        .warp(true)
+        .build()
+}
+
+/// Function of a helper, the return variable is declared when it's installed
+fn function<A, T>(custom_block: CustomBlockBuilder<A>) -> Function<A, T> {
+    let custom_block = custom_block.warp(true).build();
+    let ret = VarHandle::new(return_var_name(custom_block.proccode()), Scope::Sprite);
+    Function::new(custom_block, ret)
+}
+
+fn add_function<A, T, E>(
+    target: &mut TargetBuilder,
+    function: &Function<A, T>,
+    definition: TypedStackBuilder<UnstackableSide, E>,
+) {
+    target.add_variable_once(function.return_var().name(), "");
+    target.add_helper(function.custom_block().proccode(), definition);
+}
+
+const I: &str = "(stdlib i)";
+const J: &str = "(stdlib j)";
+const TEXT: &str = "(stdlib text)";
+
+fn i() -> VarHandle<Number> {
+    i_as()
+}
+
+fn j() -> VarHandle<Number> {
+    j_as()
+}
+
+fn text_temp() -> VarHandle<Text> {
+    text_as()
+}
+
+/// Temp variables typed as they're used, Scratch converts them
+fn i_as<T>() -> VarHandle<T> {
+    VarHandle::new(I.to_owned(), Scope::Sprite)
+}
+
+fn j_as<T>() -> VarHandle<T> {
+    VarHandle::new(J.to_owned(), Scope::Sprite)
+}
+
+fn text_as<T>() -> VarHandle<T> {
+    VarHandle::new(TEXT.to_owned(), Scope::Sprite)
+}
+
+fn any_list<T>(list: &ListHandle<T>) -> ListHandle<Value> {
+    list.retype()
+}
+
+/// Argument of the helper as `T`, Scratch converts it to what it's used as
+fn arg<T>(name: &str) -> JustReporter<T> {
+    retype(arg_reporter::<Value>(name))
+}
+
+/// Scratch converts values to what they're used as
+fn retype<T, U>(reporter: JustReporter<T>) -> JustReporter<U> {
+    Reporter::new(reporter.0)
+}
+
+#[cfg(test)]
+mod tests;
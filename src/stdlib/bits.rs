@@ -0,0 +1,209 @@
+//! Integer and bitwise math
+//!
+//! # Explanation
+//!
+//! Scratch only has float math, these are made from `floor`, `mod` and friends.
+//!
+//! Bitwise and 32-bit functions work on unsigned 32-bit integers like `>>> 0` in JavaScript.
+//! They report integers from `0` to `2^32 - 1` and other integers are wrapped into that first,
+//! so `-1` is the same as `2^32 - 1`.
+//! Numbers with a fraction are rounded down.
+//!
+//! Most are reporters made of operator blocks.
+//! Bitwise and, or and xor go through every bit so they are [`Stdlib`] helpers instead.
+//!
+//! # Usage
+//!
+//! ```
+//! # use sb_scratchy::blocks::*;
+//! # use sb_scratchy::project::{SpriteBuilder, TargetBuilder};
+//! # use sb_scratchy::scripting::arg::Integer;
+//! # use sb_scratchy::stdlib::{bits::{shl, shr}, Stdlib};
+//! let mut cat = SpriteBuilder::new(TargetBuilder::new("Cat"));
+//! let state = cat.declare_var::<Integer>("state", 2463534242.);
+//!
+//! // xorshift32
+//! let mut std = Stdlib::new();
+//! let (a, x) = std.bit_xor(&state, shl(&state, 13));
+//! let (b, y) = std.bit_xor(&state, shr(&state, 17));
+//! let (c, z) = std.bit_xor(&state, shl(&state, 5));
+//! let next = a
+//!     .next(set_var_to(&state, x))
+//!     .next(b)
+//!     .next(set_var_to(&state, y))
+//!     .next(c)
+//!     .next(set_var_to(&state, z));
+//!
+//! let cat = std.install(cat.add_script(when_flag_clicked().next(next)));
+//! ```
+
+use sb_itchy::blocks;
+
+use crate::blocks::{math_op, repeat, round, set_var_to, var};
+use crate::project::TargetBuilder;
+use crate::scripting::{
+    arg::*,
+    custom_block::{value_call_input, CustomBlockBuilder, Function},
+    menu::MathOp,
+    stack::*,
+};
+
+use super::{add_function, arg, function, i, j, retype, Helper, Stdlib};
+
+const TWO_POW_16: u64 = 1 << 16;
+const TWO_POW_31: u64 = 1 << 31;
+const TWO_POW_32: u64 = 1 << 32;
+
+/// `a / b` rounded down
+pub fn int_div(a: impl IntoInput<Integer>, b: impl IntoInput<Integer>) -> JustReporter<Integer> {
+    retype(floor(number(a) / number(b)))
+}
+
+/// `x` wrapped into 32 bits
+pub fn wrap(x: impl IntoInput<Integer>) -> JustReporter<Integer> {
+    retype(number(x) % TWO_POW_32)
+}
+
+/// `a + b` wrapped into 32 bits
+pub fn wrapping_add(
+    a: impl IntoInput<Integer>,
+    b: impl IntoInput<Integer>,
+) -> JustReporter<Integer> {
+    retype((number(a) + number(b)) % TWO_POW_32)
+}
+
+/// `a * b` wrapped into 32 bits
+pub fn wrapping_mul(
+    a: impl IntoInput<Integer>,
+    b: impl IntoInput<Integer>,
+) -> JustReporter<Integer> {
+    // a * b can be up to 2^64 which floats can't keep exactly,
+    // so a is split in 16-bit halves that are each multiplied by b
+    let a = number(a);
+    let b = number(b) % TWO_POW_32;
+    let high = floor(a.clone() / TWO_POW_16) % TWO_POW_16;
+    let low = a % TWO_POW_16;
+    let high_product = (high * b.clone()) % TWO_POW_16 * TWO_POW_16;
+    retype((high_product + low * b) % TWO_POW_32)
+}
+
+/// `x << n` wrapped into 32 bits, `n` is from `0` to `32`
+pub fn shl(x: impl IntoInput<Integer>, n: impl IntoInput<Integer>) -> JustReporter<Integer> {
+    // bits that would be shifted out are dropped first so it doesn't get past 2^32
+    let pow = pow2(n);
+    retype((number(x) % (TWO_POW_32 / pow.clone())) * pow)
+}
+
+/// `x >>> n`, `n` is from `0` to `32`
+pub fn shr(x: impl IntoInput<Integer>, n: impl IntoInput<Integer>) -> JustReporter<Integer> {
+    retype(floor((number(x) % TWO_POW_32) / pow2(n)))
+}
+
+/// Operation of a bitwise helper
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum BitOp {
+    And,
+    Or,
+    Xor,
+}
+
+impl Stdlib {
+    /// `a & b`
+    pub fn bit_and(
+        &mut self,
+        a: impl IntoInput<Integer>,
+        b: impl IntoInput<Integer>,
+    ) -> (StackBlock, JustReporter<Integer>) {
+        self.bitwise(BitOp::And, a, b)
+    }
+
+    /// `a | b`
+    pub fn bit_or(
+        &mut self,
+        a: impl IntoInput<Integer>,
+        b: impl IntoInput<Integer>,
+    ) -> (StackBlock, JustReporter<Integer>) {
+        self.bitwise(BitOp::Or, a, b)
+    }
+
+    /// `a ^ b`
+    pub fn bit_xor(
+        &mut self,
+        a: impl IntoInput<Integer>,
+        b: impl IntoInput<Integer>,
+    ) -> (StackBlock, JustReporter<Integer>) {
+        self.bitwise(BitOp::Xor, a, b)
+    }
+
+    fn bitwise(
+        &mut self,
+        op: BitOp,
+        a: impl IntoInput<Integer>,
+        b: impl IntoInput<Integer>,
+    ) -> (StackBlock, JustReporter<Integer>) {
+        self.use_(Helper::Bitwise(op));
+        bitwise_fn(op).call_with_inputs(vec![value_call_input(a), value_call_input(b)])
+    }
+}
+
+/// Add definition of a bitwise helper
+pub(super) fn install(op: BitOp, target: &mut TargetBuilder) {
+    let f = bitwise_fn(op);
+    let ret = VarHandle::<Number>::new(f.return_var().name().to_owned(), Scope::Sprite);
+    let (a_bit, b_bit) = (|| var(&i()) % 2, || var(&j()) % 2);
+    let bit = match op {
+        BitOp::And => a_bit() * b_bit(),
+        BitOp::Or => a_bit() + b_bit() - a_bit() * b_bit(),
+        BitOp::Xor => (a_bit() + b_bit()) % 2,
+    };
+    // from the lowest bit, each bit is added as the highest bit
+    // and moves down as the next ones are added
+    let def = f
+        .define()
+        .next(set_var_to(&ret, 0))
+        .next(set_var_to(&i(), floor(arg("a")) % TWO_POW_32))
+        .next(set_var_to(&j(), floor(arg("b")) % TWO_POW_32))
+        .next(repeat(
+            32u32,
+            Some(
+                set_var_to(&ret, var(&ret) / 2 + bit * TWO_POW_31)
+                    .next(set_var_to(&i(), floor(var(&i()) / 2)))
+                    .next(set_var_to(&j(), floor(var(&j()) / 2))),
+            ),
+        ));
+    add_function(target, &f, def)
+}
+
+fn bitwise_fn(op: BitOp) -> Function<(Value, Value), Integer> {
+    let text = match op {
+        BitOp::And => "bitwise and",
+        BitOp::Or => "bitwise or",
+        BitOp::Xor => "bitwise xor",
+    };
+    function(
+        CustomBlockBuilder::new()
+            .arg::<Value>("a")
+            .text(text)
+            .arg::<Value>("b"),
+    )
+}
+
+/// Integer input as a number, rounded down
+fn number(x: impl IntoInput<Integer>) -> JustReporter<Number> {
+    shaped(blocks::math_op(
+        MathOp::Floor.into_field(),
+        x.into_input_over(<Number as ShadowInput>::shadow("")),
+    ))
+}
+
+/// `2^n`, rounded as `e ^` is a little off
+fn pow2(n: impl IntoInput<Integer>) -> JustReporter<Number> {
+    round(math_op(MathOp::EPow, number(n) * std::f64::consts::LN_2))
+}
+
+fn floor(x: JustReporter<Number>) -> JustReporter<Number> {
+    math_op(MathOp::Floor, x)
+}
+
+#[cfg(test)]
+mod tests;
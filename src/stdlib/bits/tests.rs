@@ -0,0 +1,161 @@
+//! Running integer and bitwise math in the interpreter
+
+use super::*;
+use crate::blocks::{add_to_list, div, when_flag_clicked};
+use crate::interpreter::{start, Interpreter};
+use crate::project::{SpriteBuilder, StageBuilder, TargetBuilder};
+
+const A: u32 = 0xDEAD_BEEF;
+const B: u32 = 0x9E37_79B9;
+
+/// Run a script of `"Cat"` and get it to read variables from
+fn run(sprite: SpriteBuilder, std: Stdlib, script: StackBlock) -> Interpreter {
+    let sprite = std.install(sprite.add_script(when_flag_clicked().next(script)));
+    let mut interpreter = start(StageBuilder::default(), sprite);
+    assert!(interpreter.run_until_idle(100));
+    interpreter
+}
+
+/// Check reporters against what they should be
+fn check(cases: Vec<(JustReporter<Integer>, f64)>) {
+    let mut cat = SpriteBuilder::new(TargetBuilder::new("Cat"));
+    let out = cat.declare_list::<Integer, f64>("out", []);
+    let (reporters, expected): (Vec<_>, Vec<_>) = cases.into_iter().unzip();
+    let script = reporters
+        .into_iter()
+        .map(|r| add_to_list(&out, r))
+        .reduce(StackBlock::next)
+        .unwrap();
+    let interpreter = run(cat, Stdlib::new(), script);
+    let got: Vec<f64> = interpreter
+        .list("Cat", "out")
+        .unwrap()
+        .iter()
+        .map(|v| v.to_number())
+        .collect();
+    for (i, (got, expected)) in got.into_iter().zip(expected).enumerate() {
+        assert_eq!(got, expected, "case {}", i);
+    }
+}
+
+#[test]
+fn floored_division_and_wrap() {
+    // floored like `floor` and `mod` in Scratch, not truncated like `/` and `%` in Rust
+    check(vec![
+        (int_div(7, 2), 3.),
+        (int_div(-7, 2), -4.),
+        (int_div(7, -2), -4.),
+        (wrap(-1), u32::MAX as f64),
+        (wrap(-5), (u32::MAX - 4) as f64),
+        (wrap(1u64 << 32), 0.),
+        (wrap(retype(div(37, 10))), 3.),
+        (wrap(retype(div(-1, 2))), u32::MAX as f64),
+    ]);
+}
+
+#[test]
+fn wrapping_math() {
+    check(vec![
+        (wrapping_add(A, B), A.wrapping_add(B) as f64),
+        (wrapping_add(-1, 1), 0.),
+        (wrapping_mul(A, B), A.wrapping_mul(B) as f64),
+        (wrapping_mul(u32::MAX, u32::MAX), 1.),
+        (wrapping_mul(-1, B), B.wrapping_neg() as f64),
+        (wrapping_mul(B, -1), B.wrapping_neg() as f64),
+        (wrapping_mul(0x1_0000, 0x1_0000), 0.),
+    ]);
+}
+
+#[test]
+fn shifts() {
+    // bits shifted out of `shl` are dropped before multiplying so it stays exact
+    check(vec![
+        (shl(A, 13), (A << 13) as f64),
+        (shl(A, 0), A as f64),
+        (shl(A, 31), (A << 31) as f64),
+        (shl(u32::MAX, 31), 0x8000_0000u32 as f64),
+        (shl(0x8000_0001u32, 1), 2.),
+        (shl(-1, 4), (u32::MAX << 4) as f64),
+        (shr(A, 17), (A >> 17) as f64),
+        (shr(A, 31), (A >> 31) as f64),
+        (shr(-1, 28), 15.),
+        (shr(A, 32), 0.),
+    ]);
+}
+
+#[test]
+fn bitwise() {
+    let mut cat = SpriteBuilder::new(TargetBuilder::new("Cat"));
+    let out = cat.declare_list::<Integer, f64>("out", []);
+    let mut std = Stdlib::new();
+    // the highest and lowest bits check that all 32 bits go through the loop
+    let cases: Vec<(StackBlock, JustReporter<Integer>, u32)> = vec![
+        tuple(std.bit_and(A, B), A & B),
+        tuple(std.bit_or(A, B), A | B),
+        tuple(std.bit_xor(A, B), A ^ B),
+        tuple(std.bit_xor(-1, B), !B),
+        tuple(std.bit_and(0x8000_0001u32, u32::MAX), 0x8000_0001),
+        tuple(std.bit_or(0x8000_0000u32, 1), 0x8000_0001),
+        tuple(std.bit_and(A, 0), 0),
+    ];
+    let expected: Vec<f64> = cases.iter().map(|(_, _, e)| *e as f64).collect();
+    let script = cases
+        .into_iter()
+        .map(|(call, ret, _)| call.next(add_to_list(&out, ret)))
+        .reduce(StackBlock::next)
+        .unwrap();
+    let interpreter = run(cat, std, script);
+    let got: Vec<f64> = interpreter
+        .list("Cat", "out")
+        .unwrap()
+        .iter()
+        .map(|v| v.to_number())
+        .collect();
+    assert_eq!(got, expected);
+}
+
+fn tuple(
+    (call, ret): (StackBlock, JustReporter<Integer>),
+    expected: u32,
+) -> (StackBlock, JustReporter<Integer>, u32) {
+    (call, ret, expected)
+}
+
+#[test]
+fn xorshift32() {
+    // same as the example in the module documentation
+    let mut cat = SpriteBuilder::new(TargetBuilder::new("Cat"));
+    let state = cat.declare_var::<Integer>("state", 2463534242.);
+    let states = cat.declare_list::<Integer, f64>("states", []);
+    let mut std = Stdlib::new();
+    let mut next = || {
+        let (a, x) = std.bit_xor(&state, shl(&state, 13));
+        let (b, y) = std.bit_xor(&state, shr(&state, 17));
+        let (c, z) = std.bit_xor(&state, shl(&state, 5));
+        a.next(set_var_to(&state, x))
+            .next(b)
+            .next(set_var_to(&state, y))
+            .next(c)
+            .next(set_var_to(&state, z))
+            .next(add_to_list(&states, var(&state)))
+    };
+    let script = (0..5).map(|_| next()).reduce(StackBlock::next).unwrap();
+    let interpreter = run(cat, std, script);
+    let got: Vec<f64> = interpreter
+        .list("Cat", "states")
+        .unwrap()
+        .iter()
+        .map(|v| v.to_number())
+        .collect();
+    // from Marsaglia's "Xorshift RNGs"
+    assert_eq!(
+        got,
+        [
+            723471715.,
+            2497366906.,
+            2064144800.,
+            2008045182.,
+            3532304609.
+        ]
+    );
+}
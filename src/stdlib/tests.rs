@@ -0,0 +1,218 @@
+//! Running helpers in the interpreter
+
+use super::*;
+use crate::interpreter::{start, Interpreter, Value as RunValue};
+use crate::project::{ProjectBuilder, SpriteBuilder, StageBuilder, TargetBuilder};
+
+/// Sprite `"Cat"` with helpers used by a script
+struct Cat {
+    sprite: SpriteBuilder,
+    std: Stdlib,
+    script: StackBlock,
+    /// Variables that are set to what a helper returns and what they should be
+    expected: Vec<(String, RunValue)>,
+}
+
+impl Cat {
+    fn new() -> Cat {
+        Cat {
+            sprite: SpriteBuilder::new(TargetBuilder::new("Cat")),
+            std: Stdlib::new(),
+            script: show(),
+            expected: vec![],
+        }
+    }
+
+    /// Call a helper and check what it returns
+    fn check<T>(
+        &mut self,
+        helper: impl FnOnce(&mut Stdlib) -> (StackBlock, JustReporter<T>),
+        expected: impl Into<RunValue>,
+    ) {
+        let name = format!("out {}", self.expected.len());
+        let out = self.sprite.declare_var::<Value>(name.clone(), "");
+        let (call, ret) = helper(&mut self.std);
+        let script = std::mem::replace(&mut self.script, show());
+        self.script = script
+            .next(call)
+            .next(set_var_to(&out, retype::<T, Value>(ret)));
+        self.expected.push((name, expected.into()));
+    }
+
+    fn run(self) -> Interpreter {
+        let Cat {
+            sprite,
+            std,
+            script,
+            expected,
+        } = self;
+        let sprite = std.install(sprite.add_script(when_flag_clicked().next(script)));
+        let mut interpreter = start(StageBuilder::default(), sprite);
+        assert!(interpreter.run_until_idle(100));
+        for (name, expected) in expected {
+            let got = interpreter.variable("Cat", &name).unwrap();
+            assert_eq!(got.to_text(), expected.to_text(), "{}", name);
+        }
+        interpreter
+    }
+}
+
+fn list_of(interpreter: &Interpreter, name: &str) -> Vec<String> {
+    let list = interpreter.list("Cat", name).unwrap();
+    list.iter().map(RunValue::to_text).collect()
+}
+
+#[test]
+fn math() {
+    let mut cat = Cat::new();
+    cat.check(|std| std.min(3, -2), -2.);
+    cat.check(|std| std.max(3, -2), 3.);
+    cat.check(|std| std.clamp(150, -100, 100), 100.);
+    cat.check(|std| std.clamp(-150, -100, 100), -100.);
+    cat.check(|std| std.clamp(7, -100, 100), 7.);
+    cat.check(|std| std.atan2(1, -1), 135.);
+    cat.check(|std| std.atan2(-1, -1), -135.);
+    cat.check(|std| std.atan2(-5, 0), -90.);
+    cat.check(|std| std.lerp(10, 20, 0.25), 12.5);
+    cat.check(|std| std.sign(-3), -1.);
+    cat.check(|std| std.sign(0), 0.);
+    cat.check(|std| std.sign(0.5), 1.);
+    cat.run();
+}
+
+#[test]
+fn pow() {
+    // same as Math.pow in JavaScript
+    let mut cat = Cat::new();
+    cat.check(|std| std.pow(2, 10), 1024.);
+    cat.check(|std| std.pow(3, 33), 5559060566555523.);
+    cat.check(|std| std.pow(-2, 3), -8.);
+    cat.check(|std| std.pow(-2, 4), 16.);
+    cat.check(|std| std.pow(2, -2), 0.25);
+    cat.check(|std| std.pow(7, 0), 1.);
+    cat.check(|std| std.pow(0, -1), f64::INFINITY);
+    cat.check(|std| std.pow(4, 0.5), 2.);
+    // big exponents don't repeat that many times
+    cat.check(|std| std.pow(2, 1e9), f64::INFINITY);
+    cat.check(|std| std.pow(-1, 1e15 + 1.), -1.);
+    cat.check(|std| std.pow(0.5, div(1, 0)), 0.);
+    cat.check(|std| std.pow(2, div(1, 0)), f64::INFINITY);
+    let interpreter = cat.run();
+    assert!(interpreter.frame() <= 1);
+}
+
+#[test]
+fn text() {
+    let mut cat = Cat::new();
+    cat.check(|std| std.substring("hello world", 2, 4), "ell");
+    cat.check(|std| std.index_of("hello world", "wor"), 7.);
+    cat.check(|std| std.index_of("hello world", "o"), 5.);
+    cat.check(|std| std.index_of("hello world", "WOR"), 7.);
+    cat.check(|std| std.index_of("hello world", "xyz"), 0.);
+    cat.check(|std| std.index_of("abc", "c"), 3.);
+    cat.check(|std| std.index_of("ab", "abc"), 0.);
+    cat.check(|std| std.to_upper("Hello, World! ß"), "HELLO, WORLD! ß");
+    cat.check(|std| std.to_lower("Hello, World! 1"), "hello, world! 1");
+    cat.check(|std| std.to_upper(""), "");
+    cat.check(|std| std.repeat_string("ab", 3u32), "ababab");
+    cat.check(|std| std.reverse("abc"), "cba");
+    cat.run();
+}
+
+#[test]
+fn lists() {
+    let mut cat = Cat::new();
+    let words = cat.sprite.declare_list::<Text, &str>("words", ["old"]);
+    let letters = cat.sprite.declare_list::<Text, &str>("letters", []);
+    let numbers = cat
+        .sprite
+        .declare_list::<Number, f64>("numbers", [5., 3., 9., 1., 3., 7., 10.]);
+    let mixed = cat
+        .sprite
+        .declare_list::<Value, &str>("mixed", ["b", "10", "A", "9", "a"]);
+    let shuffled = cat
+        .sprite
+        .declare_list::<Number, f64>("shuffled", (1..=20).map(f64::from));
+    cat.script = show()
+        .next(cat.std.split("a,bb,,c", ",", &words))
+        .next(cat.std.split("xy", "", &letters))
+        .next(cat.std.sort(&numbers))
+        .next(cat.std.sort(&mixed))
+        .next(cat.std.shuffle(&shuffled));
+    let interpreter = cat.run();
+    assert_eq!(list_of(&interpreter, "words"), ["a", "bb", "", "c"]);
+    assert_eq!(list_of(&interpreter, "letters"), ["xy"]);
+    assert_eq!(
+        list_of(&interpreter, "numbers"),
+        ["1", "3", "3", "5", "7", "9", "10"]
+    );
+    // compared like `<`, numbers as numbers and text ignoring case
+    let mixed = list_of(&interpreter, "mixed");
+    assert_eq!(mixed[..2], ["9", "10"]);
+    assert_eq!(mixed[4], "b");
+    let mut shuffled: Vec<f64> = interpreter
+        .list("Cat", "shuffled")
+        .unwrap()
+        .iter()
+        .map(RunValue::to_number)
+        .collect();
+    assert_ne!(shuffled, (1..=20).map(f64::from).collect::<Vec<_>>());
+    shuffled.sort_by(f64::total_cmp);
+    assert_eq!(shuffled, (1..=20).map(f64::from).collect::<Vec<_>>());
+}
+
+#[test]
+fn installed_twice() {
+    let mut cat = SpriteBuilder::new(TargetBuilder::new("Cat"));
+    let out = cat.declare_var::<Text>("out", "");
+    let words = cat.declare_list::<Text, &str>("words", []);
+    let mut first = Stdlib::new();
+    let mut second = Stdlib::new();
+    let (upper, shout) = first.to_upper("hi");
+    let (reverse, back) = second.reverse(shout);
+    let (upper_again, shout_again) = second.to_upper(back);
+    let script = when_flag_clicked()
+        .next(upper)
+        .next(reverse)
+        .next(upper_again)
+        .next(set_var_to(&out, shout_again))
+        .next(first.split("a b", " ", &words))
+        .next(second.split("c d e", " ", &words));
+    let cat = second.install(first.install(cat.add_script(script)));
+    // each helper is defined once
+    let project = ProjectBuilder::new()
+        .add_sprite(cat.clone())
+        .build()
+        .unwrap();
+    let json = project.to_json().unwrap();
+    assert_eq!(json.matches("procedures_definition").count(), 3);
+
+    let mut interpreter = start(StageBuilder::default(), cat);
+    assert!(interpreter.run_until_idle(100));
+    let out = interpreter.variable("Cat", "out").unwrap();
+    assert_eq!(out.to_text(), "IH");
+    assert_eq!(list_of(&interpreter, "words"), ["c", "d", "e"]);
+}
+
+#[test]
+fn installed_on_stage() {
+    let mut stage = StageBuilder::default();
+    let out = stage.declare_var::<Text>("out", "");
+    let mut std = Stdlib::new();
+    let (upper, shout) = std.to_upper("hi");
+    let (clamp, x) = std.clamp(150, -100, 100);
+    let stage = std.install(
+        stage.add_script(
+            when_flag_clicked()
+                .next(upper)
+                .next(clamp)
+                .next(set_var_to(&out, join(shout, retype::<_, Text>(x)))),
+        ),
+    );
+    let cat = SpriteBuilder::new(TargetBuilder::new("Cat"));
+
+    let mut interpreter = start(stage, cat);
+    assert!(interpreter.run_until_idle(100));
+    let out = interpreter.variable("Stage", "out").unwrap();
+    assert_eq!(out.to_text(), "HI100");
+}
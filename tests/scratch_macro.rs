@@ -0,0 +1,7 @@
+//! Errors of `scratch!` for blocks that can't be stacked
+
+#[test]
+fn ui() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/*.rs");
+}
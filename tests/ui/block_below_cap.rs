@@ -0,0 +1,12 @@
+use sb_scratchy::blocks::*;
+use sb_scratchy::scratch;
+
+fn main() {
+    let _script = scratch! {
+        when_flag_clicked;
+        loop {
+            move_steps(10)
+        }
+        say("unreachable");
+    };
+}
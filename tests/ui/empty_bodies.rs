@@ -0,0 +1,17 @@
+use sb_scratchy::blocks::*;
+use sb_scratchy::scratch;
+
+fn main() {
+    let _empty_if = scratch! {
+        when_flag_clicked;
+        if mouse_down() {} else {
+            move_steps(10)
+        }
+    };
+    let _empty_else = scratch! {
+        when_flag_clicked;
+        if mouse_down() {
+            move_steps(10)
+        } else {}
+    };
+}
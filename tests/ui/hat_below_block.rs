@@ -0,0 +1,9 @@
+use sb_scratchy::blocks::*;
+use sb_scratchy::scratch;
+
+fn main() {
+    let _script = scratch! {
+        move_steps(10);
+        when_flag_clicked;
+    };
+}
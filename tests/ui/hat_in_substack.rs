@@ -0,0 +1,11 @@
+use sb_scratchy::blocks::*;
+use sb_scratchy::scratch;
+
+fn main() {
+    let _script = scratch! {
+        when_flag_clicked;
+        repeat 10u32 {
+            when_this_sprite_clicked
+        }
+    };
+}